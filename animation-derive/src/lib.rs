@@ -0,0 +1,149 @@
+//! `#[derive(Interpolatable)]` for structs and tuple structs.
+//!
+//! Each field is interpolated with its own `Interpolatable` implementation, and the distance is the
+//! sum of the distances of the fields. Fields can be configured with `#[interpolatable(...)]`:
+//!
+//! - `snap`: The field is not interpolated, it jumps from `from` to `to` halfway through. Useful for
+//!   enums and booleans. Snapped fields don't contribute to the distance.
+//! - `with = path`: The field is interpolated with `path::interpolate(from, to, t)` and measured
+//!   with `path::distance(from, to)`, for example `massive_animation::quaternion`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Index, Member, Path, Type, parse_macro_input};
+
+#[proc_macro_derive(Interpolatable, attributes(interpolatable))]
+pub fn derive_interpolatable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Strategy {
+    Interpolate,
+    Snap,
+    With(Path),
+}
+
+struct Field {
+    member: Member,
+    ty: Type,
+    strategy: Strategy,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Interpolatable can only be derived for structs",
+        ));
+    };
+
+    let fields = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index::from(index)),
+            };
+            Ok(Field {
+                member,
+                ty: field.ty.clone(),
+                strategy: strategy(field)?,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let interpolated = fields.iter().map(|field| {
+        let member = &field.member;
+        let value = match &field.strategy {
+            Strategy::Interpolate => quote! {
+                ::massive_animation::Interpolatable::interpolate(&from.#member, &to.#member, t)
+            },
+            Strategy::Snap => quote! {
+                ::core::clone::Clone::clone(if t < 0.5 { &from.#member } else { &to.#member })
+            },
+            Strategy::With(path) => quote! {
+                #path::interpolate(&from.#member, &to.#member, t)
+            },
+        };
+        quote! { #member: #value }
+    });
+
+    let distances = fields.iter().filter_map(|field| {
+        let member = &field.member;
+        match &field.strategy {
+            Strategy::Interpolate => Some(quote! {
+                ::massive_animation::Interpolatable::distance(&from.#member, &to.#member)
+            }),
+            Strategy::Snap => None,
+            Strategy::With(path) => Some(quote! {
+                #path::distance(&from.#member, &to.#member)
+            }),
+        }
+    });
+
+    // Generic field types need to be bounded, concrete ones are checked where they are used.
+    let mut generics = input.generics.clone();
+    if !input.generics.params.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for field in &fields {
+            if let Strategy::Interpolate = field.strategy {
+                let ty = &field.ty;
+                where_clause
+                    .predicates
+                    .push(syn::parse_quote!(#ty: ::massive_animation::Interpolatable));
+            }
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let constructor = match &data.fields {
+        // `Self { 0: .. }` constructs tuple structs, too.
+        Fields::Named(_) | Fields::Unnamed(_) => quote! { Self { #(#interpolated,)* } },
+        Fields::Unit => quote! { Self },
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::massive_animation::Interpolatable for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn interpolate(from: &Self, to: &Self, t: f64) -> Self {
+                #constructor
+            }
+
+            #[allow(unused_variables)]
+            fn distance(from: &Self, to: &Self) -> f64 {
+                0.0 #(+ #distances)*
+            }
+        }
+    })
+}
+
+fn strategy(field: &syn::Field) -> syn::Result<Strategy> {
+    let mut strategy = Strategy::Interpolate;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("interpolatable") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if !matches!(strategy, Strategy::Interpolate) {
+                return Err(meta.error("only one of `snap` and `with` can be specified"));
+            }
+            if meta.path.is_ident("snap") {
+                strategy = Strategy::Snap;
+                Ok(())
+            } else if meta.path.is_ident("with") {
+                strategy = Strategy::With(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `snap` or `with = path`"))
+            }
+        })?;
+    }
+    Ok(strategy)
+}
@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use crate::time::Instant;
-use crate::{BlendedAnimation, Interpolatable, Interpolation};
+use crate::{AnimationGroupHandle, BlendedAnimation, Interpolatable, Interpolation, Timeline};
 
 #[derive(Debug, Copy, Clone)]
 pub enum AnimationProgress {
@@ -18,6 +18,11 @@ impl From<Instant> for AnimationProgress {
 
 pub trait AnimationAllocator {
     fn allocate_animation_time(&mut self, duration: Duration) -> Instant;
+
+    /// The group the allocated animations belong to, see [`crate::AnimationGroup`].
+    fn group(&self) -> Option<&AnimationGroupHandle> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -72,8 +77,39 @@ impl<T: Send + Interpolatable> Animated<T> {
     {
         let instant = context.allocate_animation_time(duration);
         let value = self.value.clone();
-        self.animation
-            .animate_to(value, instant, target_value, duration, interpolation);
+        self.animation.animate_to(
+            value,
+            instant,
+            target_value,
+            duration,
+            interpolation,
+            context.group().cloned(),
+        );
+    }
+
+    /// Animate through the keyframes of `timeline`, starting from the current value.
+    pub fn play(&mut self, context: &mut dyn AnimationAllocator, timeline: &Timeline<T>)
+    where
+        T: 'static,
+    {
+        if timeline.keyframes().is_empty() {
+            return;
+        }
+
+        let start = context.allocate_animation_time(timeline.duration());
+        let group = context.group().cloned();
+        let mut from = self.value.clone();
+        for (offset, duration, keyframe) in timeline.segments() {
+            self.animation.animate_to(
+                from,
+                start + offset,
+                keyframe.value.clone(),
+                duration,
+                keyframe.interpolation,
+                group.clone(),
+            );
+            from = keyframe.value.clone();
+        }
     }
 
     pub fn snap(&mut self, value: T) {
@@ -108,6 +144,9 @@ impl<T: Send + Interpolatable> Animated<T> {
             if let Some(new_value) = self.animation.proceed(instant) {
                 self.value = new_value;
             }
+            if self.animation.is_cancelled() {
+                self.animation.end();
+            }
         }
     }
 
@@ -0,0 +1,271 @@
+//! Declarative choreography of animations of multiple values.
+//!
+//! A group is a tree of tracks that are combined in sequence, in parallel, or staggered. When it is
+//! played, all its animations are allocated at once, each at its offset from the start of the
+//! group. All of them can be cancelled together with the handle returned.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::time::Instant;
+use crate::{Animated, AnimationAllocator, Interpolatable, Interpolation, Timeline};
+
+/// A tree of animations that are played together.
+pub struct AnimationGroup<'a> {
+    kind: GroupKind<'a>,
+}
+
+/// Starts the animations of a track.
+type PlayTrack<'a> = Box<dyn FnOnce(&mut dyn AnimationAllocator) + 'a>;
+
+enum GroupKind<'a> {
+    Track {
+        duration: Duration,
+        play: PlayTrack<'a>,
+    },
+    Delay(Duration),
+    Sequence(Vec<AnimationGroup<'a>>),
+    Parallel(Vec<AnimationGroup<'a>>),
+}
+
+impl std::fmt::Debug for AnimationGroup<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            GroupKind::Track { duration, .. } => {
+                f.debug_struct("Track").field("duration", duration).finish()
+            }
+            GroupKind::Delay(duration) => f.debug_tuple("Delay").field(duration).finish(),
+            GroupKind::Sequence(groups) => f.debug_tuple("Sequence").field(groups).finish(),
+            GroupKind::Parallel(groups) => f.debug_tuple("Parallel").field(groups).finish(),
+        }
+    }
+}
+
+impl<'a> AnimationGroup<'a> {
+    /// A track that starts animations that run for `duration`.
+    ///
+    /// `play` is called with an allocator that offsets the animation time to the start of the
+    /// track.
+    pub fn track(duration: Duration, play: impl FnOnce(&mut dyn AnimationAllocator) + 'a) -> Self {
+        Self {
+            kind: GroupKind::Track {
+                duration,
+                play: Box::new(play),
+            },
+        }
+    }
+
+    /// A track that animates `animated` to `target_value`.
+    pub fn animate<T>(
+        animated: &'a mut Animated<T>,
+        target_value: T,
+        duration: Duration,
+        interpolation: Interpolation,
+    ) -> Self
+    where
+        T: Interpolatable + Send + 'static,
+    {
+        Self::track(duration, move |context| {
+            animated.animate(context, target_value, duration, interpolation)
+        })
+    }
+
+    /// A track that plays a timeline on `animated`.
+    pub fn timeline<T>(animated: &'a mut Animated<T>, timeline: Timeline<T>) -> Self
+    where
+        T: Interpolatable + Send + 'static,
+    {
+        Self::track(timeline.duration(), move |context| {
+            animated.play(context, &timeline)
+        })
+    }
+
+    /// Nothing happens for `duration`. Useful in sequences.
+    pub fn delay(duration: Duration) -> Self {
+        Self {
+            kind: GroupKind::Delay(duration),
+        }
+    }
+
+    /// The groups are played one after another.
+    pub fn sequence(groups: impl IntoIterator<Item = Self>) -> Self {
+        Self {
+            kind: GroupKind::Sequence(groups.into_iter().collect()),
+        }
+    }
+
+    /// The groups are played at the same time.
+    pub fn parallel(groups: impl IntoIterator<Item = Self>) -> Self {
+        Self {
+            kind: GroupKind::Parallel(groups.into_iter().collect()),
+        }
+    }
+
+    /// The groups are played in parallel, each one starts `interval` after the previous one.
+    pub fn stagger(interval: Duration, groups: impl IntoIterator<Item = Self>) -> Self {
+        Self::parallel(
+            groups.into_iter().enumerate().map(|(index, group)| {
+                Self::sequence([Self::delay(interval * index as u32), group])
+            }),
+        )
+    }
+
+    /// The duration from the start of the group until its last animation ends.
+    pub fn duration(&self) -> Duration {
+        match &self.kind {
+            GroupKind::Track { duration, .. } | GroupKind::Delay(duration) => *duration,
+            GroupKind::Sequence(groups) => groups.iter().map(Self::duration).sum(),
+            GroupKind::Parallel(groups) => {
+                groups.iter().map(Self::duration).max().unwrap_or_default()
+            }
+        }
+    }
+
+    /// Starts all animations of the group.
+    pub fn play(self, context: &mut dyn AnimationAllocator) -> AnimationGroupHandle {
+        let handle = AnimationGroupHandle::new();
+        self.play_with(context, &handle);
+        handle
+    }
+
+    /// Starts all animations of the group, so that they are cancelled with `handle`.
+    pub fn play_with(self, context: &mut dyn AnimationAllocator, handle: &AnimationGroupHandle) {
+        self.play_at(context, Duration::ZERO, handle);
+    }
+
+    fn play_at(
+        self,
+        context: &mut dyn AnimationAllocator,
+        offset: Duration,
+        handle: &AnimationGroupHandle,
+    ) {
+        match self.kind {
+            GroupKind::Track { play, .. } => play(&mut GroupAllocator {
+                inner: context,
+                offset,
+                handle,
+            }),
+            GroupKind::Delay(_) => {}
+            GroupKind::Sequence(groups) => {
+                let mut offset = offset;
+                for group in groups {
+                    let duration = group.duration();
+                    group.play_at(context, offset, handle);
+                    offset += duration;
+                }
+            }
+            GroupKind::Parallel(groups) => {
+                for group in groups {
+                    group.play_at(context, offset, handle);
+                }
+            }
+        }
+    }
+}
+
+/// Cancels the animations of a played [`AnimationGroup`].
+///
+/// Cancelled values stop where they are the next time they proceed, including animations that
+/// were blended with the ones of the group.
+#[derive(Debug, Clone, Default)]
+pub struct AnimationGroupHandle(Arc<AtomicBool>);
+
+impl AnimationGroupHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Moves the allocated animation time to the offset of a track and marks the animations as part
+/// of the group.
+struct GroupAllocator<'a> {
+    inner: &'a mut dyn AnimationAllocator,
+    offset: Duration,
+    handle: &'a AnimationGroupHandle,
+}
+
+impl AnimationAllocator for GroupAllocator<'_> {
+    fn allocate_animation_time(&mut self, duration: Duration) -> Instant {
+        self.inner.allocate_animation_time(self.offset + duration) + self.offset
+    }
+
+    fn group(&self) -> Option<&AnimationGroupHandle> {
+        Some(self.handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnimationCoordinator;
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    fn assert_near(value: &f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+    }
+
+    #[test]
+    fn sequences_and_staggers_start_at_their_offsets() {
+        let mut coordinator = AnimationCoordinator::new();
+        coordinator.begin_cycle();
+        let start = coordinator.animation_time();
+
+        let mut first = Animated::new(0.0);
+        let mut second = Animated::new(0.0);
+        let mut staggered = [Animated::new(0.0), Animated::new(0.0)];
+        let [a, b] = &mut staggered;
+
+        let group = AnimationGroup::sequence([
+            AnimationGroup::animate(&mut first, 1.0, STEP, Interpolation::Linear),
+            AnimationGroup::animate(&mut second, 1.0, STEP, Interpolation::Linear),
+            AnimationGroup::stagger(
+                STEP,
+                [
+                    AnimationGroup::animate(a, 1.0, STEP, Interpolation::Linear),
+                    AnimationGroup::animate(b, 1.0, STEP, Interpolation::Linear),
+                ],
+            ),
+        ]);
+        assert_eq!(group.duration(), STEP * 4);
+        group.play(&mut coordinator);
+
+        let half = STEP / 2;
+        assert_near(first.proceed(start + half), 0.5);
+        assert_near(second.proceed(start + half), 0.0);
+        assert_near(second.proceed(start + STEP + half), 0.5);
+        assert_near(staggered[0].proceed(start + STEP * 2 + half), 0.5);
+        assert_near(staggered[1].proceed(start + STEP * 2 + half), 0.0);
+        assert_near(staggered[1].proceed(start + STEP * 4), 1.0);
+        assert!(!staggered[1].is_animating());
+    }
+
+    #[test]
+    fn cancelled_groups_stop_where_they_are() {
+        let mut coordinator = AnimationCoordinator::new();
+        coordinator.begin_cycle();
+        let start = coordinator.animation_time();
+
+        let mut value = Animated::new(0.0);
+        let timeline = Timeline::new()
+            .keyframe(STEP, 1.0, Interpolation::Linear)
+            .keyframe(STEP * 2, 3.0, Interpolation::Linear);
+        let handle = AnimationGroup::timeline(&mut value, timeline).play(&mut coordinator);
+
+        assert_near(value.proceed(start + STEP / 2), 0.5);
+        assert_near(value.proceed(start + STEP + STEP / 2), 2.0);
+        handle.cancel();
+        assert_near(value.proceed(start + STEP + STEP * 3 / 4), 2.5);
+        assert!(!value.is_animating());
+        assert_near(value.proceed(start + STEP * 2), 2.5);
+    }
+}
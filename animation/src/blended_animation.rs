@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use crate::{time::Instant, Ease, Interpolatable, Interpolation};
+use crate::{time::Instant, AnimationGroupHandle, Ease, Interpolatable, Interpolation};
 
 #[derive(Debug)]
 pub struct BlendedAnimation<T> {
@@ -24,7 +24,11 @@ impl<T> BlendedAnimation<T> {
     /// The new end time for the blended animation and its value is now the new targeted end state.
     ///
     /// Animations on the stack reaching beyond the end time of `current_time` + `duration` won't be
-    /// animated to their final value or end time anymore but gradually fade out.
+    /// animated to their final value or end time anymore but gradually fade out. Animations that
+    /// start after all previous ones ended replace them without blending, so that they can be
+    /// sequenced.
+    ///
+    /// If `group` gets cancelled, the animation is cancelled, see [`Self::is_cancelled`].
     pub fn animate_to(
         &mut self,
         current_value: T,
@@ -32,6 +36,7 @@ impl<T> BlendedAnimation<T> {
         to: T,
         duration: Duration,
         interpolation: Interpolation,
+        group: Option<AnimationGroupHandle>,
     ) {
         self.animations.push(Animation {
             from: current_value,
@@ -39,6 +44,7 @@ impl<T> BlendedAnimation<T> {
             start_time: current_time,
             duration,
             interpolation,
+            group,
         });
     }
 
@@ -47,6 +53,16 @@ impl<T> BlendedAnimation<T> {
         !self.animations.is_empty()
     }
 
+    /// Is any animation part of a group that got cancelled?
+    pub fn is_cancelled(&self) -> bool {
+        self.animations.iter().any(|animation| {
+            animation
+                .group
+                .as_ref()
+                .is_some_and(AnimationGroupHandle::is_cancelled)
+        })
+    }
+
     /// The target / final value if all animations ran through, or `None` if animations are not active.
     pub fn target(&self) -> Option<&T> {
         self.animations.last().map(|a| &a.to)
@@ -67,6 +83,7 @@ impl<T> BlendedAnimation<T> {
         // not implement Default.
         let mut blended = self.animations[0].from.clone();
         let mut first_contributing_animation_index = 0;
+        let mut previous_end_time: Option<Instant> = None;
 
         for (index, animation) in self.animations.iter().enumerate() {
             let t = animation.t_at(instant);
@@ -74,11 +91,21 @@ impl<T> BlendedAnimation<T> {
             assert!(t >= 0.0);
             let value = animation.value_at_t(t);
 
+            // An animation that started after all previous ones ended does not need to be blended.
+            let sequenced = instant >= animation.start_time
+                && previous_end_time.is_some_and(|end_time| end_time <= animation.start_time);
+            let end_time = animation.start_time + animation.duration;
+            previous_end_time = Some(previous_end_time.map_or(end_time, |e| e.max(end_time)));
+
             // The weight of the current animation relative to all previous ones.
             //
             // Weight is 1 at index 0, because a single animation does not need a blending factor,
             // otherwise the weight is the same t the animation uses to interpolate its value.
-            let blend_weight = if index == 0 { 1. } else { t.min(1.0) };
+            let blend_weight = if index == 0 || sequenced {
+                1.
+            } else {
+                t.min(1.0)
+            };
 
             // Blend the current value (linearly) into the animations value and use it as the basis
             // for the next round.
@@ -133,6 +160,8 @@ struct Animation<T> {
     duration: Duration,
     /// How to adjust t before interpolating the value.
     interpolation: Interpolation,
+    /// The group the animation belongs to.
+    group: Option<AnimationGroupHandle>,
 }
 
 impl<T> Animation<T> {
@@ -1,27 +1,48 @@
 use std::time::Instant;
 
 use massive_geometry::{
-    CameraMode, PixelCamera, Point, Rect, Size, SizedTransform, Transform, Vector3,
+    CameraMode, Color, Oklab, PixelCamera, Point, Rect, Size, SizedTransform, Transform, Vector3,
 };
 
+use crate::quaternion;
+
 /// For now we have to support `Clone`.
 ///
 /// Other options: We pass 1.0 here and expect `Self` to return a clone for `to`, but can then never
 /// be sure that it's exactly == `to`.`
+///
+/// `t` may be outside of 0 to 1, in which case the values are extrapolated.
+///
+/// Structs can derive it with `#[derive(Interpolatable)]`, see [`massive_animation_derive`]. The
+/// geometry types can't, because they are defined in `massive_geometry`, which does not depend on
+/// this crate.
 pub trait Interpolatable: Clone {
     fn interpolate(from: &Self, to: &Self, t: f64) -> Self;
+
+    /// How far apart two values are. [`crate::Spring`]s use it to decide when they come to rest.
+    ///
+    /// For composite values, this is the sum of the distances of their components.
+    fn distance(from: &Self, to: &Self) -> f64;
 }
 
 impl Interpolatable for f32 {
     fn interpolate(from: &Self, to: &Self, t: f64) -> Self {
         (to - from) * (t as f32) + from
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        (to - from).abs() as f64
+    }
 }
 
 impl Interpolatable for f64 {
     fn interpolate(from: &Self, to: &Self, t: f64) -> Self {
         (to - from) * t + from
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        (to - from).abs()
+    }
 }
 
 impl Interpolatable for Instant {
@@ -31,6 +52,10 @@ impl Interpolatable for Instant {
         }
         *to + from.duration_since(*to).mul_f64(t)
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        from.max(to).duration_since(*from.min(to)).as_secs_f64()
+    }
 }
 
 // 3D Geometry
@@ -42,16 +67,27 @@ impl Interpolatable for Vector3 {
         let z = interpolate(&from.z, &to.z, t);
         (x, y, z).into()
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        from.distance(*to)
+    }
 }
 
 impl Interpolatable for Transform {
     fn interpolate(from: &Self, to: &Self, t: f64) -> Self {
         Transform {
             translate: interpolate(&from.translate, &to.translate, t),
-            rotate: from.rotate.slerp(to.rotate, t),
+            rotate: quaternion::interpolate(&from.rotate, &to.rotate, t),
             scale: interpolate(&from.scale, &to.scale, t),
         }
     }
+
+    /// The rotation contributes its angle in radians.
+    fn distance(from: &Self, to: &Self) -> f64 {
+        distance(&from.translate, &to.translate)
+            + quaternion::distance(&from.rotate, &to.rotate)
+            + distance(&from.scale, &to.scale)
+    }
 }
 
 impl Interpolatable for SizedTransform {
@@ -61,6 +97,10 @@ impl Interpolatable for SizedTransform {
             interpolate(&from.transform, &to.transform, t),
         )
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        distance(&from.size, &to.size) + distance(&from.transform, &to.transform)
+    }
 }
 
 impl Interpolatable for PixelCamera {
@@ -71,6 +111,12 @@ impl Interpolatable for PixelCamera {
             fovy: interpolate(&from.fovy, &to.fovy, t),
         }
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        distance(&from.look_at, &to.look_at)
+            + distance(&from.mode, &to.mode)
+            + distance(&from.fovy, &to.fovy)
+    }
 }
 
 impl Interpolatable for CameraMode {
@@ -125,6 +171,71 @@ impl Interpolatable for CameraMode {
                 target_size: interpolate(from_size, to_size, t),
                 blend: interpolate(from_blend, to_blend, t),
             },
+            (PixelPerfect, Orthographic { blend: to_blend }) => Orthographic {
+                blend: interpolate(&0.0, to_blend, t),
+            },
+            (Orthographic { blend: from_blend }, PixelPerfect) => {
+                let blend = interpolate(from_blend, &0.0, t);
+                if blend == 0.0 {
+                    PixelPerfect
+                } else {
+                    Orthographic { blend }
+                }
+            }
+            (Orthographic { blend: from_blend }, Orthographic { blend: to_blend }) => {
+                Orthographic {
+                    blend: interpolate(from_blend, to_blend, t),
+                }
+            }
+            // Sized and orthographic modes don't mix, the first half blends back to pixel perfect,
+            // the second half blends to the target mode.
+            (Sized { .. }, Orthographic { .. }) | (Orthographic { .. }, Sized { .. }) => {
+                if t < 0.5 {
+                    interpolate(from, &PixelPerfect, t * 2.0)
+                } else {
+                    interpolate(&PixelPerfect, to, t * 2.0 - 1.0)
+                }
+            }
+        }
+    }
+
+    /// A pixel perfect mode is at a blend of 0 from every sized and orthographic mode.
+    fn distance(from: &Self, to: &Self) -> f64 {
+        use CameraMode::*;
+
+        match (from, to) {
+            (PixelPerfect, PixelPerfect) => 0.0,
+            (PixelPerfect, Sized { blend, .. }) | (Sized { blend, .. }, PixelPerfect) => {
+                blend.abs()
+            }
+            (
+                Sized {
+                    target_size: from_size,
+                    blend: from_blend,
+                },
+                Sized {
+                    target_size: to_size,
+                    blend: to_blend,
+                },
+            ) => distance(from_size, to_size) + distance(from_blend, to_blend),
+            (PixelPerfect, Orthographic { blend }) | (Orthographic { blend }, PixelPerfect) => {
+                blend.abs()
+            }
+            (Orthographic { blend: from_blend }, Orthographic { blend: to_blend }) => {
+                distance(from_blend, to_blend)
+            }
+            (
+                Sized { blend: sized, .. },
+                Orthographic {
+                    blend: orthographic,
+                },
+            )
+            | (
+                Orthographic {
+                    blend: orthographic,
+                },
+                Sized { blend: sized, .. },
+            ) => sized.abs() + orthographic.abs(),
         }
     }
 }
@@ -135,6 +246,10 @@ impl Interpolatable for Size {
         let height = interpolate(&from.height, &to.height, t);
         Size::new(width, height)
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        (to.width - from.width).hypot(to.height - from.height)
+    }
 }
 
 // 2D Geometry
@@ -145,6 +260,10 @@ impl Interpolatable for Point {
         let y = f64::interpolate(&from.y, &to.y, t);
         (x, y).into()
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        (to.x - from.x).hypot(to.y - from.y)
+    }
 }
 
 impl Interpolatable for Rect {
@@ -155,6 +274,46 @@ impl Interpolatable for Rect {
         let max = Point::interpolate(&f_max, &t_max, t);
         (min, max).into()
     }
+
+    fn distance(from: &Self, to: &Self) -> f64 {
+        let (f_min, f_max) = (*from).into();
+        let (t_min, t_max) = (*to).into();
+        Point::distance(&f_min, &t_min) + Point::distance(&f_max, &t_max)
+    }
+}
+
+// Colors
+
+/// Colors are interpolated in OKLab with premultiplied alpha, so that fades between colors don't go
+/// muddy and fades to transparent don't go dark.
+impl Interpolatable for Color {
+    fn interpolate(from: &Self, to: &Self, t: f64) -> Self {
+        let (from, to) = (Oklab::from(*from), Oklab::from(*to));
+        let mix = |from: f32, to: f32| f32::interpolate(&from, &to, t);
+        let alpha = mix(from.alpha, to.alpha);
+        let component = |from_component: f32, to_component: f32| {
+            if alpha <= 0.0 {
+                return mix(from_component, to_component);
+            }
+            mix(from_component * from.alpha, to_component * to.alpha) / alpha
+        };
+        Oklab {
+            lightness: component(from.lightness, to.lightness),
+            a: component(from.a, to.a),
+            b: component(from.b, to.b),
+            alpha,
+        }
+        .into()
+    }
+
+    /// The euclidean distance in OKLab plus the difference of the alpha values.
+    fn distance(from: &Self, to: &Self) -> f64 {
+        let (from, to) = (Oklab::from(*from), Oklab::from(*to));
+        let lab = (to.lightness - from.lightness)
+            .hypot(to.a - from.a)
+            .hypot(to.b - from.b);
+        (lab + (to.alpha - from.alpha).abs()) as f64
+    }
 }
 
 pub fn interpolate<T>(from: &T, to: &T, t: f64) -> T
@@ -163,3 +322,94 @@ where
 {
     T::interpolate(from, to, t)
 }
+
+pub fn distance<T>(from: &T, to: &T) -> f64
+where
+    T: Interpolatable,
+{
+    T::distance(from, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use massive_geometry::{CameraMode, Color, Quaternion, Size};
+
+    use crate::Interpolatable;
+
+    #[derive(Debug, Clone, PartialEq, Interpolatable)]
+    struct Fade {
+        alpha: f64,
+        #[interpolatable(snap)]
+        visible: bool,
+        #[interpolatable(with = crate::quaternion)]
+        rotation: Quaternion,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Interpolatable)]
+    struct Pair(f32, f64);
+
+    #[test]
+    fn derived_fields_interpolate_snap_and_use_custom_functions() {
+        let from = Fade {
+            alpha: 0.0,
+            visible: false,
+            rotation: Quaternion::IDENTITY,
+        };
+        let to = Fade {
+            alpha: 1.0,
+            visible: true,
+            // The same rotation as `from_rotation_z(FRAC_PI_2)`, but the long way around.
+            rotation: -Quaternion::from_rotation_z(FRAC_PI_2),
+        };
+
+        let early = Fade::interpolate(&from, &to, 0.25);
+        assert_eq!(early.alpha, 0.25);
+        assert!(!early.visible);
+        let late = Fade::interpolate(&from, &to, 0.5);
+        assert!(late.visible);
+        let halfway = Quaternion::from_rotation_z(FRAC_PI_2 / 2.0);
+        assert!(late.rotation.angle_between(halfway) < 1e-9);
+
+        assert!((Fade::distance(&from, &to) - (1.0 + FRAC_PI_2)).abs() < 1e-9);
+
+        assert_eq!(
+            Pair::interpolate(&Pair(0.0, 2.0), &Pair(1.0, 4.0), 0.5),
+            Pair(0.5, 3.0)
+        );
+        assert_eq!(Pair::distance(&Pair(0.0, 2.0), &Pair(1.0, 4.0)), 3.0);
+    }
+
+    #[test]
+    fn colors_fade_to_transparent_without_darkening() {
+        let red = Color::rgb_u32(0xff0000);
+        let faded = Color::interpolate(&red, &Color::TRANSPARENT, 0.5);
+        assert!((faded.red - 1.0).abs() < 1e-3);
+        assert!(faded.green.abs() < 1e-3 && faded.blue.abs() < 1e-3);
+        assert_eq!(faded.alpha, 0.5);
+    }
+
+    #[test]
+    fn sized_cameras_become_orthographic_through_pixel_perfect() {
+        let sized = CameraMode::Sized {
+            target_size: Size::new(100.0, 100.0),
+            blend: 1.0,
+        };
+        let orthographic = CameraMode::Orthographic { blend: 1.0 };
+
+        assert!(matches!(
+            CameraMode::interpolate(&sized, &orthographic, 0.25),
+            CameraMode::Sized { blend: 0.5, .. }
+        ));
+        assert_eq!(
+            CameraMode::interpolate(&sized, &orthographic, 0.75),
+            CameraMode::Orthographic { blend: 0.5 }
+        );
+        assert_eq!(
+            CameraMode::interpolate(&orthographic, &CameraMode::PixelPerfect, 1.0),
+            CameraMode::PixelPerfect
+        );
+        assert_eq!(CameraMode::distance(&sized, &orthographic), 2.0);
+    }
+}
@@ -1,18 +1,31 @@
 mod animated;
 mod animation_coordinator;
+mod animation_group;
 mod blended_animation;
 mod interpolatable;
 mod interpolation;
 mod movement_runtime;
+pub mod quaternion;
+mod spring;
+mod spring_animated;
 mod time_scale;
+mod timeline;
 
 pub use animated::*;
 pub use animation_coordinator::*;
+pub use animation_group::*;
 pub use blended_animation::*;
 pub use interpolatable::*;
 pub use interpolation::*;
+pub use massive_animation_derive::Interpolatable;
 pub use movement_runtime::*;
+pub use spring::*;
+pub use spring_animated::*;
 pub use time_scale::*;
+pub use timeline::*;
+
+// The derive macro refers to `::massive_animation`, which needs to resolve in this crate, too.
+extern crate self as massive_animation;
 
 mod time {
     #[cfg(not(target_arch = "wasm32"))]
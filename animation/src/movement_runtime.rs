@@ -10,7 +10,7 @@ use std::time::{Duration, Instant};
 
 use parking_lot::Mutex;
 
-use crate::{AnimationAllocator, AnimationProgress};
+use crate::{AnimationAllocator, AnimationGroupHandle, AnimationProgress};
 
 pub struct MovementRuntime {
     movements: HashMap<MovementReference, MountedMovement>,
@@ -272,6 +272,10 @@ impl AnimationAllocator for MovementAnimationAllocator<'_> {
         });
         start
     }
+
+    fn group(&self) -> Option<&AnimationGroupHandle> {
+        self.inner.group()
+    }
 }
 
 type ModifyMovement =
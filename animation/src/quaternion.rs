@@ -0,0 +1,19 @@
+//! Interpolation of rotations along the shortest path.
+//!
+//! Quaternions are not [`crate::Interpolatable`], because the component-wise interpolation does not
+//! produce rotations. Use this module for quaternion fields with
+//! `#[interpolatable(with = massive_animation::quaternion)]`.
+
+use massive_geometry::Quaternion;
+
+/// Spherical linear interpolation along the shortest path between two rotations.
+pub fn interpolate(from: &Quaternion, to: &Quaternion, t: f64) -> Quaternion {
+    // `q` and `-q` are the same rotation, the one closer to `from` takes the shorter path.
+    let to = if from.dot(*to) < 0.0 { -*to } else { *to };
+    from.slerp(to, t)
+}
+
+/// The angle between two rotations in radians.
+pub fn distance(from: &Quaternion, to: &Quaternion) -> f64 {
+    from.angle_between(*to)
+}
@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use crate::time::Instant;
+use crate::Interpolatable;
+
+/// The physical parameters of a spring that pulls a value towards its target.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Spring {
+    /// The force per unit of displacement.
+    pub stiffness: f64,
+    /// The force per unit of velocity that opposes the movement.
+    pub damping: f64,
+    pub mass: f64,
+    /// The spring comes to rest when the value is at most this distance away from the target and
+    /// moves slower than `rest_velocity`.
+    pub rest_displacement: f64,
+    /// The distance per second.
+    pub rest_velocity: f64,
+}
+
+impl Default for Spring {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Spring {
+    /// Close to critically damped, reaches its target without a visible overshoot.
+    pub const DEFAULT: Self = Self::new(170.0, 26.0);
+    /// Slow, with a visible overshoot.
+    pub const GENTLE: Self = Self::new(120.0, 14.0);
+    /// Fast, with a small overshoot.
+    pub const STIFF: Self = Self::new(210.0, 20.0);
+
+    pub const DEFAULT_REST_DISPLACEMENT: f64 = 0.001;
+    pub const DEFAULT_REST_VELOCITY: f64 = 0.01;
+
+    /// The simulation advances in steps of this duration, independent of the frame rate.
+    const STEP: Duration = Duration::from_nanos(1_000_000_000 / 240);
+    /// Springs that don't come to rest (e.g. without damping) are snapped to their target after
+    /// this duration.
+    const MAX_SETTLE_DURATION: Duration = Duration::from_secs(10);
+
+    pub const fn new(stiffness: f64, damping: f64) -> Self {
+        Self {
+            stiffness,
+            damping,
+            mass: 1.0,
+            rest_displacement: Self::DEFAULT_REST_DISPLACEMENT,
+            rest_velocity: Self::DEFAULT_REST_VELOCITY,
+        }
+    }
+
+    pub const fn with_mass(mut self, mass: f64) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    pub const fn with_rest_thresholds(mut self, displacement: f64, velocity: f64) -> Self {
+        self.rest_displacement = displacement;
+        self.rest_velocity = velocity;
+        self
+    }
+
+    /// The damping ratio: Below 1 the spring overshoots, at 1 it is critically damped.
+    pub fn damping_ratio(&self) -> f64 {
+        self.damping / (2.0 * (self.stiffness * self.mass).sqrt())
+    }
+
+    /// Compute the value one simulation step after `current`, where `previous` is the value one
+    /// step before.
+    ///
+    /// This is a damped Verlet step that only needs interpolation: The velocity is represented by
+    /// the previous value and continued by extrapolating, and the spring force is applied by
+    /// interpolating towards the target.
+    fn step<T: Interpolatable>(&self, previous: &T, current: &T, target: &T) -> T {
+        let dt = Self::STEP.as_secs_f64();
+        let damping = (self.damping * dt / self.mass).clamp(0.0, 1.0);
+        let pull = (self.stiffness * dt * dt / self.mass).clamp(0.0, 1.0);
+        let moved = T::interpolate(previous, current, 2.0 - damping);
+        T::interpolate(&moved, target, pull)
+    }
+
+    fn is_at_rest<T: Interpolatable>(&self, previous: &T, current: &T, target: &T) -> bool {
+        T::distance(current, target) <= self.rest_displacement
+            && T::distance(previous, current) / Self::STEP.as_secs_f64() <= self.rest_velocity
+    }
+}
+
+/// A value that is pulled by a spring towards its target.
+///
+/// In contrast to [`crate::BlendedAnimation`], there is no fixed duration. When the target
+/// changes, the spring continues with its current velocity.
+#[derive(Debug)]
+pub struct SpringAnimation<T> {
+    spring: Spring,
+    target: T,
+    /// The value one step before `current`.
+    previous: T,
+    current: T,
+    /// The time of `current`.
+    time: Instant,
+    /// The time the spring comes to rest.
+    rest_time: Instant,
+}
+
+impl<T: Interpolatable> SpringAnimation<T> {
+    /// Starts the spring at `value` without velocity.
+    pub fn new(spring: Spring, value: T, time: Instant, target: T) -> Self {
+        let mut animation = Self {
+            spring,
+            target,
+            previous: value.clone(),
+            current: value,
+            time,
+            rest_time: time,
+        };
+        animation.rest_time = time + animation.settle_duration();
+        animation
+    }
+
+    /// Changes the target and the parameters of the spring. The current velocity is kept.
+    pub fn retarget(&mut self, spring: Spring, target: T) {
+        self.spring = spring;
+        self.target = target;
+        self.rest_time = self.time + self.settle_duration();
+    }
+
+    /// Moves the spring in time without simulating it.
+    pub(crate) fn move_to(&mut self, time: Instant) {
+        self.rest_time = time + (self.rest_time - self.time);
+        self.time = time;
+    }
+
+    /// The time the simulation is at. The spring is resting from [`Self::rest_time`] on.
+    pub fn time(&self) -> Instant {
+        self.time
+    }
+
+    pub fn rest_time(&self) -> Instant {
+        self.rest_time
+    }
+
+    pub fn target(&self) -> &T {
+        &self.target
+    }
+
+    /// Simulate up to `instant` and return the value and if the spring came to rest.
+    ///
+    /// At rest, the value is exactly the target.
+    pub fn proceed(&mut self, instant: Instant) -> (T, bool) {
+        if instant >= self.rest_time {
+            self.time = instant;
+            return (self.target.clone(), true);
+        }
+
+        while self.time < instant {
+            let next = self
+                .spring
+                .step(&self.previous, &self.current, &self.target);
+            self.previous = std::mem::replace(&mut self.current, next);
+            self.time += Spring::STEP;
+        }
+
+        (self.current.clone(), false)
+    }
+
+    /// The duration from the current state until the spring comes to rest.
+    ///
+    /// The simulation is deterministic, so this runs it ahead on a copy of the state.
+    fn settle_duration(&self) -> Duration {
+        let mut previous = self.previous.clone();
+        let mut current = self.current.clone();
+        let mut duration = Duration::ZERO;
+
+        while !self.spring.is_at_rest(&previous, &current, &self.target) {
+            if duration >= Spring::MAX_SETTLE_DURATION {
+                break;
+            }
+            let next = self.spring.step(&previous, &current, &self.target);
+            previous = std::mem::replace(&mut current, next);
+            duration += Spring::STEP;
+        }
+
+        duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_until_rest(animation: &mut SpringAnimation<f64>, start: Instant) -> Vec<f64> {
+        let frame = Duration::from_millis(16);
+        let mut values = Vec::new();
+        let mut instant = start;
+        loop {
+            instant += frame;
+            let (value, at_rest) = animation.proceed(instant);
+            values.push(value);
+            if at_rest {
+                return values;
+            }
+        }
+    }
+
+    #[test]
+    fn spring_comes_to_rest_at_its_target() {
+        let start = Instant::now();
+        let mut animation = SpringAnimation::new(Spring::DEFAULT, 0.0, start, 100.0);
+        let settle = animation.rest_time() - start;
+        assert!(settle > Duration::from_millis(100) && settle < Duration::from_secs(2));
+
+        let values = run_until_rest(&mut animation, start);
+        assert_eq!(*values.last().unwrap(), 100.0);
+        // Underdamped springs overshoot.
+        assert!(Spring::GENTLE.damping_ratio() < 1.0);
+        let gentle = run_until_rest(
+            &mut SpringAnimation::new(Spring::GENTLE, 0.0, start, 100.0),
+            start,
+        );
+        assert!(gentle.iter().any(|value| *value > 100.0));
+    }
+
+    #[test]
+    fn retargeting_keeps_the_velocity() {
+        let start = Instant::now();
+        let mut animation = SpringAnimation::new(Spring::DEFAULT, 0.0, start, 100.0);
+        let frame = Duration::from_millis(16);
+        let (before, _) = animation.proceed(start + frame * 5);
+        let (moving, _) = animation.proceed(start + frame * 6);
+        assert!(moving > before);
+
+        // Retargeting to the current value does not stop the movement immediately.
+        animation.retarget(Spring::DEFAULT, moving);
+        let (after, _) = animation.proceed(start + frame * 7);
+        assert!(after > moving);
+
+        let values = run_until_rest(&mut animation, start + frame * 7);
+        assert_eq!(*values.last().unwrap(), moving);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::time::Instant;
+use crate::{AnimationAllocator, AnimationProgress, Interpolatable, Spring, SpringAnimation};
+
+/// A value that is animated by a [`Spring`].
+///
+/// Like [`crate::Animated`], but animations keep the velocity of the current one when they are
+/// started while it is still running.
+#[derive(Debug)]
+pub struct SpringAnimated<T>
+where
+    T: Send,
+{
+    /// The current value.
+    value: T,
+    /// The currently running animation.
+    animation: Option<SpringAnimation<T>>,
+}
+
+impl<T: Send + Interpolatable> From<T> for SpringAnimated<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Send + Interpolatable> SpringAnimated<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            animation: None,
+        }
+    }
+
+    pub fn animate_if_changed(
+        &mut self,
+        context: &mut dyn AnimationAllocator,
+        target_value: T,
+        spring: Spring,
+    ) where
+        T: PartialEq,
+    {
+        if *self.target() == target_value {
+            return;
+        }
+
+        self.animate(context, target_value, spring);
+    }
+
+    /// Pull the value towards `target_value`.
+    ///
+    /// The duration is allocated up to the time the spring comes to rest.
+    pub fn animate(
+        &mut self,
+        context: &mut dyn AnimationAllocator,
+        target_value: T,
+        spring: Spring,
+    ) {
+        match &mut self.animation {
+            Some(animation) => {
+                animation.retarget(spring, target_value);
+                context.allocate_animation_time(animation.rest_time() - animation.time());
+            }
+            None => {
+                // The starting time is allocated together with the duration, so the spring is
+                // moved to it afterwards.
+                let mut animation =
+                    SpringAnimation::new(spring, self.value.clone(), Instant::now(), target_value);
+                let start =
+                    context.allocate_animation_time(animation.rest_time() - animation.time());
+                animation.move_to(start);
+                self.animation = Some(animation);
+            }
+        }
+    }
+
+    pub fn snap(&mut self, value: T) {
+        self.animation = None;
+        self.value = value;
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(animation) = self.animation.take() {
+            self.value = animation.target().clone();
+        }
+    }
+
+    pub fn latest(&self) -> &T {
+        &self.value
+    }
+
+    pub fn target(&self) -> &T {
+        self.animation
+            .as_ref()
+            .map(|animation| animation.target())
+            .unwrap_or(&self.value)
+    }
+
+    pub fn proceed(&mut self, progress: impl Into<AnimationProgress>) -> &T {
+        match progress.into() {
+            AnimationProgress::Proceed(instant) => self.proceed_animation(instant),
+            AnimationProgress::Snap => self.finish(),
+        }
+        self.latest()
+    }
+
+    fn proceed_animation(&mut self, instant: Instant) {
+        if let Some(animation) = &mut self.animation {
+            let (value, at_rest) = animation.proceed(instant);
+            self.value = value;
+            if at_rest {
+                self.animation = None;
+            }
+        }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+}
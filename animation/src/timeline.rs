@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use crate::Interpolation;
+
+/// Keyframes a value animates through, at offsets from the start of the timeline.
+///
+/// Timelines are played by [`crate::Animated::play`].
+#[derive(Debug, Clone)]
+pub struct Timeline<T> {
+    delay: Duration,
+    keyframes: Vec<Keyframe<T>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keyframe<T> {
+    /// The offset from the start of the timeline, after its delay.
+    pub offset: Duration,
+    pub value: T,
+    /// The easing of the segment that leads to this keyframe.
+    pub interpolation: Interpolation,
+}
+
+impl<T> Default for Timeline<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Timeline<T> {
+    pub fn new() -> Self {
+        Self {
+            delay: Duration::ZERO,
+            keyframes: Vec::new(),
+        }
+    }
+
+    /// The value stays where it is for `delay` before the timeline starts.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Adds a keyframe. The value animates to it from the previous keyframe, or for the first one,
+    /// from the value the timeline is played on.
+    ///
+    /// A keyframe at the offset of the previous one is jumped to.
+    ///
+    /// # Panics
+    ///
+    /// If `offset` is before the offset of the previous keyframe.
+    pub fn keyframe(mut self, offset: Duration, value: T, interpolation: Interpolation) -> Self {
+        if let Some(last) = self.keyframes.last() {
+            assert!(
+                offset >= last.offset,
+                "Keyframes must be added in the order of their offsets"
+            );
+        }
+        self.keyframes.push(Keyframe {
+            offset,
+            value,
+            interpolation,
+        });
+        self
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// The delay and the offset of the last keyframe.
+    pub fn duration(&self) -> Duration {
+        self.delay
+            + self
+                .keyframes
+                .last()
+                .map_or(Duration::ZERO, |last| last.offset)
+    }
+
+    /// The segments between the keyframes, each with its start offset including the delay, its
+    /// duration, and the keyframe it leads to.
+    pub(crate) fn segments(&self) -> impl Iterator<Item = (Duration, Duration, &Keyframe<T>)> {
+        let mut previous_offset = Duration::ZERO;
+        self.keyframes.iter().map(move |keyframe| {
+            let start = self.delay + previous_offset;
+            let duration = keyframe.offset - previous_offset;
+            previous_offset = keyframe.offset;
+            (start, duration, keyframe)
+        })
+    }
+}
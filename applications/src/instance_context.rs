@@ -131,6 +131,11 @@ impl InstanceContext {
         &self.environment.font_manager
     }
 
+    /// The location the views of this instance are placed relative to.
+    pub fn view_parent(&self) -> &Ref<Location> {
+        &self.view_parent
+    }
+
     /// ADR: We share _one_ single scene in all views now, so that we can keep the updates that we
     /// send to desktop coordinated. Also, changes can't be submitted independently, all updates
     /// from all views need to be submitted at once.
@@ -172,6 +177,21 @@ impl InstanceContext {
         self.submit_with_pacing(submission.pacing())
     }
 
+    /// Submit changes that were not collected by this context, for example the ones of an
+    /// instance that runs in another process.
+    ///
+    /// Scene changes must be in the identity space of the desktop.
+    pub fn submit_changes(
+        &mut self,
+        changes: impl IntoIterator<Item = InstanceChange>,
+        pacing: RenderPacing,
+    ) -> Result<()> {
+        for change in changes {
+            self.changes.collect(change);
+        }
+        self.submit_with_pacing(pacing)
+    }
+
     fn submit_with_pacing(&mut self, pacing: RenderPacing) -> Result<()> {
         let changes = self.changes.take_all();
         let change_count = changes.len();
@@ -1,4 +1,5 @@
 use derive_more::From;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 mod application_event;
@@ -6,6 +7,7 @@ mod frame;
 mod instance_context;
 mod instance_environment;
 mod project;
+mod remote;
 mod view;
 mod view_builder;
 mod view_event;
@@ -15,15 +17,16 @@ pub use frame::*;
 pub use instance_context::*;
 pub use instance_environment::*;
 pub use project::*;
+pub use remote::*;
 pub use view::*;
 pub use view_event::*;
 
 pub use massive_scene::Scene;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, From)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, From, Serialize, Deserialize)]
 pub struct InstanceId(Uuid);
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, From)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, From, Serialize, Deserialize)]
 pub struct ViewId(Uuid);
 
 impl ViewId {
@@ -38,7 +41,7 @@ impl ViewId {
 /// This is neither a [`ViewId`], which identifies logical application content and input targets,
 /// nor a native window identifier. A presentation may be backed by a window, an embedded surface,
 /// or another host-specific rendering target.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, From)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, From, Serialize, Deserialize)]
 pub struct PresentationId(Uuid);
 
 impl PresentationId {
@@ -0,0 +1,491 @@
+//! The protocol between the desktop and an instance that runs in a separate process.
+//!
+//! The desktop starts the instance process and talks to it over the process' stdin and stdout.
+//! Both directions carry length prefixed postcard messages. The desktop sends
+//! [`DesktopMessage::Start`] first, after that, the instance sends submissions and the desktop
+//! forwards the events for the instance's views.
+//!
+//! Scene ids are allocated by the instance process. The desktop maps them into its own identity
+//! space and attaches locations without a parent to the instance's view parent.
+//!
+//! The connection ends when the instance process closes its stdout.
+
+use std::convert::Infallible;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use winit::event::{DeviceId, ElementState, Ime, MouseButton, MouseScrollDelta, TouchPhase};
+use winit::keyboard::{Key, KeyLocation, ModifiersState, PhysicalKey};
+use winit::window::CursorIcon;
+
+use massive_geometry::{BoxPx, Point};
+use massive_renderer::{ChangeDecoder, ChangeEncoder, EncodedChanges, RenderPacing};
+use massive_scene::SceneChange;
+
+use crate::{
+    ApplicationEvent, ConfigurationRequest, CreationMode, InstanceChange, InstanceId,
+    InstanceParameters, PresentationId, ViewChange, ViewCreationInfo, ViewEvent, ViewId, ViewRole,
+};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The maximum size of a serialized message. Larger messages are rejected, so that a corrupt or
+/// hostile length prefix can not make the receiver allocate arbitrary amounts of memory.
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Messages from the desktop to the instance process.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum DesktopMessage {
+    Start(RemoteStart),
+    View(ViewId, RemoteViewEvent),
+    ApplyAnimations(PresentationId),
+    Shutdown(InstanceId),
+}
+
+impl DesktopMessage {
+    pub fn start(
+        instance: InstanceId,
+        creation_mode: &CreationMode,
+        primary_monitor_scale_factor: f64,
+    ) -> Result<Self> {
+        let parameters = match creation_mode {
+            CreationMode::New(parameters) => Some(serde_json::to_string(parameters)?),
+            CreationMode::Restore => None,
+        };
+        Ok(Self::Start(RemoteStart {
+            version: PROTOCOL_VERSION,
+            instance,
+            parameters,
+            primary_monitor_scale_factor,
+        }))
+    }
+}
+
+impl From<ApplicationEvent<Infallible>> for DesktopMessage {
+    fn from(event: ApplicationEvent<Infallible>) -> Self {
+        match event {
+            ApplicationEvent::View(view, event) => Self::View(view, (&event).into()),
+            ApplicationEvent::ApplyAnimations(presentation) => Self::ApplyAnimations(presentation),
+            ApplicationEvent::Shutdown(instance) => Self::Shutdown(instance),
+            ApplicationEvent::Custom(never) => match never {},
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoteStart {
+    pub version: u32,
+    pub instance: InstanceId,
+    /// The instance parameters as JSON. `None` if the instance is restored.
+    ///
+    /// Detail: JSON values can't be deserialized by postcard, because they are self-describing.
+    pub parameters: Option<String>,
+    pub primary_monitor_scale_factor: f64,
+}
+
+impl RemoteStart {
+    pub fn creation_mode(&self) -> Result<CreationMode> {
+        if self.version != PROTOCOL_VERSION {
+            bail!(
+                "Unsupported protocol version {}, expected {PROTOCOL_VERSION}",
+                self.version
+            );
+        }
+        Ok(match &self.parameters {
+            Some(parameters) => CreationMode::New(
+                serde_json::from_str::<InstanceParameters>(parameters)
+                    .context("Parsing instance parameters")?,
+            ),
+            None => CreationMode::Restore,
+        })
+    }
+}
+
+/// Messages from the instance process to the desktop.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum InstanceMessage {
+    Submission {
+        changes: Vec<RemoteChange>,
+        pacing: RenderPacing,
+    },
+}
+
+/// The serialized form of an [`InstanceChange`].
+///
+/// [`InstanceChange::End`] has no remote form, the end of the connection ends the instance.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RemoteChange {
+    /// A run of consecutive scene changes.
+    Scene(EncodedChanges),
+    CreateView {
+        id: ViewId,
+        role: ViewRole,
+        extents: [i32; 4],
+    },
+    View(ViewId, RemoteViewChange),
+    DestroyView(ViewId),
+    Configuration(ConfigurationRequest),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RemoteViewChange {
+    Resize([i32; 4]),
+    SetTitle(String),
+    SetCursor(CursorIcon),
+}
+
+impl RemoteChange {
+    /// Encode instance changes in order. Scene changes are encoded together until the next
+    /// instance change.
+    pub fn encode_all(
+        changes: impl IntoIterator<Item = InstanceChange>,
+        encoder: &mut ChangeEncoder,
+    ) -> Vec<Self> {
+        let mut encoded = Vec::new();
+        let mut scene_changes = Vec::new();
+
+        for change in changes {
+            let change = match change {
+                InstanceChange::Scene(change) => {
+                    scene_changes.push(change);
+                    continue;
+                }
+                InstanceChange::CreateView(info) => Self::CreateView {
+                    id: info.id,
+                    role: info.role,
+                    extents: box_to_array(info.extents),
+                },
+                InstanceChange::View(id, change) => Self::View(id, change.into()),
+                InstanceChange::DestroyView(id) => Self::DestroyView(id),
+                InstanceChange::Configuration(request) => Self::Configuration(request),
+                InstanceChange::End(_) => continue,
+            };
+            flush_scene_changes(&mut scene_changes, encoder, &mut encoded);
+            encoded.push(change);
+        }
+
+        flush_scene_changes(&mut scene_changes, encoder, &mut encoded);
+        encoded
+    }
+
+    /// Decode remote changes in order. The ids of the scene changes are the ones of the instance
+    /// process.
+    pub fn decode_all(
+        changes: Vec<Self>,
+        decoder: &mut ChangeDecoder,
+    ) -> Result<Vec<InstanceChange>> {
+        let mut decoded = Vec::with_capacity(changes.len());
+        for change in changes {
+            match change {
+                Self::Scene(changes) => decoded.extend(
+                    decoder
+                        .decode(changes)?
+                        .into_iter()
+                        .map(InstanceChange::Scene),
+                ),
+                Self::CreateView { id, role, extents } => {
+                    decoded.push(InstanceChange::CreateView(ViewCreationInfo {
+                        id,
+                        role,
+                        extents: box_from_array(extents),
+                    }))
+                }
+                Self::View(id, change) => decoded.push(InstanceChange::View(id, change.into())),
+                Self::DestroyView(id) => decoded.push(InstanceChange::DestroyView(id)),
+                Self::Configuration(request) => {
+                    decoded.push(InstanceChange::Configuration(request))
+                }
+            }
+        }
+        Ok(decoded)
+    }
+}
+
+fn flush_scene_changes(
+    scene_changes: &mut Vec<SceneChange>,
+    encoder: &mut ChangeEncoder,
+    encoded: &mut Vec<RemoteChange>,
+) {
+    if !scene_changes.is_empty() {
+        encoded.push(RemoteChange::Scene(encoder.encode(scene_changes)));
+        scene_changes.clear();
+    }
+}
+
+impl From<ViewChange> for RemoteViewChange {
+    fn from(change: ViewChange) -> Self {
+        match change {
+            ViewChange::Resize(extents) => Self::Resize(box_to_array(extents)),
+            ViewChange::SetTitle(title) => Self::SetTitle(title),
+            ViewChange::SetCursor(cursor) => Self::SetCursor(cursor),
+        }
+    }
+}
+
+impl From<RemoteViewChange> for ViewChange {
+    fn from(change: RemoteViewChange) -> Self {
+        match change {
+            RemoteViewChange::Resize(extents) => Self::Resize(box_from_array(extents)),
+            RemoteViewChange::SetTitle(title) => Self::SetTitle(title),
+            RemoteViewChange::SetCursor(cursor) => Self::SetCursor(cursor),
+        }
+    }
+}
+
+fn box_to_array(extents: BoxPx) -> [i32; 4] {
+    [extents.min.x, extents.min.y, extents.max.x, extents.max.y]
+}
+
+fn box_from_array([left, top, right, bottom]: [i32; 4]) -> BoxPx {
+    BoxPx::new((left, top).into(), (right, bottom).into())
+}
+
+/// The serialized form of a [`ViewEvent`].
+///
+/// Devices are identified by a hash of their [`DeviceId`], because device ids can't be created
+/// outside of winit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RemoteViewEvent {
+    Resized(u32, u32),
+    RedrawRequested,
+    CloseRequested,
+    DroppedFile(PathBuf),
+    HoveredFile(PathBuf),
+    HoveredFileCancelled,
+    Focused(bool),
+    KeyboardInput {
+        device: u64,
+        event: RemoteKeyEvent,
+        is_synthetic: bool,
+    },
+    ModifiersChanged(ModifiersState),
+    Ime(Ime),
+    CursorMoved {
+        device: u64,
+        position: Point,
+    },
+    CursorEntered {
+        device: u64,
+    },
+    CursorLeft {
+        device: u64,
+    },
+    MouseWheel {
+        device: u64,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+    },
+    MouseInput {
+        device: u64,
+        state: ElementState,
+        button: MouseButton,
+    },
+}
+
+/// The platform independent part of a [`winit::event::KeyEvent`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteKeyEvent {
+    pub physical_key: PhysicalKey,
+    pub logical_key: Key,
+    pub text: Option<String>,
+    pub location: KeyLocation,
+    pub state: ElementState,
+    pub repeat: bool,
+}
+
+impl RemoteViewEvent {
+    /// See [`ViewEvent::pressed_key`].
+    pub fn pressed_key(&self) -> Option<&Key> {
+        match self {
+            Self::KeyboardInput {
+                event:
+                    RemoteKeyEvent {
+                        logical_key,
+                        state: ElementState::Pressed,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => Some(logical_key),
+            _ => None,
+        }
+    }
+}
+
+impl From<&ViewEvent> for RemoteViewEvent {
+    fn from(event: &ViewEvent) -> Self {
+        match event {
+            ViewEvent::Resized(size) => Self::Resized(size.width, size.height),
+            ViewEvent::RedrawRequested => Self::RedrawRequested,
+            ViewEvent::CloseRequested => Self::CloseRequested,
+            ViewEvent::DroppedFile(path) => Self::DroppedFile(path.clone()),
+            ViewEvent::HoveredFile(path) => Self::HoveredFile(path.clone()),
+            ViewEvent::HoveredFileCancelled => Self::HoveredFileCancelled,
+            ViewEvent::Focused(focused) => Self::Focused(*focused),
+            ViewEvent::KeyboardInput {
+                device_id,
+                event,
+                is_synthetic,
+            } => Self::KeyboardInput {
+                device: device_key(device_id),
+                event: RemoteKeyEvent {
+                    physical_key: event.physical_key,
+                    logical_key: event.logical_key.clone(),
+                    text: event.text.as_ref().map(|text| text.to_string()),
+                    location: event.location,
+                    state: event.state,
+                    repeat: event.repeat,
+                },
+                is_synthetic: *is_synthetic,
+            },
+            ViewEvent::ModifiersChanged(modifiers) => Self::ModifiersChanged(modifiers.state()),
+            ViewEvent::Ime(ime) => Self::Ime(ime.clone()),
+            ViewEvent::CursorMoved {
+                device_id,
+                position,
+            } => Self::CursorMoved {
+                device: device_key(device_id),
+                position: *position,
+            },
+            ViewEvent::CursorEntered { device_id } => Self::CursorEntered {
+                device: device_key(device_id),
+            },
+            ViewEvent::CursorLeft { device_id } => Self::CursorLeft {
+                device: device_key(device_id),
+            },
+            ViewEvent::MouseWheel {
+                device_id,
+                delta,
+                phase,
+            } => Self::MouseWheel {
+                device: device_key(device_id),
+                delta: *delta,
+                phase: *phase,
+            },
+            ViewEvent::MouseInput {
+                device_id,
+                state,
+                button,
+            } => Self::MouseInput {
+                device: device_key(device_id),
+                state: *state,
+                button: *button,
+            },
+        }
+    }
+}
+
+fn device_key(device_id: &DeviceId) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    device_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub async fn send_message(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &impl Serialize,
+) -> Result<()> {
+    let bytes = postcard::to_stdvec(message).context("Serializing message")?;
+    if bytes.len() > MAX_MESSAGE_SIZE {
+        bail!(
+            "Message of {} bytes exceeds the maximum message size of {MAX_MESSAGE_SIZE} bytes",
+            bytes.len()
+        );
+    }
+    writer
+        .write_all(&(bytes.len() as u32).to_le_bytes())
+        .await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await.context("Sending message")
+}
+
+/// Receives the next message. Returns `None` if the connection was closed.
+///
+/// Messages larger than [`MAX_MESSAGE_SIZE`] are rejected with an error before they are read.
+///
+/// Not cancel safe: A partially received message is lost when the future is dropped.
+pub async fn receive_message<T: DeserializeOwned>(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> Result<Option<T>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e).context("Receiving message"),
+    }
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        bail!(
+            "Message of {length} bytes exceeds the maximum message size of {MAX_MESSAGE_SIZE} bytes"
+        );
+    }
+    let mut bytes = vec![0u8; length];
+    reader
+        .read_exact(&mut bytes)
+        .await
+        .context("Receiving message")?;
+    let message = postcard::from_bytes(&bytes).context("Deserializing message")?;
+    Ok(Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use massive_geometry::Transform;
+    use massive_renderer::FontManager;
+    use massive_scene::{Change, Id};
+
+    use super::*;
+
+    #[test]
+    fn scene_changes_are_grouped_between_instance_changes() {
+        let fonts = FontManager::bare("en-US");
+        let view = ViewId::new();
+        let changes = vec![
+            InstanceChange::Scene(Change::Create(Id::from_raw(0), Transform::IDENTITY).into()),
+            InstanceChange::Scene(Change::Create(Id::from_raw(1), Transform::IDENTITY).into()),
+            InstanceChange::View(view, ViewChange::SetTitle("title".into())),
+            InstanceChange::Scene(Change::<Transform>::Delete(Id::from_raw(0)).into()),
+        ];
+
+        let encoded = RemoteChange::encode_all(changes, &mut ChangeEncoder::new(fonts.clone()));
+        assert!(matches!(
+            encoded.as_slice(),
+            [
+                RemoteChange::Scene(_),
+                RemoteChange::View(..),
+                RemoteChange::Scene(_)
+            ]
+        ));
+
+        let bytes = postcard::to_stdvec(&encoded).unwrap();
+        let decoded = RemoteChange::decode_all(
+            postcard::from_bytes(&bytes).unwrap(),
+            &mut ChangeDecoder::new(fonts),
+        )
+        .unwrap();
+        assert_eq!(decoded.len(), 4);
+        assert!(matches!(
+            &decoded[2],
+            InstanceChange::View(id, ViewChange::SetTitle(title)) if *id == view && title == "title"
+        ));
+        assert!(matches!(
+            &decoded[3],
+            InstanceChange::Scene(SceneChange::Transform(Change::Delete(id))) if **id == 0
+        ));
+    }
+
+    #[tokio::test]
+    async fn oversized_messages_are_rejected() {
+        let mut stream = Vec::new();
+        send_message(&mut stream, &PROTOCOL_VERSION).await.unwrap();
+        let received: Option<u32> = receive_message(&mut stream.as_slice()).await.unwrap();
+        assert_eq!(received, Some(PROTOCOL_VERSION));
+
+        let length = (MAX_MESSAGE_SIZE as u32 + 1).to_le_bytes();
+        let result = receive_message::<u32>(&mut length.as_slice()).await;
+        assert!(result.is_err());
+    }
+}
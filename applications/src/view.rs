@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use derive_more::{From, Into};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use winit::window::CursorIcon;
 
@@ -112,7 +113,7 @@ impl View {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 /// Some ideas for roles.
 pub enum ViewRole {
     #[default]
@@ -208,4 +208,18 @@ impl InputEvent for ViewEvent {
             _ => None,
         }
     }
+
+    fn key_event(&self) -> Option<&KeyEvent> {
+        match self {
+            ViewEvent::KeyboardInput { event, .. } => Some(event),
+            _ => None,
+        }
+    }
+
+    fn scroll_delta(&self) -> Option<event::MouseScrollDelta> {
+        match self {
+            ViewEvent::MouseWheel { delta, .. } => Some(*delta),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,40 @@
+//! A minimal instance that runs in its own process.
+//!
+//! Add a launcher with the parameters `{ "executable": "<path to this example>" }` to the desktop
+//! configuration to start it.
+
+use anyhow::Result;
+
+use massive_client::{RemoteEvent, RemoteInstance};
+use massive_geometry::{Color, Rect};
+use massive_renderer::FontManager;
+use massive_scene::Visual;
+use massive_shapes::{Rect as FilledRect, Shape};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    massive_client::run(FontManager::system(), hello_instance).await
+}
+
+async fn hello_instance(mut instance: RemoteInstance) -> Result<()> {
+    let mut view = instance.view((640, 480)).build()?;
+    view.set_title("Hello from another process");
+
+    let shapes: Vec<Shape> = vec![
+        FilledRect::new(
+            Rect::new((0.0, 0.0), (640.0, 480.0)),
+            Color::rgb(0.2, 0.4, 0.8),
+        )
+        .into(),
+    ];
+    let _visual = view.scene().stage(Visual::new(view.location(), shapes));
+
+    let submission = instance.frame(view.scene()).submission();
+    instance.submit(submission)?;
+
+    loop {
+        if let RemoteEvent::Shutdown(_) = instance.wait_for_event().await? {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Implement an instance in a separate process.
+//!
+//! The desktop starts the executable that is named by the `executable` parameter of a launcher and
+//! talks to it over stdin and stdout. The executable must not write anything else to stdout, logs
+//! go to stderr.
+
+mod remote_event;
+mod remote_instance;
+
+pub use remote_event::*;
+pub use remote_instance::*;
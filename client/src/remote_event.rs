@@ -0,0 +1,46 @@
+use std::mem;
+
+use massive_applications::{InstanceId, PresentationId, RemoteViewEvent, ViewId};
+use massive_util::CoalescingKey;
+
+/// The events an instance process receives from the desktop.
+#[derive(Debug, Clone)]
+pub enum RemoteEvent {
+    View(ViewId, RemoteViewEvent),
+    ApplyAnimations(PresentationId),
+    Shutdown(InstanceId),
+}
+
+impl CoalescingKey for RemoteEvent {
+    type Key = RemoteEventCoalescingKey;
+
+    fn coalescing_key(&self) -> Option<RemoteEventCoalescingKey> {
+        match self {
+            RemoteEvent::View(view_id, event) => match event {
+                RemoteViewEvent::Resized(..) => Some(RemoteEventCoalescingKey::View(
+                    *view_id,
+                    mem::discriminant(event),
+                    None,
+                )),
+                RemoteViewEvent::CursorMoved { device, .. } => {
+                    Some(RemoteEventCoalescingKey::View(
+                        *view_id,
+                        mem::discriminant(event),
+                        Some(*device),
+                    ))
+                }
+                _ => None,
+            },
+            RemoteEvent::ApplyAnimations(presentation_id) => {
+                Some(RemoteEventCoalescingKey::ApplyAnimations(*presentation_id))
+            }
+            RemoteEvent::Shutdown(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum RemoteEventCoalescingKey {
+    ApplyAnimations(PresentationId),
+    View(ViewId, mem::Discriminant<RemoteViewEvent>, Option<u64>),
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use anyhow::{Result, bail};
+use derive_more::{Deref, DerefMut};
+use log::error;
+use tokio::io;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+use massive_applications::{
+    ApplicationMessage, DesktopMessage, InstanceChange, InstanceContext, InstanceEnvironment,
+    InstanceId, InstanceMessage, InstanceSubmission, RemoteChange, receive_message, send_message,
+};
+use massive_renderer::{ChangeEncoder, FontManager};
+use massive_scene::{ChangeCollector, Scene, StageIdentityLocation};
+use massive_util::CoalescingReceiver;
+
+use crate::RemoteEvent;
+
+/// An instance that is driven by the desktop.
+///
+/// Views are created and changes are submitted through the [`InstanceContext`] it dereferences
+/// to. Events are received with [`RemoteInstance::wait_for_event`], because the events of the
+/// context are never sent.
+#[derive(Debug, Deref, DerefMut)]
+pub struct RemoteInstance {
+    #[deref]
+    #[deref_mut]
+    context: InstanceContext,
+    events: CoalescingReceiver<RemoteEvent>,
+    /// Keeps the events of the context open, so that waiting for them does not fail.
+    _context_events: UnboundedSender<ApplicationMessage>,
+}
+
+impl RemoteInstance {
+    pub async fn wait_for_event(&mut self) -> Result<RemoteEvent> {
+        self.events.recv().await
+    }
+}
+
+/// Wait for the desktop to start the instance and run it.
+///
+/// Returns after `run` returned and the final changes of the instance were sent.
+pub async fn run<F, R>(fonts: FontManager, run: F) -> Result<()>
+where
+    F: FnOnce(RemoteInstance) -> R,
+    R: Future<Output = Result<()>>,
+{
+    let mut stdin = io::stdin();
+    let Some(DesktopMessage::Start(start)) = receive_message(&mut stdin).await? else {
+        bail!("Expected the desktop to start the instance");
+    };
+    let creation_mode = start.creation_mode()?;
+
+    // The desktop attaches locations without a parent to the view parent of the instance, so an
+    // identity location stands in for it here. Its changes are sent with the first submission.
+    let root_changes = Arc::new(ChangeCollector::default());
+    let (_, view_parent) = Scene::new(root_changes.clone()).enter_identity_location();
+
+    let (submissions_tx, submissions_rx) = unbounded_channel();
+    let (context_events_tx, context_events_rx) = unbounded_channel();
+    let context = InstanceContext::new(
+        start.instance,
+        creation_mode,
+        InstanceEnvironment::new(
+            submissions_tx,
+            start.primary_monitor_scale_factor,
+            fonts.clone(),
+        ),
+        view_parent.into(),
+        context_events_rx,
+    );
+
+    // Receiving is not cancel safe, so it runs in its own task.
+    let (events_tx, events_rx) = unbounded_channel();
+    let receiver = tokio::spawn(async move {
+        loop {
+            let event = match receive_message::<DesktopMessage>(&mut stdin).await {
+                Ok(Some(DesktopMessage::Start(_))) => {
+                    error!("The desktop started the instance twice");
+                    continue;
+                }
+                Ok(Some(DesktopMessage::View(view, event))) => RemoteEvent::View(view, event),
+                Ok(Some(DesktopMessage::ApplyAnimations(presentation))) => {
+                    RemoteEvent::ApplyAnimations(presentation)
+                }
+                Ok(Some(DesktopMessage::Shutdown(instance))) => RemoteEvent::Shutdown(instance),
+                Ok(None) => break,
+                Err(e) => {
+                    error!("Failed to receive from the desktop: {e:?}");
+                    break;
+                }
+            };
+            if events_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    let instance = RemoteInstance {
+        context,
+        events: events_rx.into(),
+        _context_events: context_events_tx,
+    };
+
+    let (result, sent) = tokio::join!(
+        run(instance),
+        send_submissions(submissions_rx, &root_changes, fonts)
+    );
+    receiver.abort();
+    result?;
+    sent
+}
+
+/// Send the submissions of the context until the instance ends.
+async fn send_submissions(
+    mut submissions: UnboundedReceiver<(InstanceId, InstanceSubmission)>,
+    root_changes: &ChangeCollector,
+    fonts: FontManager,
+) -> Result<()> {
+    let mut stdout = io::stdout();
+    let mut encoder = ChangeEncoder::new(fonts);
+    let mut root_changes = Some(root_changes.take_all());
+
+    while let Some((_, submission)) = submissions.recv().await {
+        let (changes, pacing) = submission.into_parts();
+        let changes: Vec<_> = root_changes
+            .take()
+            .into_iter()
+            .flat_map(|changes| changes.release())
+            .map(InstanceChange::Scene)
+            .chain(changes.release())
+            .collect();
+        let end = changes
+            .iter()
+            .any(|change| matches!(change, InstanceChange::End(_)));
+
+        let changes = RemoteChange::encode_all(changes, &mut encoder);
+        send_message(
+            &mut stdout,
+            &InstanceMessage::Submission { changes, pacing },
+        )
+        .await?;
+
+        if end {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -6,15 +6,24 @@ use derive_more::Debug;
 
 use massive_applications::InstanceContext;
 
+use crate::remote_instance::{REMOTE_APPLICATION, run_remote_instance};
+
 #[derive(Debug)]
 pub struct ApplicationRegistry {
     applications: HashMap<String, Application>,
 }
 
 impl ApplicationRegistry {
+    /// Instances that run in a separate process are always available under [`REMOTE_APPLICATION`].
     pub fn new(applications: Vec<Application>) -> Self {
+        let remote = Application::new(REMOTE_APPLICATION, run_remote_instance);
         Self {
-            applications: HashMap::from_iter(applications.into_iter().map(|a| (a.name.clone(), a))),
+            applications: HashMap::from_iter(
+                [remote]
+                    .into_iter()
+                    .chain(applications)
+                    .map(|a| (a.name.clone(), a)),
+            ),
         }
     }
 
@@ -1,4 +1,6 @@
 use std::convert::Infallible;
+use std::future;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -21,13 +23,15 @@ use massive_util::CollectingVec;
 use crate::DesktopEnvironment;
 use crate::desktop_system::change::{Changes, DesktopChange};
 use crate::desktop_system::{
-    Commands, DesktopCommand, DesktopSystem, ProjectCommand, TransactionEffectsMode,
+    Commands, DesktopCommand, DesktopSystem, Keymap, ProjectCommand, TransactionEffectsMode,
 };
 use crate::instance_manager::InstanceManager;
 use crate::instance_presenter::InstanceRoot;
+use crate::instance_supervisor::{InstanceSupervisor, Supervision};
 use crate::projects::{
-    LaunchProfile, LaunchProfileId, Launcher, LauncherMode, MatrixPlacement, Project,
-    ProjectConfiguration, ProjectId, ProjectProperties, ProjectSet,
+    ConfigurationWatcher, LaunchProfile, LaunchProfileId, Launcher, LauncherMode, MatrixPlacement,
+    Project, ProjectConfiguration, ProjectId, ProjectProperties, ProjectSet, RestartPolicy,
+    Session, reload_commands,
 };
 use crate::window_state::WindowPresentationState;
 use crate::window_state::WindowState;
@@ -46,7 +50,16 @@ pub struct Desktop {
 
     instance_manager: InstanceManager,
     instance_submissions: UnboundedReceiver<(InstanceId, InstanceSubmission)>,
+    /// Decides about restarting instances that ended on their own.
+    supervisor: InstanceSupervisor,
     context: ApplicationContext,
+
+    /// Where the project configuration is saved to after it was changed.
+    projects_dir: Option<PathBuf>,
+    /// Reports external changes of the project configuration.
+    configuration_watcher: Option<ConfigurationWatcher>,
+    /// The project the desktop creates at startup, which is not part of the configuration.
+    primary_project: ProjectId,
 }
 
 #[derive(Debug)]
@@ -54,6 +67,8 @@ enum DesktopEvent {
     ApplicationEvents(Vec<ApplicationEvent<Infallible>>),
     InstanceSubmission(InstanceId, InstanceSubmission),
     InstanceEnded(InstanceId, massive_shell::Result<()>),
+    RestartInstance(InstanceId),
+    ConfigurationChanged,
 }
 
 impl Desktop {
@@ -62,6 +77,8 @@ impl Desktop {
 
         let projects_dir = env.projects_dir();
         let project_configuration = ProjectConfiguration::from_dir(projects_dir.as_deref())?;
+        let keymap = Keymap::from_specs(&project_configuration.keybindings)
+            .context("Failed to load the keybindings")?;
         let project_set = ProjectSet::from_configuration(project_configuration)?;
 
         // Create the font manager - shared between desktop and instances
@@ -129,6 +146,7 @@ impl Desktop {
         let mut system = DesktopSystem::new(
             env,
             fonts.clone(),
+            keymap,
             default_size,
             &scene,
             context.movement_runtime(),
@@ -168,6 +186,8 @@ impl Desktop {
             TransactionEffectsMode::Setup,
             window_state.inner_size,
         )?;
+        // Setup reproduces the configuration, there is nothing to save.
+        system.take_configuration_changed();
         let mut presentation_state = WindowPresentationState::default();
         {
             let mut window_context =
@@ -175,7 +195,7 @@ impl Desktop {
             finalize_frame(&mut system, frame, &mut window_context)?;
         }
 
-        let desktop = Self {
+        let mut desktop = Self {
             scene,
             window,
             window_state,
@@ -185,8 +205,13 @@ impl Desktop {
             event_manager,
             instance_manager,
             instance_submissions: submissions_rx,
+            supervisor: InstanceSupervisor::default(),
             context,
+            configuration_watcher: projects_dir.as_deref().map(ConfigurationWatcher::new),
+            projects_dir,
+            primary_project: primary_project.project,
         };
+        desktop.restore_session(primary_instance)?;
         Ok(desktop)
     }
 
@@ -201,13 +226,30 @@ impl Desktop {
                     DesktopEvent::ApplicationEvents(events?)
                 }
 
-                instance = self.instance_manager.join_next() => {
+                // Crashed instances keep the desktop running without any instance running.
+                instance = self.instance_manager.join_next(), if !self.instance_manager.is_empty() => {
                     let (instance_id, instance_result) = instance?;
                     DesktopEvent::InstanceEnded(instance_id, instance_result)
                 }
+
+                instance = self.supervisor.next_restart() => {
+                    DesktopEvent::RestartInstance(instance)
+                }
+
+                _ = configuration_changed(&mut self.configuration_watcher) => {
+                    DesktopEvent::ConfigurationChanged
+                }
             };
 
+            if let DesktopEvent::ConfigurationChanged = event {
+                // The reload transacts and submits its own frame.
+                self.reload_configuration()?;
+                continue;
+            }
+
             let mut frame = self.context.frame(&self.scene);
+            // Set when the desktop exits after the frame is submitted.
+            let mut exit = false;
 
             match event {
                 DesktopEvent::ApplicationEvents(events) => {
@@ -273,7 +315,8 @@ impl Desktop {
                             ApplicationEvent::Shutdown(_) => {
                                 // Robustness: Clarify if and when this happens.
                                 info!("Desktop shutdown request received");
-                                return Ok(());
+                                exit = true;
+                                break;
                             }
                             ApplicationEvent::Custom(event) => match event {},
                         }
@@ -292,12 +335,19 @@ impl Desktop {
                         self.instance_submissions.len()
                     );
 
+                    if let Err(e) = &instance_result {
+                        log::warn!("Instance returned error: {e}");
+                    }
+
+                    // Did it end on its own? -> Its launcher's restart policy decides.
                     if self.system.is_present(&instance_id) {
-                        // Did it end on its own? -> Act as if the user ended it.
-                        // Robustness: This should probably handled differently.
-                        let changes = self
-                            .system
-                            .plan(DesktopCommand::StopInstance(instance_id), &self.scene)?;
+                        let changes = supervise(
+                            &mut self.supervisor,
+                            &self.system,
+                            &self.scene,
+                            instance_id,
+                            instance_result,
+                        )?;
                         self.system.transact(
                             changes,
                             &mut frame,
@@ -307,23 +357,32 @@ impl Desktop {
                         )?;
                     }
 
-                    // Feature: Display the error to the user?
-
-                    if let Err(e) = instance_result {
-                        log::warn!("Instance returned error: {e}");
-                    }
-
                     // If all instances have finished, exit
-                    if self.instance_manager.is_empty() {
+                    if self.instance_manager.is_empty() && !self.system.has_instances() {
                         let queued_submissions = self.instance_submissions.len();
                         if queued_submissions > 0 {
                             error!(
                                 "Desktop exiting with queued instance submissions after all instances finished: queued_submissions={queued_submissions}"
                             );
                         }
-                        return Ok(());
+                        exit = true;
                     }
                 }
+                DesktopEvent::RestartInstance(instance) => {
+                    info!("Restarting instance: {instance:?}");
+                    let changes = self
+                        .system
+                        .plan(DesktopCommand::RestartInstance(instance), &self.scene)?;
+                    self.system.transact(
+                        changes,
+                        &mut frame,
+                        &mut self.instance_manager,
+                        None,
+                        self.window_state.inner_size,
+                    )?;
+                }
+                // Reloaded before the frame was created.
+                DesktopEvent::ConfigurationChanged => {}
             }
 
             {
@@ -334,10 +393,221 @@ impl Desktop {
                 );
                 finalize_frame(&mut self.system, frame, &mut window_context)?;
             }
+
+            // The frame borrows the context and the scene until it is submitted.
+            if self.system.take_configuration_changed() {
+                self.save_configuration();
+            }
+
+            // Stopped instances must not be restarted anymore.
+            for instance in self.system.take_stopped_instances() {
+                self.supervisor.forget(instance);
+            }
+
+            if exit {
+                self.save_session();
+                return Ok(());
+            }
         }
     }
 }
 
+impl Desktop {
+    /// Save the live project configuration to the project directory.
+    ///
+    /// The caller decides when to save by taking [`DesktopSystem::take_configuration_changed`],
+    /// the flag is not taken here again.
+    ///
+    /// Failing to save must not take the desktop and its running instances down, so errors are
+    /// logged only.
+    fn save_configuration(&mut self) {
+        let Some(projects_dir) = &self.projects_dir else {
+            return;
+        };
+
+        let result = self
+            .system
+            .project_set(self.primary_project)
+            .to_configuration()
+            .and_then(|configuration| {
+                ProjectConfiguration {
+                    keybindings: self.system.keymap().specs().to_vec(),
+                    ..configuration
+                }
+                .save_to_dir(projects_dir)
+            });
+
+        if let Some(watcher) = &mut self.configuration_watcher {
+            watcher.mark_seen();
+        }
+
+        match result {
+            Ok(()) => info!("Saved project configuration to {}", projects_dir.display()),
+            Err(e) => error!("Failed to save project configuration: {e:?}"),
+        }
+    }
+
+    /// Save the running instances, so that the next run can start them again.
+    ///
+    /// Like the configuration, errors are logged only.
+    fn save_session(&self) {
+        let Some(projects_dir) = &self.projects_dir else {
+            return;
+        };
+
+        match self
+            .system
+            .session(self.primary_project)
+            .save_to_dir(projects_dir)
+        {
+            Ok(()) => info!("Saved session to {}", projects_dir.display()),
+            Err(e) => error!("Failed to save session: {e:?}"),
+        }
+    }
+
+    /// Restore the session saved in the project directory.
+    ///
+    /// A broken session must not prevent the desktop from starting, so only the error of
+    /// submitting the frame is returned.
+    fn restore_session(&mut self, primary_instance: InstanceId) -> Result<()> {
+        let Some(projects_dir) = &self.projects_dir else {
+            return Ok(());
+        };
+        let session = match Session::from_dir(projects_dir) {
+            Ok(session) => session,
+            Err(e) => {
+                error!("Failed to load the session: {e:?}");
+                return Ok(());
+            }
+        };
+        if session == Session::default() {
+            return Ok(());
+        }
+
+        let mut frame = self.context.frame(&self.scene);
+
+        self.system.restore_session(
+            &session,
+            primary_instance,
+            &mut frame,
+            &mut self.instance_manager,
+            self.window_state.inner_size,
+        );
+
+        let mut window_context = WindowContext::new(
+            &self.window,
+            &mut self.window_presentation_state,
+            &mut self.renderer,
+        );
+        finalize_frame(&mut self.system, frame, &mut window_context)
+    }
+
+    /// Apply the changes of the configuration file to the live projects.
+    ///
+    /// Launchers that did not change keep their instances. Nothing is applied if the new
+    /// configuration can not be loaded. The reload commands build on each other and are applied one
+    /// after the other: If one fails, the ones before it stay applied and the rest is skipped. The
+    /// configuration file is not written back in either case.
+    ///
+    /// Configuration errors are logged only, the returned error is about submitting the frame.
+    fn reload_configuration(&mut self) -> Result<()> {
+        let Some(projects_dir) = &self.projects_dir else {
+            return Ok(());
+        };
+        info!(
+            "Reloading project configuration from {}",
+            projects_dir.display()
+        );
+
+        let loaded = ProjectConfiguration::from_dir(Some(projects_dir)).and_then(|configuration| {
+            let keymap = Keymap::from_specs(&configuration.keybindings)
+                .context("Failed to load the keybindings")?;
+            Ok((keymap, ProjectSet::from_configuration(configuration)?))
+        });
+        let (keymap, reloaded) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                error!("Failed to load the project configuration, nothing was reloaded: {e:?}");
+                return Ok(());
+            }
+        };
+        let live = self.system.project_set(self.primary_project);
+        let commands = reload_commands(&live, &reloaded);
+        let command_count = commands.len();
+
+        let mut frame = self.context.frame(&self.scene);
+
+        // Every command is planned against the result of the previous one.
+        for (applied, command) in commands.into_iter().enumerate() {
+            let result = self
+                .system
+                .plan(DesktopCommand::Project(command), &self.scene)
+                .and_then(|changes| {
+                    self.system.transact(
+                        changes,
+                        &mut frame,
+                        &mut self.instance_manager,
+                        None,
+                        self.window_state.inner_size,
+                    )
+                });
+            if let Err(e) = result {
+                error!(
+                    "Failed to reload the project configuration, the projects are partially reloaded ({applied} of {command_count} changes applied): {e:?}"
+                );
+                break;
+            }
+        }
+        self.system.set_keymap(keymap);
+
+        // The configuration file is where the changes came from, don't write it back.
+        self.system.take_configuration_changed();
+
+        let mut window_context = WindowContext::new(
+            &self.window,
+            &mut self.window_presentation_state,
+            &mut self.renderer,
+        );
+        finalize_frame(&mut self.system, frame, &mut window_context)
+    }
+}
+
+/// Plan what happens to an instance that ended on its own.
+fn supervise(
+    supervisor: &mut InstanceSupervisor,
+    system: &DesktopSystem,
+    scene: &Scene,
+    instance: InstanceId,
+    result: massive_shell::Result<()>,
+) -> Result<Changes> {
+    let policy = system.restart_policy(instance);
+    let error = result.err().map(|e| format!("{e:#}"));
+    let supervision =
+        supervisor.instance_ended(instance, policy, error, tokio::time::Instant::now());
+
+    Ok(match supervision {
+        Supervision::Stop => {
+            // Act as if the user ended it.
+            system.plan(DesktopCommand::StopInstance(instance), scene)?
+        }
+        Supervision::Crashed { message } => DesktopChange::ShowInstanceCrash {
+            instance,
+            message,
+            restarting: false,
+        }
+        .into(),
+        Supervision::Restart { message, delay } => {
+            info!("Restarting instance {instance:?} in {delay:?}");
+            DesktopChange::ShowInstanceCrash {
+                instance,
+                message,
+                restarting: true,
+            }
+            .into()
+        }
+    })
+}
+
 /// Push everything out.
 ///
 /// Update the camera, pacing, submit the frame, and update the window presentation.
@@ -370,6 +640,7 @@ struct WindowContext<'a> {
 
 #[derive(Debug)]
 struct PrimaryProject {
+    project: ProjectId,
     primary_launcher: LaunchProfileId,
     commands: CollectingVec<ProjectCommand>,
 }
@@ -396,11 +667,13 @@ fn primary_project() -> PrimaryProject {
             mode: LauncherMode::Band,
             tags: Vec::new(),
             params: Default::default(),
+            restart: RestartPolicy::Never,
         },
         placement: MatrixPlacement { column: 0, row: 0 },
     };
 
     PrimaryProject {
+        project: primary_project,
         primary_launcher,
         commands,
     }
@@ -442,3 +715,11 @@ fn launcher_commands(
         placement: launcher.placement,
     })
 }
+
+/// Waits for the next change of the configuration, never completes without a watcher.
+async fn configuration_changed(watcher: &mut Option<ConfigurationWatcher>) {
+    match watcher {
+        Some(watcher) => watcher.changed().await,
+        None => future::pending().await,
+    }
+}
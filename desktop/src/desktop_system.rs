@@ -12,18 +12,24 @@ pub mod change;
 mod change_surface;
 mod command_dispatch;
 mod commands;
+mod configuration_snapshot;
 mod effects;
 mod event_forwarding;
 mod focus_input;
 mod focus_path_ext;
 mod fullscreen;
 mod hierarchy_focus;
+mod keymap;
 mod layout_algorithm;
 mod layout_effects;
 mod layout_state;
 mod navigation;
 mod presentation;
+mod session_snapshot;
+#[cfg(test)]
+mod test_harness;
 mod topology;
+mod undo_history;
 
 use anyhow::Result;
 use derive_more::Debug;
@@ -33,7 +39,7 @@ use std::collections::{HashSet, VecDeque};
 use std::mem;
 use std::time::Instant;
 
-use massive_animation::{Animated, MovementRuntime};
+use massive_animation::{MovementRuntime, SpringAnimated};
 use massive_applications::{InstanceId, ViewId};
 use massive_geometry::{PixelCamera, SizePx};
 use massive_layout::{LayoutTopology, Placement};
@@ -44,17 +50,21 @@ use massive_shell::{FontManager, Frame, Scene};
 pub use commands::{DesktopCommand, ProjectCommand};
 pub use effects::Effects;
 pub use fullscreen::fullscreen_scale;
+pub use keymap::Keymap;
 use layout_algorithm::DesktopLayoutAlgorithm;
 pub use layout_algorithm::place_container_children;
 use layout_state::DesktopLayoutState;
 pub(crate) use navigation::NavigationControl;
+use undo_history::UndoHistory;
 
 use crate::desktop_presenter::DesktopPresenter;
 use crate::desktop_system::change_surface::{ChangeSurface, TargetSet};
 use crate::focus_path::{FocusPath, PathResolver};
 use crate::instance_manager::InstanceManager;
 use crate::instance_presenter::{InstancePresenter, ViewWindowState};
-use crate::projects::{LaunchProfileId, LauncherPresenter, ProjectId, ProjectPresenter};
+use crate::projects::{
+    LaunchProfileId, LauncherPresenter, ProjectId, ProjectPresenter, RestartPolicy,
+};
 use crate::{DesktopEnvironment, EventRouter, Map, MatrixPositions, OrderedHierarchy};
 use change::{Changes, DesktopChange};
 use effects::DesktopEffect;
@@ -115,7 +125,19 @@ pub type Commands = CollectingVec<DesktopCommand>;
 /// the system is optional and depends on the currently focused target.
 ///
 /// The system should show when the focus depth is changed, so that the user knows them.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum::EnumCount, strum::FromRepr)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    strum::EnumCount,
+    strum::FromRepr,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
 #[repr(u8)]
 pub enum FocusDepth {
     InstanceFullScreen,
@@ -144,6 +166,7 @@ pub enum KeyboardFocusReason {
     PresentInstance,
     Navigate,
     PromotePrimaryView,
+    RestoreSession,
 }
 
 impl KeyboardFocusReason {
@@ -153,7 +176,8 @@ impl KeyboardFocusReason {
             KeyboardFocusReason::InputTransition
             | KeyboardFocusReason::StopInstanceReplacement
             | KeyboardFocusReason::PresentInstance
-            | KeyboardFocusReason::PromotePrimaryView => true,
+            | KeyboardFocusReason::PromotePrimaryView
+            | KeyboardFocusReason::RestoreSession => true,
         }
     }
 }
@@ -196,7 +220,8 @@ pub struct DesktopSystem {
     default_panel_size: SizePx,
 
     event_router: EventRouter<DesktopTarget>,
-    camera: Animated<PixelCamera>,
+    keymap: Keymap,
+    camera: SpringAnimated<PixelCamera>,
     focus_depth: FocusDepth,
     navigation_control: NavigationControl,
     /// Focus-change measures deferred until pointer buttons are released and the camera unlocks.
@@ -207,6 +232,12 @@ pub struct DesktopSystem {
 
     #[debug(skip)]
     layout_state: DesktopLayoutState,
+    /// Undo / redo of the edits made through configuration requests.
+    undo_history: UndoHistory,
+    /// Set when project changes were applied since the last call to `take_configuration_changed`.
+    configuration_changed: bool,
+    /// Instances that were shut down since the last call to `take_stopped_instances`.
+    stopped_instances: Vec<InstanceId>,
 
     desktop_presenter: DesktopPresenter,
     aggregates: Aggregates,
@@ -246,6 +277,7 @@ impl DesktopSystem {
     pub fn new(
         env: DesktopEnvironment,
         fonts: FontManager,
+        keymap: Keymap,
         default_panel_size: SizePx,
         scene: &Scene,
         movement_runtime: &mut MovementRuntime,
@@ -267,12 +299,16 @@ impl DesktopSystem {
             default_panel_size,
 
             event_router,
+            keymap,
             camera: PixelCamera::default().into(),
             focus_depth: FocusDepth::default(),
             navigation_control: NavigationControl::default(),
             deferred_focus_launcher_measures: Default::default(),
             deferred_camera_move: false,
             layout_state,
+            undo_history: UndoHistory::default(),
+            configuration_changed: false,
+            stopped_instances: Vec::new(),
 
             desktop_presenter,
             aggregates: Aggregates::new(OrderedHierarchy::default()),
@@ -304,7 +340,14 @@ impl DesktopSystem {
         {
             let mut changes: VecDeque<DesktopChange> = changes.into_iter().collect();
             while let Some(change) = changes.pop_front() {
-                let output = self.apply_change(change, frame, instance_manager)?;
+                let output = match self.apply_change(change, frame, instance_manager) {
+                    Ok(output) => output,
+                    Err(e) => {
+                        // An edit that failed midway must not block the next one.
+                        self.undo_history.abort();
+                        return Err(e);
+                    }
+                };
                 // TODO: I think Changes should support a DoubleEndedIterator.
                 for new_change in output
                     .changes
@@ -343,7 +386,7 @@ impl DesktopSystem {
             self.deferred_camera_move |= update_camera;
             update_camera = false;
             // Lock camera motion immediately, including already running camera animations.
-            // Ergonomics: There should probably be a function for that in `Animated`.
+            // Ergonomics: There should probably be a function for that in `SpringAnimated`.
             let camera = *self.camera.proceed(frame.animation_time());
             self.camera.snap(camera);
         }
@@ -385,6 +428,23 @@ impl DesktopSystem {
         self.aggregates.instances.contains_key(instance)
     }
 
+    /// Are instances presented, including crashed ones?
+    pub fn has_instances(&self) -> bool {
+        !self.aggregates.instances.is_empty()
+    }
+
+    /// The restart policy of the launcher `instance` belongs to.
+    pub fn restart_policy(&self, instance: InstanceId) -> RestartPolicy {
+        let launcher = self.aggregates.hierarchy.launcher_of_instance(instance);
+        self.aggregates.launchers[&launcher].profile().restart
+    }
+
+    /// The instances that were shut down since the last call, so that their supervision can be
+    /// forgotten.
+    pub fn take_stopped_instances(&mut self) -> Vec<InstanceId> {
+        mem::take(&mut self.stopped_instances)
+    }
+
     pub fn camera(&mut self, instant: Instant) -> &PixelCamera {
         self.camera.proceed(instant)
     }
@@ -528,3 +588,31 @@ fn convert_change_surface_to_effects(surface: ChangeSurface) -> Effects {
         .map(DesktopEffect::Measure)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::test_harness::TestDesktop;
+    use super::*;
+
+    #[tokio::test]
+    async fn stopped_instances_are_taken_once() -> Result<()> {
+        let mut desktop = TestDesktop::new()?;
+        let project = desktop.add_project("Work")?;
+        let launcher = desktop.add_launcher(project, "Terminal", 0)?;
+        let instance: InstanceId = Uuid::new_v4().into();
+        desktop.run(DesktopCommand::StartInstance {
+            launcher,
+            instance,
+            root: None,
+            parameters: Default::default(),
+        })?;
+        assert!(desktop.system.take_stopped_instances().is_empty());
+
+        desktop.run(DesktopCommand::StopInstance(instance))?;
+        assert_eq!(desktop.system.take_stopped_instances(), [instance]);
+        assert!(desktop.system.take_stopped_instances().is_empty());
+        Ok(())
+    }
+}
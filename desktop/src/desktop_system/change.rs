@@ -3,6 +3,7 @@ use massive_geometry::{SizePx, Vector3};
 use massive_util::CollectingVec;
 
 use super::KeyboardFocusReason;
+use super::undo_history::{EditKind, UndoStep};
 use crate::desktop_system::FocusDepth;
 use crate::event_router::EventTransitions;
 use crate::instance_presenter::InstanceRoot;
@@ -36,6 +37,15 @@ pub enum DesktopChange {
         launcher: LaunchProfileId,
         instance: InstanceId,
     },
+    /// Show why an instance ended on its own in place of its view.
+    ShowInstanceCrash {
+        instance: InstanceId,
+        message: String,
+        /// The instance is restarted automatically.
+        restarting: bool,
+    },
+    /// Prepare the presenter of a crashed instance for the view of its next run.
+    RestartInstance(InstanceId),
     SetFocus {
         // None: Completely removes the focus from the application.
         target: Option<DesktopTarget>,
@@ -51,6 +61,7 @@ pub enum DesktopChange {
     Topology(TopologyChange),
     ForwardEvents(EventTransitions<DesktopTarget>),
     IntegrateInstanceSubmission(InstanceId, InstanceSubmission),
+    History(HistoryChange),
 }
 
 #[derive(Debug)]
@@ -78,7 +89,10 @@ pub enum ProjectChange {
         launcher: LaunchProfileId,
         placement: MatrixPlacement,
     },
-    RemoveLauncher(LaunchProfileId),
+    RemoveLauncher {
+        project: ProjectId,
+        launcher: LaunchProfileId,
+    },
     RemoveSlot {
         project: ProjectId,
         placement: MatrixPlacement,
@@ -87,6 +101,18 @@ pub enum ProjectChange {
     SetStartupProfile(Option<LaunchProfileId>),
 }
 
+#[derive(Debug)]
+pub enum HistoryChange {
+    /// Start recording the inverses of the following project changes.
+    BeginEdit(EditKind),
+    /// Stop recording and push the edit to the history.
+    CommitEdit,
+    Undo,
+    Redo,
+    /// Plan and apply one inverse step of a recorded edit.
+    Revert(UndoStep),
+}
+
 /// Constructs the change(s) for a focus transition.
 ///
 /// Emits `SetFocus`, and — when the focus reason resets navigation affinity — a sibling
@@ -133,3 +159,9 @@ impl From<ProjectChange> for DesktopChange {
         Self::Project(value)
     }
 }
+
+impl From<HistoryChange> for DesktopChange {
+    fn from(value: HistoryChange) -> Self {
+        Self::History(value)
+    }
+}
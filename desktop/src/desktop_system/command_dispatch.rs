@@ -10,8 +10,9 @@ use massive_shell::{Frame, Scene};
 
 use super::change::Zoom;
 use super::change::set_focus;
-use super::change::{Changes, DesktopChange, ProjectChange, TopologyChange};
+use super::change::{Changes, DesktopChange, HistoryChange, ProjectChange, TopologyChange};
 use super::navigation::focus_depth_from_target;
+use super::undo_history::edit;
 use super::{
     ChangeSurface, DesktopCommand, DesktopSystem, DesktopTarget, FocusDepth, KeyboardFocusReason,
     ProjectCommand,
@@ -21,8 +22,9 @@ use crate::instance_manager::{InstanceManager, ViewPath};
 use crate::instance_presenter::InstanceRoot;
 use crate::projects::{
     LaunchProfile, LaunchProfileId, LauncherMode, LauncherPresenter, MatrixPlacement, ProjectId,
-    ProjectPresenter, ProjectProperties,
+    ProjectPresenter, ProjectProperties, RestartPolicy,
 };
+use crate::remote_instance::{REMOTE_APPLICATION, is_remote};
 use crate::{MatrixPositions, RemoveSlotShiftingPolicy};
 
 /// The outcome of applying a change: its effects and any follow-up changes.
@@ -158,6 +160,27 @@ impl DesktopSystem {
 
                 return Ok(changes);
             }
+            DesktopCommand::RestartInstance(instance) => {
+                // A restart may have been requested by the user before a scheduled one is due.
+                let Some(presenter) = self
+                    .aggregates
+                    .instances
+                    .get(&instance)
+                    .filter(|presenter| presenter.is_crashed())
+                else {
+                    return Ok(Changes::Empty);
+                };
+
+                return Ok([
+                    DesktopChange::RestartInstance(instance),
+                    DesktopChange::SpawnInstance {
+                        instance,
+                        root: presenter.root().clone(),
+                        parameters: presenter.parameters().clone(),
+                    },
+                ]
+                .into());
+            }
             DesktopCommand::Navigate(direction) => return self.plan_navigate(direction),
             DesktopCommand::Zoom(Zoom::In) => {
                 if let Some(focus_depth) = self.focus_depth.zoom_in() {
@@ -274,6 +297,12 @@ impl DesktopSystem {
                     Some(RemoveSlotShiftingPolicy::ShiftLeft),
                 );
             }
+            ProjectCommand::MoveLauncher { id, placement } => {
+                changes <<= ProjectChange::MoveLauncher {
+                    launcher: id,
+                    placement,
+                };
+            }
             ProjectCommand::SetStartupProfile(launch_profile_id) => {
                 changes <<= ProjectChange::SetStartupProfile(launch_profile_id)
             }
@@ -282,7 +311,7 @@ impl DesktopSystem {
         Ok(changes)
     }
 
-    fn plan_project_removal_focus(&self, project: ProjectId) -> Changes {
+    pub(super) fn plan_project_removal_focus(&self, project: ProjectId) -> Changes {
         let project_target = DesktopTarget::Project(project);
         if self
             .aggregates
@@ -298,7 +327,7 @@ impl DesktopSystem {
         Changes::Empty
     }
 
-    fn plan_remove_project(&self, project: ProjectId) -> Changes {
+    pub(super) fn plan_remove_project(&self, project: ProjectId) -> Changes {
         let mut changes = Changes::Empty;
         for launcher in self.aggregates.hierarchy.matrix_launchers(project) {
             changes += self.plan_remove_launcher(project, launcher, None);
@@ -309,7 +338,7 @@ impl DesktopSystem {
         changes
     }
 
-    fn plan_remove_launcher(
+    pub(super) fn plan_remove_launcher(
         &self,
         project: ProjectId,
         launcher: LaunchProfileId,
@@ -325,7 +354,7 @@ impl DesktopSystem {
         }
         let placement = self.aggregates.matrix_positions[&launcher];
         changes <<= TopologyChange::Remove(launcher.into());
-        changes <<= ProjectChange::RemoveLauncher(launcher);
+        changes <<= ProjectChange::RemoveLauncher { project, launcher };
         if let Some(shifting_policy) = shifting_policy {
             changes <<= ProjectChange::RemoveSlot {
                 project,
@@ -349,10 +378,15 @@ impl DesktopSystem {
                 mut parameters,
             } => {
                 // Probably pull the name of the application into SpawnInstance?
+                let application_name = if is_remote(&parameters) {
+                    REMOTE_APPLICATION
+                } else {
+                    self.env.primary_application.as_str()
+                };
                 let application = self
                     .env
                     .applications
-                    .get_named(&self.env.primary_application)
+                    .get_named(application_name)
                     .context("Internal error, application not registered")?;
 
                 parameters.insert(
@@ -370,6 +404,7 @@ impl DesktopSystem {
                 )?;
             }
             DesktopChange::ShutdownInstance(instance) => {
+                self.stopped_instances.push(instance);
                 // This might fail if StopInstance gets triggered with an instance that ended in
                 // itself (shouldn't the instance_manager keep it until we finally free it).
                 if let Err(e) = instance_manager.request_shutdown(instance) {
@@ -395,6 +430,20 @@ impl DesktopSystem {
             DesktopChange::HideInstance { launcher, instance } => {
                 self.hide_instance(launcher, instance)?;
             }
+            DesktopChange::ShowInstanceCrash {
+                instance,
+                message,
+                restarting,
+            } => {
+                return self.show_instance_crash(instance, &message, restarting, frame);
+            }
+            DesktopChange::RestartInstance(instance) => {
+                self.aggregates
+                    .instances
+                    .get_mut(&instance)
+                    .context("Instance not found (restart)")?
+                    .restart()?;
+            }
             DesktopChange::SetFocus { target, reason } => {
                 let previous_focus = self.event_router.keyboard_focus().cloned();
                 self.focus(target.as_ref(), instance_manager, reason)?;
@@ -459,6 +508,7 @@ impl DesktopSystem {
                 return Ok(ChangeOutput::measures(DesktopTarget::Desktop));
             }
             DesktopChange::Topology(change) => {
+                self.record_topology_change(&change)?;
                 let previous_focus = self.event_router.keyboard_focus().cloned();
                 // Design: That's somewhat unexpected here, that `apply_topology_change` changes
                 // focus. Can we make this more obvious? We should combine the `instance_manager`
@@ -490,8 +540,15 @@ impl DesktopSystem {
                 return self.apply_instance_submission(instance_id, instance_submission, frame);
             }
             DesktopChange::Project(project_change) => {
+                self.record_project_change(&project_change)?;
+                self.configuration_changed = true;
                 return self.apply_project_change(project_change, frame);
             }
+            DesktopChange::History(history_change) => {
+                return Ok(ChangeOutput::changes(
+                    self.apply_history_change(history_change)?,
+                ));
+            }
         }
 
         Ok(ChangeOutput::default())
@@ -603,11 +660,9 @@ impl DesktopSystem {
                     project,
                 )));
             }
-            ProjectChange::RemoveLauncher(launch_profile_id) => {
-                self.aggregates.launchers.remove(&launch_profile_id)?;
-                self.aggregates
-                    .matrix_positions
-                    .remove(&launch_profile_id)?;
+            ProjectChange::RemoveLauncher { launcher, .. } => {
+                self.aggregates.launchers.remove(&launcher)?;
+                self.aggregates.matrix_positions.remove(&launcher)?;
             }
             ProjectChange::RemoveSlot {
                 project,
@@ -753,6 +808,7 @@ impl DesktopSystem {
                             mode: LauncherMode::Visor,
                             tags: Vec::new(),
                             params: Default::default(),
+                            restart: RestartPolicy::Never,
                         },
                         placement: MatrixPlacement { column: 0, row: 0 },
                     },
@@ -763,7 +819,7 @@ impl DesktopSystem {
                     changes += self.plan_project(command)?;
                 }
 
-                Ok(ChangeOutput::changes(changes))
+                Ok(ChangeOutput::changes(edit(changes)))
             }
             ConfigurationRequest::RemoveProject { name } => {
                 let project = match name {
@@ -790,9 +846,9 @@ impl DesktopSystem {
                     None => current_project,
                 };
 
-                Ok(ChangeOutput::changes(
+                Ok(ChangeOutput::changes(edit(
                     self.plan_project(ProjectCommand::RemoveProject(project))?,
-                ))
+                )))
             }
             ConfigurationRequest::AddLauncher => {
                 let current_launcher = self.aggregates.hierarchy.launcher_of_instance(instance);
@@ -806,6 +862,7 @@ impl DesktopSystem {
                         mode: LauncherMode::Visor,
                         tags: Vec::new(),
                         params: Default::default(),
+                        restart: RestartPolicy::Never,
                     },
                     placement: MatrixPlacement {
                         column: current_placement.column + 1,
@@ -813,7 +870,7 @@ impl DesktopSystem {
                     },
                 })?;
 
-                Ok(ChangeOutput::changes(changes))
+                Ok(ChangeOutput::changes(edit(changes)))
             }
             ConfigurationRequest::RemoveLauncher { name } => {
                 let launcher = match name {
@@ -833,9 +890,9 @@ impl DesktopSystem {
                     None => self.aggregates.hierarchy.launcher_of_instance(instance),
                 };
 
-                Ok(ChangeOutput::changes(
+                Ok(ChangeOutput::changes(edit(
                     self.plan_project(ProjectCommand::RemoveLauncher(launcher))?,
-                ))
+                )))
             }
             ConfigurationRequest::MoveLauncher { direction } => {
                 let launcher = self.aggregates.hierarchy.launcher_of_instance(instance);
@@ -864,13 +921,13 @@ impl DesktopSystem {
                     launcher,
                     placement,
                 };
-                Ok(ChangeOutput::changes(changes))
+                Ok(ChangeOutput::changes(edit(changes)))
             }
             ConfigurationRequest::PushLauncher { direction } => {
                 let launcher = self.aggregates.hierarchy.launcher_of_instance(instance);
                 let current_placement = self.aggregates.matrix_positions[&launcher];
                 match self.launcher_shift_sequence(current_project, launcher, *direction) {
-                    Ok(changes) => Ok(ChangeOutput::changes(changes)),
+                    Ok(changes) => Ok(ChangeOutput::changes(edit(changes))),
                     Err(_) => {
                         warn!(
                             "Ignoring {direction:?} launcher push from matrix position ({}, {})",
@@ -897,8 +954,12 @@ impl DesktopSystem {
                 output.surface.update_camera = true;
                 Ok(output)
             }
-            ConfigurationRequest::Undo => todo!(),
-            ConfigurationRequest::Redo => todo!(),
+            ConfigurationRequest::Undo => Ok(ChangeOutput::changes(
+                DesktopChange::History(HistoryChange::Undo).into(),
+            )),
+            ConfigurationRequest::Redo => Ok(ChangeOutput::changes(
+                DesktopChange::History(HistoryChange::Redo).into(),
+            )),
         }
     }
 
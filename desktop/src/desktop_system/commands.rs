@@ -24,6 +24,8 @@ pub enum DesktopCommand {
         parameters: InstanceParameters,
     },
     StopInstance(InstanceId),
+    /// Start a crashed instance again in its panel, with the same parameters.
+    RestartInstance(InstanceId),
 
     Navigate(Direction),
 
@@ -45,5 +47,10 @@ pub enum ProjectCommand {
         placement: MatrixPlacement,
     },
     RemoveLauncher(LaunchProfileId),
+    /// Move a launcher to a matrix slot. Launchers in the slot are not moved away.
+    MoveLauncher {
+        id: LaunchProfileId,
+        placement: MatrixPlacement,
+    },
     SetStartupProfile(Option<LaunchProfileId>),
 }
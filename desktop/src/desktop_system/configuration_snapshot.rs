@@ -0,0 +1,67 @@
+//! Snapshots the live project configuration, so that it can be persisted.
+
+use std::mem;
+
+use super::{DesktopSystem, DesktopTarget, Keymap};
+use crate::projects::{Launcher, Project, ProjectId, ProjectProperties, ProjectSet};
+
+impl DesktopSystem {
+    /// The current projects and launchers in presentation order.
+    ///
+    /// `transient_project` is skipped. It is created by the desktop itself and not part of the
+    /// configuration.
+    pub fn project_set(&self, transient_project: ProjectId) -> ProjectSet {
+        let projects = self
+            .aggregates
+            .hierarchy
+            .get_nested(&DesktopTarget::Desktop)
+            .iter()
+            .filter_map(|target| match target {
+                DesktopTarget::Project(project) if *project != transient_project => Some(*project),
+                DesktopTarget::Project(_) => None,
+                _ => panic!("Desktop children must be projects"),
+            })
+            .map(|project| self.project_snapshot(project))
+            .collect();
+
+        ProjectSet {
+            start: self.aggregates.startup_profile,
+            projects,
+        }
+    }
+
+    /// The keymap, its keybindings are saved alongside the projects.
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Returns `true` once after project changes were applied.
+    pub fn take_configuration_changed(&mut self) -> bool {
+        mem::take(&mut self.configuration_changed)
+    }
+
+    fn project_snapshot(&self, project: ProjectId) -> Project {
+        let launchers = self
+            .aggregates
+            .hierarchy
+            .matrix_launchers(project)
+            .map(|launcher| Launcher {
+                id: launcher,
+                profile: self.aggregates.launchers[&launcher].profile().clone(),
+                placement: self.aggregates.matrix_positions[&launcher],
+            })
+            .collect();
+
+        Project {
+            id: project,
+            properties: ProjectProperties {
+                name: self.aggregates.projects[&project].name().to_string(),
+            },
+            launchers,
+        }
+    }
+}
@@ -50,6 +50,11 @@ impl DesktopSystem {
                     .expect("Launcher not found");
                 return launcher.process(event);
             }
+            DesktopTarget::Instance(instance) => {
+                if let Some(presenter) = self.aggregates.instances.get_mut(&instance) {
+                    return presenter.process(instance, event);
+                }
+            }
             DesktopTarget::View(view_id) => {
                 let path = self
                     .aggregates
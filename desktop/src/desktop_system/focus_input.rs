@@ -3,13 +3,13 @@ use std::collections::HashSet;
 use anyhow::Result;
 use uuid::Uuid;
 use winit::event::ElementState;
-use winit::keyboard::{Key, NamedKey};
 
 use massive_applications::{InstanceId, InstanceParameters, ViewEvent};
 use massive_input::Event;
 use massive_renderer::RenderGeometry;
 
 use super::change::{Changes, DesktopChange, set_focus};
+use super::keymap::KeyChord;
 use super::navigation::focus_depth_from_target;
 use super::{DesktopCommand, DesktopSystem, DesktopTarget, Direction, KeyboardFocusReason};
 use crate::EventTransition;
@@ -17,7 +17,7 @@ use crate::desktop_system::change::Zoom;
 use crate::event_router::{EventTransitions, ProcessOutcome};
 use crate::hit_tester::AggregateHitTester;
 use crate::instance_manager::InstanceManager;
-use crate::projects::LaunchProfileId;
+use crate::projects::{KeyAction, LaunchProfileId};
 
 impl DesktopSystem {
     // This processes input events and converts it to a set of commands.
@@ -186,89 +186,83 @@ impl DesktopSystem {
         &self,
         event: &Event<ViewEvent>,
     ) -> Option<DesktopKeyboardShortcut> {
-        // Catch `CMD+t` and `CMD+w` if an instance has the keyboard focus.
-
-        if let ViewEvent::KeyboardInput {
+        let ViewEvent::KeyboardInput {
             event: key_event, ..
         } = event.event()
-            && key_event.state == ElementState::Pressed
-            && event.device_states().is_command()
-        {
-            // Design: Extract this part into (match `desktop_cmd_key`?)
-            let focused_path = self.focused_path();
+        else {
+            return None;
+        };
+        if key_event.state != ElementState::Pressed {
+            return None;
+        }
+
+        let chord = KeyChord::new(
+            event.device_states().keyboard_modifiers().state(),
+            &key_event.logical_key,
+        );
+        let action = self.keymap.resolve(&chord)?;
+
+        let navigate = |direction| Some(DesktopKeyboardShortcut::Navigate(direction));
 
-            // Simplify: Instance should probably return the launcher, too now.
-            if !key_event.repeat
-                && let Some(instance) = focused_path.instance()
-            {
+        match action {
+            KeyAction::NewInstance | KeyAction::NewDefaultInstance if !key_event.repeat => {
+                let instance = self.focused_path().instance()?;
                 let launcher_id = self.aggregates.hierarchy.launcher_of_instance(instance);
-                match &key_event.logical_key {
-                    // Shift results in `T`.
-                    Key::Character(c) if c.as_str().eq_ignore_ascii_case("t") => {
-                        // Design: I don't like that a) this policy is decided here, and b) that we
-                        // pull the parameters here, too.
-                        let parameters = if event.device_states().is_shift() {
-                            Default::default()
-                        } else {
-                            self.aggregates
-                                .instances
-                                .get(&instance)
-                                .expect("Focused instance has no presenter")
-                                .parameters()
-                                .clone()
-                        };
-                        return Some(DesktopKeyboardShortcut::NewInstance {
-                            launcher: launcher_id,
-                            parameters,
-                        });
-                    }
-                    Key::Character(c) if c.as_str() == "w" => {
-                        // Architecture: Shouldn't this just end the current view, and let the
-                        // instance decide then?
-                        return Some(DesktopKeyboardShortcut::CloseInstance(instance));
-                    }
-                    _ => {}
-                }
+                // Design: I don't like that a) this policy is decided here, and b) that we pull
+                // the parameters here, too.
+                let parameters = if action == KeyAction::NewDefaultInstance {
+                    Default::default()
+                } else {
+                    self.aggregates
+                        .instances
+                        .get(&instance)
+                        .expect("Focused instance has no presenter")
+                        .parameters()
+                        .clone()
+                };
+                Some(DesktopKeyboardShortcut::NewInstance {
+                    launcher: launcher_id,
+                    parameters,
+                })
             }
-
-            if !key_event.repeat
-                && key_event.logical_key == Key::Named(NamedKey::Enter)
-                && let Some(keyboard_focus) = self.event_router.keyboard_focus()
-            {
-                // Architecture: When we issue ResetZoom redundantly, we could capture the
-                // `Cmd+Enter` in situations in which it needs to be delivered to the
-                // `LauncherPresenter`. Therefore, we test upfront if the ResetZoom is needed.
+            KeyAction::CloseInstance if !key_event.repeat => {
+                // Architecture: Shouldn't this just end the current view, and let the instance
+                // decide then?
+                let instance = self.focused_path().instance()?;
+                Some(DesktopKeyboardShortcut::CloseInstance(instance))
+            }
+            KeyAction::RestartInstance if !key_event.repeat => {
+                // Passed on to the instance's view, unless there is no view to receive it.
+                let instance = self.focused_path().instance()?;
+                self.aggregates
+                    .instances
+                    .get(&instance)?
+                    .is_crashed()
+                    .then_some(DesktopKeyboardShortcut::RestartInstance(instance))
+            }
+            KeyAction::ResetZoom if !key_event.repeat => {
+                let keyboard_focus = self.event_router.keyboard_focus()?;
+                // Architecture: When we issue ResetZoom redundantly, we could capture the chord in
+                // situations in which it needs to be delivered to the `LauncherPresenter`.
+                // Therefore, we test upfront if the ResetZoom is needed.
                 let current_level = self.focus_depth;
                 let keyboard_focus_level = focus_depth_from_target(keyboard_focus);
 
-                if current_level != keyboard_focus_level {
-                    return Some(DesktopKeyboardShortcut::Zoom(Zoom::DefaultForFocused));
-                }
-            }
-
-            if let Some(direction) = match &key_event.logical_key {
-                Key::Named(NamedKey::ArrowLeft) => Some(Direction::Left),
-                Key::Named(NamedKey::ArrowRight) => Some(Direction::Right),
-                Key::Named(NamedKey::ArrowUp) => Some(Direction::Up),
-                Key::Named(NamedKey::ArrowDown) => Some(Direction::Down),
-                _ => None,
-            } {
-                if event.device_states().is_ctrl() {
-                    match direction {
-                        Direction::Up => {
-                            return Some(DesktopKeyboardShortcut::Zoom(Zoom::In));
-                        }
-                        Direction::Down => {
-                            return Some(DesktopKeyboardShortcut::Zoom(Zoom::Out));
-                        }
-                        _ => {}
-                    }
-                }
-                return Some(DesktopKeyboardShortcut::Navigate(direction));
+                (current_level != keyboard_focus_level)
+                    .then_some(DesktopKeyboardShortcut::Zoom(Zoom::DefaultForFocused))
             }
+            KeyAction::NewInstance
+            | KeyAction::NewDefaultInstance
+            | KeyAction::CloseInstance
+            | KeyAction::RestartInstance
+            | KeyAction::ResetZoom => None,
+            KeyAction::ZoomIn => Some(DesktopKeyboardShortcut::Zoom(Zoom::In)),
+            KeyAction::ZoomOut => Some(DesktopKeyboardShortcut::Zoom(Zoom::Out)),
+            KeyAction::NavigateLeft => navigate(Direction::Left),
+            KeyAction::NavigateRight => navigate(Direction::Right),
+            KeyAction::NavigateUp => navigate(Direction::Up),
+            KeyAction::NavigateDown => navigate(Direction::Down),
         }
-
-        None
     }
 }
 
@@ -296,6 +290,7 @@ pub enum DesktopKeyboardShortcut {
         parameters: InstanceParameters,
     },
     CloseInstance(InstanceId),
+    RestartInstance(InstanceId),
     Zoom(Zoom),
     Navigate(Direction),
 }
@@ -313,6 +308,7 @@ impl DesktopKeyboardShortcut {
                 parameters,
             },
             Self::CloseInstance(instance) => DesktopCommand::StopInstance(instance),
+            Self::RestartInstance(instance) => DesktopCommand::RestartInstance(instance),
             Self::Navigate(direction) => DesktopCommand::Navigate(direction),
             Self::Zoom(change) => DesktopCommand::Zoom(change),
         }
@@ -0,0 +1,295 @@
+//! Key chords and the actions they are bound to.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::{Context, Result, bail};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::projects::{KeyAction, KeybindingSpec};
+
+const DEFAULT_BINDINGS: &[(&str, KeyAction)] = &[
+    ("cmd+t", KeyAction::NewInstance),
+    ("cmd+shift+t", KeyAction::NewDefaultInstance),
+    ("cmd+w", KeyAction::CloseInstance),
+    ("cmd+r", KeyAction::RestartInstance),
+    ("cmd+enter", KeyAction::ResetZoom),
+    ("cmd+left", KeyAction::NavigateLeft),
+    ("cmd+right", KeyAction::NavigateRight),
+    ("cmd+up", KeyAction::NavigateUp),
+    ("cmd+down", KeyAction::NavigateDown),
+    ("cmd+ctrl+left", KeyAction::NavigateLeft),
+    ("cmd+ctrl+right", KeyAction::NavigateRight),
+    ("cmd+ctrl+up", KeyAction::ZoomIn),
+    ("cmd+ctrl+down", KeyAction::ZoomOut),
+];
+
+/// A set of modifiers and a key.
+///
+/// Characters are stored in lowercase, so that `Shift` does not change the key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: ModifiersState,
+    pub key: Key,
+}
+
+impl KeyChord {
+    pub fn new(modifiers: ModifiersState, key: &Key) -> Self {
+        let key = match key {
+            Key::Character(c) => Key::Character(c.to_lowercase().into()),
+            key => key.clone(),
+        };
+        Self { modifiers, key }
+    }
+
+    /// Parses chords like `cmd+shift+t`.
+    ///
+    /// `cmd`, `super`, `win`, and `meta` name the same modifier ([`DeviceStates::is_command`]).
+    /// `mod` is the platform's primary modifier: `cmd` on macOS and `ctrl` everywhere else.
+    ///
+    /// [`DeviceStates::is_command`]: massive_input::DeviceStates::is_command
+    pub fn parse(chord: &str) -> Result<Self> {
+        let mut modifiers = ModifiersState::empty();
+        let mut parts: Vec<_> = chord.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+
+        for part in parts {
+            let modifier = match part.to_lowercase().as_str() {
+                "shift" => ModifiersState::SHIFT,
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "alt" | "option" | "opt" => ModifiersState::ALT,
+                "cmd" | "command" | "super" | "win" | "meta" => ModifiersState::SUPER,
+                "mod" => primary_modifier(),
+                _ => bail!("Unknown modifier `{part}` in key chord `{chord}`"),
+            };
+            modifiers |= modifier;
+        }
+
+        let key = parse_key(key).with_context(|| format!("Invalid key chord `{chord}`"))?;
+        Ok(Self::new(modifiers, &key))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (ModifiersState::SUPER, "cmd"),
+            (ModifiersState::CONTROL, "ctrl"),
+            (ModifiersState::ALT, "alt"),
+            (ModifiersState::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match &self.key {
+            Key::Character(c) => write!(f, "{c}"),
+            key => write!(f, "{key:?}"),
+        }
+    }
+}
+
+fn primary_modifier() -> ModifiersState {
+    if cfg!(target_os = "macos") {
+        ModifiersState::SUPER
+    } else {
+        ModifiersState::CONTROL
+    }
+}
+
+fn parse_key(key: &str) -> Result<Key> {
+    let named = match key.to_lowercase().as_str() {
+        "enter" | "return" => NamedKey::Enter,
+        "tab" => NamedKey::Tab,
+        "space" => NamedKey::Space,
+        "escape" | "esc" => NamedKey::Escape,
+        "backspace" => NamedKey::Backspace,
+        "delete" | "del" => NamedKey::Delete,
+        "left" => NamedKey::ArrowLeft,
+        "right" => NamedKey::ArrowRight,
+        "up" => NamedKey::ArrowUp,
+        "down" => NamedKey::ArrowDown,
+        "home" => NamedKey::Home,
+        "end" => NamedKey::End,
+        "pageup" => NamedKey::PageUp,
+        "pagedown" => NamedKey::PageDown,
+        _ => {
+            let mut chars = key.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Key::Character(c.to_string().into())),
+                _ => bail!("Unknown key `{key}`"),
+            };
+        }
+    };
+    Ok(Key::Named(named))
+}
+
+/// Maps key chords to desktop actions.
+///
+/// The keybindings of the configuration are resolved before the defaults. A keybinding without an
+/// action removes the default binding of its chord.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyChord, KeyAction>,
+    /// The keybindings from the configuration, kept to save them back.
+    specs: Vec<KeybindingSpec>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_specs(&[]).expect("Invalid default keybindings")
+    }
+}
+
+impl Keymap {
+    /// Fails if a chord can not be parsed or if two keybindings bind the same chord to different
+    /// actions.
+    pub fn from_specs(specs: &[KeybindingSpec]) -> Result<Self> {
+        let mut bindings = HashMap::new();
+        for (chord, action) in DEFAULT_BINDINGS {
+            bindings.insert(KeyChord::parse(chord)?, *action);
+        }
+
+        let mut configured: HashMap<KeyChord, &KeybindingSpec> = HashMap::new();
+        for spec in specs {
+            let chord = KeyChord::parse(&spec.keys)?;
+            if let Some(previous) = configured.get(&chord) {
+                if previous.action != spec.action {
+                    bail!(
+                        "Conflicting keybindings for `{chord}`: `{}` and `{}`",
+                        previous.keys,
+                        spec.keys
+                    );
+                }
+                continue;
+            }
+
+            match spec.action {
+                Some(action) => bindings.insert(chord.clone(), action),
+                None => bindings.remove(&chord),
+            };
+            configured.insert(chord, spec);
+        }
+
+        Ok(Self {
+            bindings,
+            specs: specs.to_vec(),
+        })
+    }
+
+    /// Without a binding for the exact chord, `Shift` and `Alt` are ignored, so that for example
+    /// `cmd+shift+left` navigates like `cmd+left`.
+    pub fn resolve(&self, chord: &KeyChord) -> Option<KeyAction> {
+        let lenient = KeyChord {
+            modifiers: chord.modifiers - (ModifiersState::SHIFT | ModifiersState::ALT),
+            key: chord.key.clone(),
+        };
+        self.bindings
+            .get(chord)
+            .or_else(|| self.bindings.get(&lenient))
+            .copied()
+    }
+
+    pub fn specs(&self) -> &[KeybindingSpec] {
+        &self.specs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(keys: &str, action: Option<KeyAction>) -> KeybindingSpec {
+        KeybindingSpec {
+            keys: keys.into(),
+            action,
+        }
+    }
+
+    fn resolve(keymap: &Keymap, chord: &str) -> Option<KeyAction> {
+        keymap.resolve(&KeyChord::parse(chord).unwrap())
+    }
+
+    #[test]
+    fn configured_bindings_resolve_before_defaults() {
+        let keymap = Keymap::from_specs(&[
+            spec("cmd+left", None),
+            spec("cmd+alt+h", Some(KeyAction::NavigateLeft)),
+            spec("cmd+w", Some(KeyAction::ResetZoom)),
+        ])
+        .unwrap();
+
+        assert_eq!(resolve(&keymap, "cmd+left"), None);
+        assert_eq!(resolve(&keymap, "cmd+alt+h"), Some(KeyAction::NavigateLeft));
+        assert_eq!(resolve(&keymap, "cmd+w"), Some(KeyAction::ResetZoom));
+        // Untouched defaults stay.
+        assert_eq!(
+            resolve(&keymap, "cmd+right"),
+            Some(KeyAction::NavigateRight)
+        );
+    }
+
+    #[test]
+    fn chords_are_normalized() {
+        let keymap = Keymap::default();
+        let shifted_t = KeyChord::new(
+            ModifiersState::SUPER | ModifiersState::SHIFT,
+            &Key::Character("T".into()),
+        );
+
+        assert_eq!(
+            keymap.resolve(&shifted_t),
+            Some(KeyAction::NewDefaultInstance)
+        );
+        assert_eq!(resolve(&keymap, "Super + T"), Some(KeyAction::NewInstance));
+        assert_eq!(
+            KeyChord::parse("mod+t").unwrap().modifiers,
+            primary_modifier()
+        );
+    }
+
+    #[test]
+    fn shift_and_alt_are_ignored_without_an_exact_binding() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            resolve(&keymap, "cmd+shift+left"),
+            Some(KeyAction::NavigateLeft)
+        );
+        assert_eq!(
+            resolve(&keymap, "cmd+alt+down"),
+            Some(KeyAction::NavigateDown)
+        );
+        assert_eq!(
+            resolve(&keymap, "cmd+ctrl+shift+up"),
+            Some(KeyAction::ZoomIn)
+        );
+        // Exact bindings win.
+        assert_eq!(
+            resolve(&keymap, "cmd+shift+t"),
+            Some(KeyAction::NewDefaultInstance)
+        );
+        // Other modifiers are not ignored.
+        assert_eq!(resolve(&keymap, "ctrl+left"), None);
+
+        // Removing a binding removes its lenient matches, too.
+        let keymap = Keymap::from_specs(&[spec("cmd+left", None)]).unwrap();
+        assert_eq!(resolve(&keymap, "cmd+shift+left"), None);
+    }
+
+    #[test]
+    fn conflicting_bindings_are_rejected() {
+        let result = Keymap::from_specs(&[
+            spec("cmd+k", Some(KeyAction::ZoomIn)),
+            spec("win+K", Some(KeyAction::ZoomOut)),
+        ]);
+        assert!(result.is_err());
+
+        // The same binding twice is not a conflict.
+        let result = Keymap::from_specs(&[
+            spec("cmd+k", Some(KeyAction::ZoomIn)),
+            spec("super+k", Some(KeyAction::ZoomIn)),
+        ]);
+        assert!(result.is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use log::error;
 
-use massive_animation::{AnimationAllocator, Interpolation};
+use massive_animation::{AnimationAllocator, Spring};
 use massive_applications::ViewEvent;
 use massive_geometry::{SizePx, SizedTransform};
 use massive_layout::LayoutTopology;
@@ -10,9 +10,11 @@ use super::effects::{DesktopEffect, DesktopEffectScheduler, Effects};
 use super::layout_state::PlacementUpdate;
 use super::{DesktopLayoutAlgorithm, DesktopSystem, DesktopTarget, TransactionEffectsMode};
 use crate::instance_manager::InstanceManager;
-use crate::instance_presenter::STRUCTURAL_ANIMATION_DURATION;
 use crate::window_state::WindowPresentationState;
 
+/// The camera comes to rest when it is less than a hundredth of a pixel away from its target.
+const CAMERA_SPRING: Spring = Spring::DEFAULT.with_rest_thresholds(0.01, 0.1);
+
 impl DesktopSystem {
     pub(super) fn run_effects_to_completion(
         &mut self,
@@ -290,12 +292,9 @@ impl DesktopSystem {
             self.resolve_camera_for_target_or_ancestor(focused, self.focus_depth, window_size);
 
         if effects_mode.permit_animations() {
-            self.camera.animate_if_changed(
-                context,
-                camera,
-                STRUCTURAL_ANIMATION_DURATION,
-                Interpolation::CubicOut,
-            );
+            // A spring keeps the velocity of a running flight when the focus changes again.
+            self.camera
+                .animate_if_changed(context, camera, CAMERA_SPRING);
         } else {
             self.camera.snap(camera);
         }
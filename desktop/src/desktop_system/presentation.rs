@@ -148,6 +148,35 @@ impl DesktopSystem {
         Ok(ChangeOutput::changes(changes))
     }
 
+    pub(super) fn show_instance_crash(
+        &mut self,
+        instance: InstanceId,
+        message: &str,
+        restarting: bool,
+        frame: &mut Frame,
+    ) -> Result<ChangeOutput> {
+        let Some(instance_presenter) = self.aggregates.instances.get_mut(&instance) else {
+            bail!("Instance not found (show_instance_crash)");
+        };
+
+        let view = instance_presenter.show_crash(
+            message,
+            restarting,
+            self.default_panel_size,
+            frame.scene(),
+            &mut self.fonts.lock(),
+        );
+
+        // The view is gone with its instance. Removing it moves its focus to the instance, which
+        // receives the events for restarting it.
+        let mut changes = Changes::Empty;
+        if let Some(view) = view {
+            changes <<= DesktopChange::Topology(TopologyChange::Remove(DesktopTarget::View(view)));
+        }
+
+        Ok(ChangeOutput::changes(changes))
+    }
+
     pub(super) fn sync_hover_with_target(&self, target: Option<&DesktopTarget>) {
         let hover_placement = match target {
             Some(
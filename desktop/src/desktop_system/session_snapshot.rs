@@ -0,0 +1,280 @@
+//! Snapshots the running instances, so that they can be started again on the next run.
+
+use anyhow::Result;
+use log::{error, warn};
+
+use massive_applications::InstanceId;
+use massive_geometry::SizePx;
+use massive_shell::Frame;
+
+use super::change::{Changes, DesktopChange, set_focus};
+use super::{
+    DesktopCommand, DesktopSystem, DesktopTarget, KeyboardFocusReason, TransactionEffectsMode,
+};
+use crate::instance_manager::InstanceManager;
+use crate::projects::{
+    InstanceSession, LaunchProfileId, LauncherSession, ProjectId, Session, SessionFocus,
+};
+
+impl DesktopSystem {
+    /// The instances of all launchers, the keyboard focus, and the focus depth.
+    ///
+    /// Instances of `transient_project` are skipped. They are started by the desktop itself.
+    pub fn session(&self, transient_project: ProjectId) -> Session {
+        let launchers = self
+            .aggregates
+            .hierarchy
+            .get_nested(&DesktopTarget::Desktop)
+            .iter()
+            // Desktop children are projects only, anything else is not part of the session.
+            .filter_map(|target| match target {
+                DesktopTarget::Project(project) if *project != transient_project => Some(*project),
+                _ => None,
+            })
+            .flat_map(|project| self.aggregates.hierarchy.matrix_launchers(project))
+            .filter_map(|launcher| self.launcher_session(launcher))
+            .collect();
+
+        let focus = self
+            .event_router
+            .keyboard_focus()
+            .filter(|focused| {
+                self.aggregates.hierarchy.project_of_target(focused) != Some(transient_project)
+            })
+            .and_then(|focused| self.session_focus(focused));
+
+        Session {
+            launchers,
+            focus,
+            focus_depth: self.focus_depth,
+        }
+    }
+
+    /// Start the instances of a session through [`DesktopCommand::StartInstance`] and restore its
+    /// focus and focus depth.
+    ///
+    /// Launchers that are not configured anymore are skipped with their instances. Without a
+    /// focus in the session, `primary_instance` is focused. Instances that fail to start are
+    /// logged and skipped.
+    pub fn restore_session(
+        &mut self,
+        session: &Session,
+        primary_instance: InstanceId,
+        frame: &mut Frame,
+        instance_manager: &mut InstanceManager,
+        window_size: SizePx,
+    ) {
+        for launcher_session in &session.launchers {
+            let Some(launcher) = self.session_launcher(launcher_session) else {
+                warn!(
+                    "Launcher of the session is not configured anymore: {} / {}",
+                    launcher_session.project, launcher_session.launcher
+                );
+                continue;
+            };
+
+            // Every instance is inserted after the previous one, which is focused after it
+            // started.
+            for instance in &launcher_session.instances {
+                let started = self
+                    .plan(
+                        DesktopCommand::StartInstance {
+                            launcher,
+                            instance: instance.id,
+                            root: None,
+                            parameters: instance.parameters.clone(),
+                        },
+                        frame.scene(),
+                    )
+                    .and_then(|changes| {
+                        self.transact_setup(changes, frame, instance_manager, window_size)
+                    });
+                if let Err(e) = started {
+                    error!(
+                        "Failed to restore instance {:?} of the session: {e:?}",
+                        instance.id
+                    );
+                }
+            }
+
+            // Focusing the center moves the visor anchor of the launcher to it.
+            if let Some(center) = launcher_session.center
+                && self.is_present(&center)
+            {
+                let focus = set_focus(
+                    Some(DesktopTarget::Instance(center)),
+                    KeyboardFocusReason::RestoreSession,
+                );
+                if let Err(e) = self.transact_setup(focus, frame, instance_manager, window_size) {
+                    error!("Failed to focus the center instance {center:?} of the session: {e:?}");
+                }
+            }
+        }
+
+        let focus = match &session.focus {
+            Some(focus) => self.session_focus_target(focus),
+            None => Some(DesktopTarget::Instance(primary_instance)),
+        };
+        let mut changes = Changes::Empty;
+        if let Some(focus) = focus {
+            changes += set_focus(Some(focus), KeyboardFocusReason::RestoreSession);
+        }
+        changes <<= DesktopChange::CommitFocusDepth(session.focus_depth);
+        if let Err(e) = self.transact_setup(changes, frame, instance_manager, window_size) {
+            error!("Failed to restore the focus of the session: {e:?}");
+        }
+    }
+
+    fn transact_setup(
+        &mut self,
+        changes: Changes,
+        frame: &mut Frame,
+        instance_manager: &mut InstanceManager,
+        window_size: SizePx,
+    ) -> Result<()> {
+        self.transact(
+            changes,
+            frame,
+            instance_manager,
+            TransactionEffectsMode::Setup,
+            window_size,
+        )
+    }
+
+    /// The launcher a saved launcher session belongs to, if it is still configured.
+    pub fn session_launcher(&self, session: &LauncherSession) -> Option<LaunchProfileId> {
+        self.launcher_named(&session.project, &session.launcher)
+    }
+
+    /// The target a saved focus refers to, if it still exists.
+    pub fn session_focus_target(&self, focus: &SessionFocus) -> Option<DesktopTarget> {
+        let target = match focus {
+            SessionFocus::Project { project } => {
+                DesktopTarget::Project(self.project_named(project)?)
+            }
+            SessionFocus::Launcher { project, launcher } => {
+                DesktopTarget::Launcher(self.launcher_named(project, launcher)?)
+            }
+            SessionFocus::Instance(instance) => DesktopTarget::Instance(*instance),
+        };
+        self.aggregates.hierarchy.exists(&target).then_some(target)
+    }
+
+    fn launcher_session(&self, launcher: LaunchProfileId) -> Option<LauncherSession> {
+        let instances: Vec<_> = self
+            .aggregates
+            .hierarchy
+            .get_nested(&DesktopTarget::Launcher(launcher))
+            .iter()
+            // Launcher children are instances only, anything else is not part of the session.
+            .filter_map(|target| match target {
+                DesktopTarget::Instance(instance) => Some(InstanceSession {
+                    id: *instance,
+                    parameters: self
+                        .aggregates
+                        .instances
+                        .get(instance)?
+                        .parameters()
+                        .clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        if instances.is_empty() {
+            return None;
+        }
+
+        let presenter = &self.aggregates.launchers[&launcher];
+        let project = self.aggregates.hierarchy.project_of_launcher(launcher);
+        Some(LauncherSession {
+            project: self.aggregates.projects[&project].name().to_string(),
+            launcher: presenter.name().to_string(),
+            instances,
+            center: presenter.focus_anchor_instance,
+        })
+    }
+
+    fn session_focus(&self, focused: &DesktopTarget) -> Option<SessionFocus> {
+        let hierarchy = &self.aggregates.hierarchy;
+        if let Some(instance) = hierarchy.instance_of_target(focused) {
+            return Some(SessionFocus::Instance(instance));
+        }
+        let project = hierarchy.project_of_target(focused)?;
+        let project = self.aggregates.projects[&project].name().to_string();
+        Some(match hierarchy.launcher_of_target(focused) {
+            Some(launcher) => SessionFocus::Launcher {
+                project,
+                launcher: self.aggregates.launchers[&launcher].name().to_string(),
+            },
+            None => SessionFocus::Project { project },
+        })
+    }
+
+    fn launcher_named(&self, project: &str, launcher: &str) -> Option<LaunchProfileId> {
+        let project = self.project_named(project)?;
+        self.aggregates
+            .hierarchy
+            .matrix_launchers(project)
+            .find(|id| self.aggregates.launchers[id].name() == launcher)
+    }
+
+    fn project_named(&self, name: &str) -> Option<ProjectId> {
+        self.aggregates
+            .hierarchy
+            .get_nested(&DesktopTarget::Desktop)
+            .iter()
+            .find_map(|target| match target {
+                DesktopTarget::Project(project)
+                    if self.aggregates.projects[project].name() == name =>
+                {
+                    Some(*project)
+                }
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::desktop_system::FocusDepth;
+    use crate::desktop_system::test_harness::TestDesktop;
+
+    #[tokio::test]
+    async fn restoring_a_snapshot_reproduces_the_session() -> Result<()> {
+        let mut desktop = TestDesktop::new()?;
+        let project = desktop.add_project("Work")?;
+        let terminal = desktop.add_launcher(project, "Terminal", 0)?;
+        let editor = desktop.add_launcher(project, "Editor", 1)?;
+        for (launcher, command) in [(terminal, "htop"), (terminal, "top"), (editor, "vi")] {
+            desktop.run(DesktopCommand::StartInstance {
+                launcher,
+                instance: Uuid::new_v4().into(),
+                root: None,
+                parameters: json!({ "command": command }).as_object().unwrap().clone(),
+            })?;
+        }
+        desktop.transact(DesktopChange::CommitFocusDepth(FocusDepth::Launcher))?;
+
+        // Through the session file format.
+        let session = desktop.system.session(ProjectId::new());
+        let session: Session = serde_json::from_str(&serde_json::to_string(&session)?)?;
+        assert_eq!(session.launchers.len(), 2);
+        assert_eq!(session.launchers[0].instances.len(), 2);
+        assert!(matches!(session.focus, Some(SessionFocus::Instance(_))));
+        assert_eq!(session.focus_depth, FocusDepth::Launcher);
+
+        // The next run configures new ids for the same names.
+        let mut restored = TestDesktop::new()?;
+        let project = restored.add_project("Work")?;
+        restored.add_launcher(project, "Terminal", 0)?;
+        restored.add_launcher(project, "Editor", 1)?;
+        restored.restore_session(&session, Uuid::new_v4().into());
+
+        assert_eq!(restored.system.session(ProjectId::new()), session);
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! A desktop system without a window, for tests that run commands and changes through it.
+//!
+//! Instances are spawned on the tokio runtime of the test and never submit anything.
+
+use std::future;
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use massive_animation::{AnimationCoordinator, MovementRuntime};
+use massive_applications::{InstanceEnvironment, InstanceId, InstanceSubmission};
+use massive_geometry::SizePx;
+use massive_scene::ChangeCollector;
+use massive_shell::{FontManager, Frame, Scene};
+
+use super::change::Changes;
+use super::undo_history::edit;
+use super::{DesktopCommand, DesktopSystem, Keymap, ProjectCommand};
+use crate::instance_manager::InstanceManager;
+use crate::projects::{
+    LaunchProfile, LaunchProfileId, LauncherMode, MatrixPlacement, ProjectId, ProjectProperties,
+    RestartPolicy, Session,
+};
+use crate::{Application, DesktopEnvironment};
+
+const WINDOW_SIZE: SizePx = SizePx::new(1280, 800);
+
+pub struct TestDesktop {
+    pub system: DesktopSystem,
+    pub instance_manager: InstanceManager,
+    scene: Scene,
+    animation: AnimationCoordinator,
+    movement: MovementRuntime,
+    _submissions: UnboundedReceiver<(InstanceId, InstanceSubmission)>,
+}
+
+impl TestDesktop {
+    /// Must be called from inside a tokio runtime.
+    pub fn new() -> Result<Self> {
+        let fonts = FontManager::system();
+        let application = Application::new("Test", |_context| future::pending::<Result<()>>());
+        let scene = Scene::new(Arc::new(ChangeCollector::default()));
+        let mut movement = MovementRuntime::new();
+        let system = DesktopSystem::new(
+            DesktopEnvironment::new(vec![application]),
+            fonts.clone(),
+            Keymap::default(),
+            SizePx::new(640, 480),
+            &scene,
+            &mut movement,
+        )?;
+        let (submissions_tx, submissions) = unbounded_channel();
+        let instance_manager =
+            InstanceManager::new(InstanceEnvironment::new(submissions_tx, 1.0, fonts));
+
+        Ok(Self {
+            system,
+            instance_manager,
+            scene,
+            animation: AnimationCoordinator::new(),
+            movement,
+            _submissions: submissions,
+        })
+    }
+
+    pub fn add_project(&mut self, name: &str) -> Result<ProjectId> {
+        let id = ProjectId::new();
+        self.run(DesktopCommand::Project(ProjectCommand::AddProject {
+            id,
+            properties: ProjectProperties { name: name.into() },
+            after: None,
+        }))?;
+        Ok(id)
+    }
+
+    pub fn add_launcher(
+        &mut self,
+        project: ProjectId,
+        name: &str,
+        column: u32,
+    ) -> Result<LaunchProfileId> {
+        let id = LaunchProfileId::new();
+        self.run(DesktopCommand::Project(ProjectCommand::AddLauncher {
+            project,
+            id,
+            profile: LaunchProfile {
+                name: name.into(),
+                mode: LauncherMode::Visor,
+                tags: Vec::new(),
+                params: Default::default(),
+                restart: RestartPolicy::Never,
+            },
+            placement: MatrixPlacement { column, row: 0 },
+        }))?;
+        Ok(id)
+    }
+
+    /// Plan and apply a command in its own frame.
+    pub fn run(&mut self, command: DesktopCommand) -> Result<()> {
+        let changes = self.system.plan(command, &self.scene)?;
+        self.transact(changes)
+    }
+
+    /// Plan and apply a project command as an undoable edit, like configuration requests are.
+    pub fn run_edit(&mut self, command: ProjectCommand) -> Result<()> {
+        let changes = self
+            .system
+            .plan(DesktopCommand::Project(command), &self.scene)?;
+        self.transact(edit(changes))
+    }
+
+    /// Apply changes in their own frame.
+    pub fn transact(&mut self, changes: impl Into<Changes>) -> Result<()> {
+        let mut frame = Frame::new(&self.scene, &mut self.animation, &mut self.movement);
+        let result = self.system.transact(
+            changes,
+            &mut frame,
+            &mut self.instance_manager,
+            None,
+            WINDOW_SIZE,
+        );
+        frame.submission();
+        result
+    }
+
+    pub fn restore_session(&mut self, session: &Session, primary_instance: InstanceId) {
+        let mut frame = Frame::new(&self.scene, &mut self.animation, &mut self.movement);
+        self.system.restore_session(
+            session,
+            primary_instance,
+            &mut frame,
+            &mut self.instance_manager,
+            WINDOW_SIZE,
+        );
+        frame.submission();
+    }
+}
@@ -0,0 +1,567 @@
+//! Undo / redo of project configuration edits.
+//!
+//! While an edit is recorded, every applied [`ProjectChange`] records the [`UndoStep`]s that revert
+//! it. Launchers removed from the topology record their index, so that they are put back in order.
+//! Steps are planned lazily against the then current state, one after another, so that the
+//! follow-up changes of one step (e.g. shutting down instances) are applied before the next step
+//! is planned.
+//!
+//! Project changes that are applied outside of an edit, for example when the configuration is
+//! changed from the outside, clear the history. The recorded steps were planned against a
+//! configuration that may not exist anymore.
+
+use anyhow::{Context, Result, bail};
+use log::warn;
+
+use super::change::set_focus;
+use super::change::{Changes, HistoryChange, ProjectChange, TopologyChange};
+use super::{DesktopSystem, DesktopTarget, KeyboardFocusReason};
+use crate::projects::{
+    LaunchProfile, LaunchProfileId, MatrixPlacement, ProjectId, ProjectProperties,
+};
+
+/// Where a finished edit goes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// A new edit: Pushed to the undo stack, invalidates the redo stack.
+    Do,
+    /// The reversal of an undone edit: Pushed to the redo stack.
+    Undo,
+    /// The reversal of a redone edit: Pushed to the undo stack.
+    Redo,
+}
+
+/// A single inverse of an applied [`ProjectChange`].
+#[derive(Debug, Clone)]
+pub enum UndoStep {
+    AddProject {
+        id: ProjectId,
+        properties: ProjectProperties,
+        /// The index of the project in the desktop.
+        index: usize,
+    },
+    RemoveProject(ProjectId),
+    AddLauncher {
+        project: ProjectId,
+        id: LaunchProfileId,
+        profile: LaunchProfile,
+        placement: MatrixPlacement,
+    },
+    /// Put a launcher back at its index in the project, which is the order it is saved in.
+    InsertLauncher {
+        project: ProjectId,
+        launcher: LaunchProfileId,
+        index: usize,
+    },
+    RemoveLauncher {
+        project: ProjectId,
+        launcher: LaunchProfileId,
+    },
+    MoveLauncher {
+        launcher: LaunchProfileId,
+        placement: MatrixPlacement,
+    },
+    SetStartupProfile(Option<LaunchProfileId>),
+}
+
+/// The inverse steps recorded while one configuration request was applied, in recording order.
+#[derive(Debug, Default)]
+struct Edit {
+    steps: Vec<UndoStep>,
+}
+
+#[derive(Debug)]
+struct Recording {
+    kind: EditKind,
+    edit: Edit,
+}
+
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    recording: Option<Recording>,
+}
+
+impl UndoHistory {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn begin(&mut self, kind: EditKind) -> Result<()> {
+        if self.recording.is_some() {
+            bail!("Internal error: Nested edits are not supported");
+        }
+        self.recording = Some(Recording {
+            kind,
+            edit: Edit::default(),
+        });
+        Ok(())
+    }
+
+    pub fn record(&mut self, steps: impl IntoIterator<Item = UndoStep>) -> Result<()> {
+        let Some(recording) = &mut self.recording else {
+            bail!("Internal error: Recording undo steps without an edit");
+        };
+        recording.edit.steps.extend(steps);
+        Ok(())
+    }
+
+    pub fn commit(&mut self) -> Result<()> {
+        let Some(Recording { kind, edit }) = self.recording.take() else {
+            bail!("Internal error: Commit without a recorded edit");
+        };
+
+        // Requests that turned out to change nothing should not consume an undo.
+        if edit.steps.is_empty() {
+            return Ok(());
+        }
+
+        match kind {
+            EditKind::Do => {
+                self.undo.push(edit);
+                self.redo.clear();
+            }
+            EditKind::Undo => self.redo.push(edit),
+            EditKind::Redo => self.undo.push(edit),
+        }
+        Ok(())
+    }
+
+    /// Discard the edit that is recorded, because applying it failed midway.
+    ///
+    /// The changes applied so far can not be reverted as a unit, so the history is cleared, too.
+    pub fn abort(&mut self) {
+        if self.recording.take().is_some() {
+            self.clear();
+        }
+    }
+
+    /// Forget everything. Used when project changes are applied outside of an edit, because the
+    /// recorded steps may not match the configuration anymore.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Returns the changes that revert the most recent edit.
+    fn undo(&mut self) -> Option<Changes> {
+        self.undo.pop().map(|edit| edit.revert(EditKind::Undo))
+    }
+
+    /// Returns the changes that revert the most recent undo.
+    fn redo(&mut self) -> Option<Changes> {
+        self.redo.pop().map(|edit| edit.revert(EditKind::Redo))
+    }
+}
+
+impl Edit {
+    fn revert(self, kind: EditKind) -> Changes {
+        let mut changes = Changes::Empty;
+        changes <<= HistoryChange::BeginEdit(kind);
+        changes.extend(
+            self.steps
+                .into_iter()
+                .rev()
+                .map(|step| HistoryChange::Revert(step).into()),
+        );
+        changes <<= HistoryChange::CommitEdit;
+        changes
+    }
+}
+
+/// Wrap the changes of a configuration request into an edit, so that they can be undone as a unit.
+pub fn edit(changes: Changes) -> Changes {
+    if changes.is_empty() {
+        return changes;
+    }
+    let mut edit = Changes::Empty;
+    edit <<= HistoryChange::BeginEdit(EditKind::Do);
+    edit += changes;
+    edit <<= HistoryChange::CommitEdit;
+    edit
+}
+
+impl DesktopSystem {
+    pub(super) fn apply_history_change(&mut self, change: HistoryChange) -> Result<Changes> {
+        match change {
+            HistoryChange::BeginEdit(kind) => self.undo_history.begin(kind)?,
+            HistoryChange::CommitEdit => self.undo_history.commit()?,
+            HistoryChange::Undo => match self.undo_history.undo() {
+                Some(changes) => return Ok(changes),
+                None => warn!("Nothing to undo"),
+            },
+            HistoryChange::Redo => match self.undo_history.redo() {
+                Some(changes) => return Ok(changes),
+                None => warn!("Nothing to redo"),
+            },
+            HistoryChange::Revert(step) => return Ok(self.plan_undo_step(step)),
+        }
+        Ok(Changes::Empty)
+    }
+
+    /// Record the inverse of a project change before it is applied.
+    ///
+    /// Outside of an edit, the history is cleared instead.
+    pub(super) fn record_project_change(&mut self, change: &ProjectChange) -> Result<()> {
+        if self.undo_history.is_recording() {
+            let steps = self.inverse_steps(change)?;
+            self.undo_history.record(steps)?;
+        } else {
+            self.undo_history.clear();
+        }
+        Ok(())
+    }
+
+    /// Record where a launcher was in its project before it is removed from the topology.
+    ///
+    /// Other topology changes are derived from project changes and need no undo steps.
+    pub(super) fn record_topology_change(&mut self, change: &TopologyChange) -> Result<()> {
+        let TopologyChange::Remove(DesktopTarget::Launcher(launcher)) = change else {
+            return Ok(());
+        };
+        if !self.undo_history.is_recording() {
+            return Ok(());
+        }
+        let project = self.aggregates.hierarchy.project_of_launcher(*launcher);
+        let index = self
+            .aggregates
+            .hierarchy
+            .matrix_launchers(project)
+            .position(|id| id == *launcher)
+            .context("Internal error: Launcher missing from project hierarchy")?;
+        self.undo_history.record([UndoStep::InsertLauncher {
+            project,
+            launcher: *launcher,
+            index,
+        }])
+    }
+
+    fn inverse_steps(&self, change: &ProjectChange) -> Result<Vec<UndoStep>> {
+        Ok(match change {
+            ProjectChange::AddProject { id, .. } => vec![UndoStep::RemoveProject(*id)],
+            ProjectChange::RemoveProject(project) => {
+                let index = self
+                    .aggregates
+                    .hierarchy
+                    .get_nested(&DesktopTarget::Desktop)
+                    .iter()
+                    .position(|target| *target == DesktopTarget::Project(*project))
+                    .context("Internal error: Project missing from desktop hierarchy")?;
+                vec![UndoStep::AddProject {
+                    id: *project,
+                    properties: ProjectProperties {
+                        name: self.aggregates.projects[project].name().to_string(),
+                    },
+                    index,
+                }]
+            }
+            ProjectChange::AddLauncher { project, id, .. } => vec![UndoStep::RemoveLauncher {
+                project: *project,
+                launcher: *id,
+            }],
+            // The launcher was removed from the topology before, which recorded its index.
+            ProjectChange::RemoveLauncher { project, launcher } => vec![UndoStep::AddLauncher {
+                project: *project,
+                id: *launcher,
+                profile: self.aggregates.launchers[launcher].profile().clone(),
+                placement: self.aggregates.matrix_positions[launcher],
+            }],
+            ProjectChange::MoveLauncher { launcher, .. } => vec![UndoStep::MoveLauncher {
+                launcher: *launcher,
+                placement: self.aggregates.matrix_positions[launcher],
+            }],
+            // Removing a slot shifts the remaining launchers, so restore their current placements.
+            ProjectChange::RemoveSlot { project, .. } => self
+                .aggregates
+                .hierarchy
+                .matrix_launchers(*project)
+                .map(|launcher| UndoStep::MoveLauncher {
+                    launcher,
+                    placement: self.aggregates.matrix_positions[&launcher],
+                })
+                .collect(),
+            ProjectChange::SetStartupProfile(_) => {
+                vec![UndoStep::SetStartupProfile(self.aggregates.startup_profile)]
+            }
+        })
+    }
+
+    fn plan_undo_step(&self, step: UndoStep) -> Changes {
+        let mut changes = Changes::Empty;
+        match step {
+            UndoStep::AddProject {
+                id,
+                properties,
+                index,
+            } => {
+                let project_target = DesktopTarget::Project(id);
+                changes <<= TopologyChange::Insert {
+                    what: project_target.clone(),
+                    at_index: index,
+                    under: DesktopTarget::Desktop,
+                };
+                changes <<= TopologyChange::AddNested {
+                    what: [
+                        DesktopTarget::ProjectHeader(id),
+                        DesktopTarget::ProjectMatrix(id),
+                    ]
+                    .into(),
+                    under: project_target,
+                };
+                changes <<= ProjectChange::AddProject { id, properties };
+            }
+            UndoStep::RemoveProject(project) => {
+                changes += self.plan_project_removal_focus(project);
+                changes += self.plan_remove_project(project);
+            }
+            UndoStep::AddLauncher {
+                project,
+                id,
+                profile,
+                placement,
+            } => {
+                changes <<= ProjectChange::AddLauncher {
+                    project,
+                    id,
+                    profile,
+                    placement,
+                };
+            }
+            UndoStep::InsertLauncher {
+                project,
+                launcher,
+                index,
+            } => {
+                changes <<= TopologyChange::Insert {
+                    what: launcher.into(),
+                    at_index: index,
+                    under: DesktopTarget::ProjectMatrix(project),
+                };
+            }
+            UndoStep::RemoveLauncher { project, launcher } => {
+                let launcher_target = DesktopTarget::Launcher(launcher);
+                if let Some(focused) = self.event_router.keyboard_focus()
+                    && self
+                        .aggregates
+                        .hierarchy
+                        .path_contains_target(Some(focused), &launcher_target)
+                {
+                    changes += set_focus(
+                        Some(self.launcher_removal_focus(launcher, focused)),
+                        KeyboardFocusReason::InputTransition,
+                    );
+                }
+                // Slot shifts were recorded as separate moves, so they are reverted separately.
+                changes += self.plan_remove_launcher(project, launcher, None);
+            }
+            UndoStep::MoveLauncher {
+                launcher,
+                placement,
+            } => {
+                changes <<= ProjectChange::MoveLauncher {
+                    launcher,
+                    placement,
+                };
+            }
+            UndoStep::SetStartupProfile(profile) => {
+                changes <<= ProjectChange::SetStartupProfile(profile);
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desktop_system::ProjectCommand;
+    use crate::desktop_system::change::DesktopChange;
+    use crate::desktop_system::test_harness::TestDesktop;
+    use crate::projects::{LauncherMode, RestartPolicy};
+
+    /// The projects with their launchers and placements in order, and the startup profile.
+    #[derive(Debug, PartialEq)]
+    struct Snapshot {
+        projects: Vec<(String, Vec<(String, MatrixPlacement)>)>,
+        startup_profile: Option<LaunchProfileId>,
+    }
+
+    fn snapshot(desktop: &TestDesktop) -> Snapshot {
+        let aggregates = &desktop.system.aggregates;
+        let projects = aggregates
+            .hierarchy
+            .get_nested(&DesktopTarget::Desktop)
+            .iter()
+            .map(|target| {
+                let DesktopTarget::Project(project) = target else {
+                    panic!("Desktop children must be projects");
+                };
+                let launchers = aggregates
+                    .hierarchy
+                    .matrix_launchers(*project)
+                    .map(|launcher| {
+                        (
+                            aggregates.launchers[&launcher].name().to_string(),
+                            aggregates.matrix_positions[&launcher],
+                        )
+                    })
+                    .collect();
+                (aggregates.projects[project].name().to_string(), launchers)
+            })
+            .collect();
+        Snapshot {
+            projects,
+            startup_profile: aggregates.startup_profile,
+        }
+    }
+
+    /// Applies `command` as an edit and checks that undo restores the state before and redo the
+    /// one after it.
+    fn assert_round_trip(desktop: &mut TestDesktop, command: ProjectCommand) -> Result<()> {
+        let before = snapshot(desktop);
+        desktop.run_edit(command)?;
+        let after = snapshot(desktop);
+        assert_ne!(before, after);
+
+        desktop.transact(DesktopChange::History(HistoryChange::Undo))?;
+        assert_eq!(snapshot(desktop), before);
+        desktop.transact(DesktopChange::History(HistoryChange::Redo))?;
+        assert_eq!(snapshot(desktop), after);
+        // And once more, the redo is undoable again.
+        desktop.transact(DesktopChange::History(HistoryChange::Undo))?;
+        assert_eq!(snapshot(desktop), before);
+        Ok(())
+    }
+
+    fn profile(name: &str) -> LaunchProfile {
+        LaunchProfile {
+            name: name.into(),
+            mode: LauncherMode::Visor,
+            tags: Vec::new(),
+            params: Default::default(),
+            restart: RestartPolicy::Never,
+        }
+    }
+
+    #[tokio::test]
+    async fn project_edits_round_trip() -> Result<()> {
+        let mut desktop = TestDesktop::new()?;
+        let work = desktop.add_project("Work")?;
+        desktop.add_launcher(work, "Terminal", 0)?;
+        desktop.add_launcher(work, "Editor", 1)?;
+        let home = desktop.add_project("Home")?;
+        desktop.add_launcher(home, "Browser", 0)?;
+
+        assert_round_trip(
+            &mut desktop,
+            ProjectCommand::AddProject {
+                id: ProjectId::new(),
+                properties: ProjectProperties {
+                    name: "Play".into(),
+                },
+                after: Some(work),
+            },
+        )?;
+        // Restores the project at its index, with its launchers in order.
+        assert_round_trip(&mut desktop, ProjectCommand::RemoveProject(work))?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn launcher_edits_round_trip() -> Result<()> {
+        let mut desktop = TestDesktop::new()?;
+        let work = desktop.add_project("Work")?;
+        let terminal = desktop.add_launcher(work, "Terminal", 0)?;
+        desktop.add_launcher(work, "Editor", 1)?;
+
+        assert_round_trip(
+            &mut desktop,
+            ProjectCommand::AddLauncher {
+                project: work,
+                id: LaunchProfileId::new(),
+                profile: profile("Browser"),
+                placement: MatrixPlacement { column: 0, row: 1 },
+            },
+        )?;
+        // Puts the launcher back in front of the editor.
+        assert_round_trip(&mut desktop, ProjectCommand::RemoveLauncher(terminal))?;
+        assert_round_trip(
+            &mut desktop,
+            ProjectCommand::SetStartupProfile(Some(terminal)),
+        )?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn new_edits_clear_the_redo_history() -> Result<()> {
+        let mut desktop = TestDesktop::new()?;
+        desktop.add_project("Work")?;
+
+        desktop.run_edit(ProjectCommand::AddProject {
+            id: ProjectId::new(),
+            properties: ProjectProperties {
+                name: "Play".into(),
+            },
+            after: None,
+        })?;
+        desktop.transact(DesktopChange::History(HistoryChange::Undo))?;
+        let undone = snapshot(&desktop);
+        desktop.run_edit(ProjectCommand::AddProject {
+            id: ProjectId::new(),
+            properties: ProjectProperties {
+                name: "Home".into(),
+            },
+            after: None,
+        })?;
+        desktop.transact(DesktopChange::History(HistoryChange::Undo))?;
+        assert_eq!(snapshot(&desktop), undone);
+
+        // Nothing to redo but the last undo.
+        desktop.transact(DesktopChange::History(HistoryChange::Redo))?;
+        desktop.transact(DesktopChange::History(HistoryChange::Redo))?;
+        assert_eq!(snapshot(&desktop).projects.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn changes_outside_of_edits_clear_the_history() -> Result<()> {
+        let mut desktop = TestDesktop::new()?;
+        desktop.run_edit(ProjectCommand::AddProject {
+            id: ProjectId::new(),
+            properties: ProjectProperties {
+                name: "Work".into(),
+            },
+            after: None,
+        })?;
+        desktop.add_project("Home")?;
+
+        let before = snapshot(&desktop);
+        desktop.transact(DesktopChange::History(HistoryChange::Undo))?;
+        assert_eq!(snapshot(&desktop), before);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failed_edits_end_the_recording() -> Result<()> {
+        let mut desktop = TestDesktop::new()?;
+        let work = desktop.add_project("Work")?;
+
+        let mut failing = Changes::Empty;
+        failing <<= HistoryChange::BeginEdit(EditKind::Do);
+        // There is no such project to remove.
+        failing <<= ProjectChange::RemoveProject(ProjectId::new());
+        failing <<= HistoryChange::CommitEdit;
+        assert!(desktop.transact(failing).is_err());
+
+        assert_round_trip(
+            &mut desktop,
+            ProjectCommand::AddLauncher {
+                project: work,
+                id: LaunchProfileId::new(),
+                profile: profile("Terminal"),
+                placement: MatrixPlacement { column: 0, row: 0 },
+            },
+        )
+    }
+}
@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::collections::HashMap;
 use std::panic::AssertUnwindSafe;
 
@@ -69,7 +70,7 @@ impl InstanceManager {
             let result = AssertUnwindSafe(instance_future).catch_unwind().await;
             let result = match result {
                 Ok(r) => r,
-                Err(e) => Err(anyhow!("Instance panicked : {e:?}")),
+                Err(e) => Err(anyhow!("Instance panicked: {}", panic_message(&*e))),
             };
             (instance_id, result)
         });
@@ -143,3 +144,11 @@ impl InstanceManager {
             .ok_or_else(|| anyhow!("Instance {:?} does not exist", instance))
     }
 }
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Unknown panic payload")
+}
@@ -1,23 +1,29 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Result, bail};
 
+use winit::event::MouseButton;
 use winit::window::CursorIcon;
 
 use massive_animation::{
     Animated, AnimationAllocator, AnimationProgress, Interpolation, Movement, MovementRuntime,
+    Timeline,
 };
-use massive_applications::{InstanceParameters, ViewCreationInfo, ViewId, ViewRole};
-use massive_geometry::{Color, Rect, Size, SizePx, SizedTransform, Transform, Vector3};
+use massive_applications::{
+    InstanceId, InstanceParameters, ViewCreationInfo, ViewEvent, ViewId, ViewRole,
+};
+use massive_geometry::{Bounds, Color, Rect, Size, SizePx, SizedTransform, Transform, Vector3};
+use massive_input::EventManager;
 use massive_renderer::RenderPacing;
+use massive_renderer::text::FontSystem;
 use massive_scene::{
     At, Handle, Location, Object, Ref, StageIdentityLocation, ToLocationRelative, Visual,
 };
-use massive_shapes::{self as shapes, Shape};
+use massive_shapes::{self as shapes, GlyphRun, IntoShape, ParagraphStyle, Shape, Size as SizeExt};
 use massive_shell::Scene;
 
-use crate::desktop_system::fullscreen_scale;
+use crate::desktop_system::{Commands, DesktopCommand, fullscreen_scale};
 
 #[derive(Debug, Clone)]
 pub struct InstanceRoot {
@@ -57,6 +63,12 @@ impl InstanceRoot {
 
 pub const STRUCTURAL_ANIMATION_DURATION: Duration = Duration::from_millis(500);
 const INSTANCE_BACKGROUND_COLOR: Color = Color::rgb_u32(0x282828);
+const CRASH_BACKGROUND_COLOR: Color = Color::rgb_u32(0x3c1e1e);
+const CRASH_TEXT_COLOR: Color = Color::WHITE;
+const CRASH_TITLE_FONT_SIZE: f32 = 32.0;
+const CRASH_FONT_SIZE: f32 = 20.0;
+const CRASH_PADDING: f64 = 32.0;
+const CRASH_MESSAGE_MAX_LINES: usize = 4;
 
 #[derive(Debug)]
 pub struct InstancePresenter {
@@ -71,6 +83,8 @@ pub struct InstancePresenter {
     has_applied_layout: bool,
     pub pacing: RenderPacing,
     background: Option<InstanceBackground>,
+    /// Receives the events while the instance is crashed.
+    event_manager: EventManager<ViewEvent>,
 }
 
 #[derive(Debug)]
@@ -87,6 +101,11 @@ enum InstancePresenterState {
         view: PrimaryViewPresenter,
     },
     Disappearing,
+    /// The instance ended on its own, a placeholder shows why until it is restarted.
+    Crashed {
+        #[allow(unused)]
+        visuals: [Handle<Visual>; 2],
+    },
 }
 
 #[derive(Debug)]
@@ -188,6 +207,7 @@ impl InstancePresenter {
             has_applied_layout: has_initial_center_translation,
             pacing: RenderPacing::default(),
             background,
+            event_manager: EventManager::default(),
         }
     }
 
@@ -195,6 +215,10 @@ impl InstancePresenter {
         &self.parameters
     }
 
+    pub fn root(&self) -> &InstanceRoot {
+        &self.root
+    }
+
     pub fn latest_transform(&self) -> Transform {
         *self.root.layout_transform.value()
     }
@@ -209,6 +233,9 @@ impl InstancePresenter {
             InstancePresenterState::Presenting { .. } | InstancePresenterState::Disappearing => {
                 bail!("Primary view is already presenting");
             }
+            InstancePresenterState::Crashed { .. } => {
+                bail!("A crashed instance can't present a view");
+            }
         }
 
         // Blend in.
@@ -220,13 +247,10 @@ impl InstancePresenter {
         });
         self.movement.modify(move |movement, context| {
             // Same here, this looks weird.
-            movement.view_alpha.snap(0.0);
-            movement.view_alpha.animate(
-                context,
-                1.0,
-                STRUCTURAL_ANIMATION_DURATION,
-                Interpolation::CubicOut,
-            );
+            let arrival = Timeline::new()
+                .keyframe(Duration::ZERO, 0.0, Interpolation::Linear)
+                .keyframe(STRUCTURAL_ANIMATION_DURATION, 1.0, Interpolation::CubicOut);
+            movement.view_alpha.play(context, &arrival);
         });
 
         let view_size = view_creation_info.size();
@@ -267,7 +291,123 @@ impl InstancePresenter {
                 // Ignored, we are already disappearing.
                 Ok(())
             }
+            InstancePresenterState::Crashed { .. } => {
+                // Ignored, the view was already removed when the instance crashed.
+                Ok(())
+            }
+        }
+    }
+
+    /// Replace the view by a placeholder that shows `message`.
+    ///
+    /// Returns the view that was presented until now.
+    pub fn show_crash(
+        &mut self,
+        message: &str,
+        restarting: bool,
+        panel_size: SizePx,
+        scene: &Scene,
+        font_system: &mut FontSystem,
+    ) -> Option<ViewId> {
+        let view = self.primary_view_id();
+        let size = self.state.view().map_or(panel_size, |view| view.view_size);
+        let rect = Rect::from_size(size);
+        let rect = rect - rect.center();
+
+        let background = shapes::Rect::new(rect, CRASH_BACKGROUND_COLOR)
+            .into_shape()
+            .at(&self.root.presentation_location)
+            .with_decal_order(0)
+            .enter(scene);
+
+        let hint = if restarting {
+            "Restarting soon, click to restart now"
+        } else {
+            "Click to restart"
+        };
+        let width = (rect.size().width - 2.0 * CRASH_PADDING) as f32;
+        let paragraph = ParagraphStyle::default()
+            .with_max_width(width)
+            .with_max_lines(CRASH_MESSAGE_MAX_LINES);
+        let mut top = rect.top + CRASH_PADDING;
+        let text: Vec<Shape> = [
+            ("The instance crashed", CRASH_TITLE_FONT_SIZE),
+            (message, CRASH_FONT_SIZE),
+            (hint, CRASH_FONT_SIZE),
+        ]
+        .into_iter()
+        .flat_map(|(text, font_size)| {
+            let shaped = text
+                .size(font_size)
+                .shape_paragraph(font_system, &paragraph);
+            let translation = Vector3::new(rect.left + CRASH_PADDING, top, 0.0);
+            top += shaped.size.height as f64 + font_size as f64 * 0.5;
+            shaped.runs.into_iter().map(move |run| {
+                GlyphRun {
+                    translation: run.translation + translation,
+                    ..run
+                }
+                .with_color(CRASH_TEXT_COLOR)
+                .into_shape()
+            })
+        })
+        .collect();
+        let text = text
+            .at(&self.root.presentation_location)
+            .with_decal_order(1)
+            .with_clip_bounds(Bounds::new(
+                (rect.left, rect.top),
+                (rect.right - CRASH_PADDING, rect.bottom),
+            ))
+            .enter(scene);
+
+        self.state = InstancePresenterState::Crashed {
+            visuals: [background, text],
+        };
+        self.pacing = RenderPacing::default();
+
+        // An instance may crash before its view was blended in.
+        self.movement.modify(|movement, context| {
+            movement.view_alpha.animate_if_changed(
+                context,
+                1.0,
+                STRUCTURAL_ANIMATION_DURATION,
+                Interpolation::CubicOut,
+            );
+        });
+
+        view
+    }
+
+    pub fn is_crashed(&self) -> bool {
+        matches!(self.state, InstancePresenterState::Crashed { .. })
+    }
+
+    /// Remove the crash placeholder and wait for the view of the restarted instance.
+    pub fn restart(&mut self) -> Result<()> {
+        if !self.is_crashed() {
+            bail!("Only crashed instances can be restarted");
         }
+        self.state = InstancePresenterState::WaitingForPrimaryView;
+        Ok(())
+    }
+
+    /// Process an event that targets the instance itself and not its view.
+    ///
+    /// A click on a crashed instance restarts it, like [`KeyAction::RestartInstance`] does.
+    ///
+    /// [`KeyAction::RestartInstance`]: crate::projects::KeyAction::RestartInstance
+    pub fn process(&mut self, instance: InstanceId, event: ViewEvent) -> Result<Commands> {
+        let crashed = self.is_crashed();
+        let Some(event) = self.event_manager.add_event(event, Instant::now()) else {
+            return Ok(Commands::Empty);
+        };
+
+        if crashed && event.detect_click(MouseButton::Left).is_some() {
+            return Ok(DesktopCommand::RestartInstance(instance).into());
+        }
+
+        Ok(Commands::Empty)
     }
 
     pub fn set_view_title(&mut self, view_id: ViewId, title: String) -> Result<()> {
@@ -401,6 +541,9 @@ impl InstanceMovement {
         location.update_if_changed_with(|location| {
             location.alpha =
                 *self.view_alpha.proceed(progress) * *self.visibility_alpha.proceed(progress);
+            // While fading, composite the instance as a whole, so that its overlapping visuals
+            // don't show through each other.
+            location.layer = location.alpha < 1.0;
         });
     }
 }
@@ -431,6 +574,7 @@ impl InstancePresenterState {
             Self::WaitingForPrimaryView => None,
             Self::Presenting { view } => Some(view),
             Self::Disappearing => None,
+            Self::Crashed { .. } => None,
         }
     }
 
@@ -439,6 +583,7 @@ impl InstancePresenterState {
             Self::WaitingForPrimaryView => None,
             Self::Presenting { view } => Some(view),
             Self::Disappearing => None,
+            Self::Crashed { .. } => None,
         }
     }
 }
@@ -0,0 +1,196 @@
+//! Decides what happens to instances that end on their own.
+
+use std::collections::HashMap;
+use std::future;
+use std::time::Duration;
+
+use tokio::time::{Instant, sleep_until};
+
+use massive_applications::InstanceId;
+
+use crate::projects::RestartPolicy;
+
+/// Automatic restarts in a row after which an instance is considered to be in a crash loop.
+const MAX_RESTARTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// An instance that runs this long after a restart has recovered, its restarts are forgotten.
+const RECOVERY_DURATION: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+pub struct InstanceSupervisor {
+    restarts: HashMap<InstanceId, Restarts>,
+    /// Restarts that are waiting for their backoff to pass.
+    pending: Vec<(Instant, InstanceId)>,
+}
+
+#[derive(Debug)]
+struct Restarts {
+    count: u32,
+    last: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Supervision {
+    /// The instance is removed, as if the user stopped it.
+    Stop,
+    /// The crash is shown until the user restarts the instance.
+    Crashed { message: String },
+    /// The crash is shown until the instance is restarted after `delay`.
+    Restart { message: String, delay: Duration },
+}
+
+impl InstanceSupervisor {
+    /// Decide how to continue with an instance that ended on its own with `error`.
+    pub fn instance_ended(
+        &mut self,
+        instance: InstanceId,
+        policy: RestartPolicy,
+        error: Option<String>,
+        now: Instant,
+    ) -> Supervision {
+        let failed = error.is_some();
+        let message = error.unwrap_or_else(|| "The instance ended".into());
+
+        if !policy.restarts(failed) {
+            self.forget(instance);
+            return if failed {
+                Supervision::Crashed { message }
+            } else {
+                Supervision::Stop
+            };
+        }
+
+        let restarts = self.restarts.entry(instance).or_insert(Restarts {
+            count: 0,
+            last: now,
+        });
+        if now.duration_since(restarts.last) >= RECOVERY_DURATION {
+            restarts.count = 0;
+        }
+
+        if restarts.count >= MAX_RESTARTS {
+            // Forgotten, so that a restart by the user starts over.
+            self.forget(instance);
+            return Supervision::Crashed {
+                message: format!("{message} (gave up after {MAX_RESTARTS} restarts)"),
+            };
+        }
+
+        let delay = INITIAL_BACKOFF
+            .saturating_mul(2u32.saturating_pow(restarts.count))
+            .min(MAX_BACKOFF);
+        restarts.count += 1;
+        restarts.last = now + delay;
+        self.pending.push((now + delay, instance));
+
+        Supervision::Restart { message, delay }
+    }
+
+    /// Completes with the next instance whose restart is due. Never completes without pending
+    /// restarts.
+    ///
+    /// Cancel safe.
+    pub async fn next_restart(&mut self) -> InstanceId {
+        let Some(index) = (0..self.pending.len()).min_by_key(|index| self.pending[*index].0) else {
+            return future::pending().await;
+        };
+        sleep_until(self.pending[index].0).await;
+        self.pending.swap_remove(index).1
+    }
+
+    /// Forget the restarts of an instance, including a pending one.
+    pub fn forget(&mut self, instance: InstanceId) {
+        self.restarts.remove(&instance);
+        self.pending.retain(|(_, pending)| *pending != instance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn restarts_back_off_and_stop_in_a_crash_loop() {
+        let mut supervisor = InstanceSupervisor::default();
+        let instance: InstanceId = Uuid::new_v4().into();
+        let mut now = Instant::now();
+
+        let mut delays = Vec::new();
+        loop {
+            match supervisor.instance_ended(
+                instance,
+                RestartPolicy::OnFailure,
+                Some("crashed".into()),
+                now,
+            ) {
+                Supervision::Restart { delay, .. } => {
+                    delays.push(delay);
+                    now += delay;
+                }
+                Supervision::Crashed { message } => {
+                    assert!(message.starts_with("crashed"));
+                    break;
+                }
+                Supervision::Stop => panic!("A failed instance must not be stopped"),
+            }
+        }
+        assert_eq!(delays.len(), MAX_RESTARTS as usize);
+        assert!(delays.windows(2).all(|pair| pair[1] == pair[0] * 2));
+
+        // After giving up, a restart by the user starts over.
+        assert!(matches!(
+            supervisor.instance_ended(
+                instance,
+                RestartPolicy::OnFailure,
+                Some("crashed".into()),
+                now
+            ),
+            Supervision::Restart {
+                delay: INITIAL_BACKOFF,
+                ..
+            }
+        ));
+
+        // An instance that ran long enough has recovered.
+        now += RECOVERY_DURATION * 2;
+        assert!(matches!(
+            supervisor.instance_ended(
+                instance,
+                RestartPolicy::OnFailure,
+                Some("crashed".into()),
+                now
+            ),
+            Supervision::Restart {
+                delay: INITIAL_BACKOFF,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn policies_decide_about_restarts() {
+        let mut supervisor = InstanceSupervisor::default();
+        let instance: InstanceId = Uuid::new_v4().into();
+        let now = Instant::now();
+
+        assert_eq!(
+            supervisor.instance_ended(instance, RestartPolicy::Never, None, now),
+            Supervision::Stop
+        );
+        assert!(matches!(
+            supervisor.instance_ended(instance, RestartPolicy::Never, Some("failed".into()), now),
+            Supervision::Crashed { .. }
+        ));
+        assert_eq!(
+            supervisor.instance_ended(instance, RestartPolicy::OnFailure, None, now),
+            Supervision::Stop
+        );
+        assert!(matches!(
+            supervisor.instance_ended(instance, RestartPolicy::Always, None, now),
+            Supervision::Restart { .. }
+        ));
+    }
+}
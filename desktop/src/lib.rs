@@ -10,8 +10,10 @@ mod focus_target;
 mod hit_tester;
 mod instance_manager;
 mod instance_presenter;
+mod instance_supervisor;
 mod layout;
 mod projects;
+mod remote_instance;
 mod targeted_event;
 mod window_state;
 
@@ -1,9 +1,9 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use super::types::ProjectSpec;
+use super::types::{KeybindingSpec, ProjectSpec};
 
-/// Intermediate representation for deserializing JSON configuration files.
-#[derive(Debug, Deserialize)]
+/// Intermediate representation for deserializing and serializing JSON configuration files.
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigFile {
     /// The startup launch profile.
@@ -13,4 +13,8 @@ pub struct ConfigFile {
     /// The ordered project sections.
     #[serde(default)]
     pub projects: Vec<ProjectSpec>,
+
+    /// Overrides of the default desktop shortcuts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keybindings: Vec<KeybindingSpec>,
 }
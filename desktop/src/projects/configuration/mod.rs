@@ -8,11 +8,12 @@ pub use types::*;
 
 use json_reader::ConfigFile;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct ProjectConfiguration {
     /// The startup profile.
     pub startup: Option<String>,
     pub projects: Vec<ProjectSpec>,
+    pub keybindings: Vec<KeybindingSpec>,
 }
 
 impl ProjectConfiguration {
@@ -25,6 +26,19 @@ impl ProjectConfiguration {
         Ok(ProjectConfiguration {
             startup,
             projects: config.projects,
+            keybindings: config.keybindings,
         })
     }
+
+    /// Serialize the configuration to the same JSON schema [`Self::from_json`] reads.
+    pub fn to_json(&self, name: &str) -> Result<String> {
+        let config = ConfigFile {
+            startup: self.startup.clone(),
+            projects: self.projects.clone(),
+            keybindings: self.keybindings.clone(),
+        };
+
+        serde_json::to_string_pretty(&config)
+            .with_context(|| format!("Failed to serialize JSON configuration {name}"))
+    }
 }
@@ -1,7 +1,7 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectSpec {
     pub name: String,
@@ -9,35 +9,95 @@ pub struct ProjectSpec {
     pub launchers: Vec<LauncherSpec>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct LaunchProfile {
     pub name: String,
     #[serde(default)]
     pub mode: LauncherMode,
-    #[allow(unused)]
     #[serde(default)]
     pub tags: Vec<String>,
     #[serde(default)]
     pub params: Map<String, Value>,
+    #[serde(default)]
+    pub restart: RestartPolicy,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct LauncherSpec {
     pub name: String,
     pub column: u32,
     pub row: u32,
     #[serde(default)]
     pub mode: LauncherMode,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
     pub params: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "RestartPolicy::is_never")]
+    pub restart: RestartPolicy,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum LauncherMode {
     Band,
     #[default]
     Visor,
 }
+
+/// What happens to the instances of a launcher that end on their own.
+///
+/// Automatic restarts are delayed longer with every restart and stop after a few attempts. A
+/// failed instance that is not restarted shows its error until the user restarts it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+impl RestartPolicy {
+    pub fn is_never(&self) -> bool {
+        *self == Self::Never
+    }
+
+    /// Should an instance that ended be restarted automatically?
+    pub fn restarts(self, failed: bool) -> bool {
+        match self {
+            Self::Never => false,
+            Self::OnFailure => failed,
+            Self::Always => true,
+        }
+    }
+}
+
+/// Binds a key chord like `cmd+shift+t` to an action. Without an action, the chord is unbound.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KeybindingSpec {
+    pub keys: String,
+    #[serde(default)]
+    pub action: Option<KeyAction>,
+}
+
+/// The actions desktop shortcuts can trigger.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    /// Start a new instance with the parameters of the focused instance.
+    NewInstance,
+    /// Start a new instance with default parameters.
+    NewDefaultInstance,
+    CloseInstance,
+    /// Zoom to the default level of the keyboard focus.
+    ResetZoom,
+    ZoomIn,
+    ZoomOut,
+    NavigateLeft,
+    NavigateRight,
+    NavigateUp,
+    NavigateDown,
+    /// Start the focused instance again after it crashed.
+    RestartInstance,
+}
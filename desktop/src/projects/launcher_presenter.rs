@@ -151,6 +151,10 @@ impl LauncherPresenter {
         &self.profile.name
     }
 
+    pub fn profile(&self) -> &LaunchProfile {
+        &self.profile
+    }
+
     pub fn includes_overflow_children_in_hit_testing(&self) -> bool {
         match self.mode {
             LauncherMode::Band => false,
@@ -1,4 +1,5 @@
-use std::{fs, path::Path};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use log::warn;
@@ -7,12 +8,20 @@ mod configuration;
 mod launcher_presenter;
 mod project;
 mod project_presenter;
+mod reload;
+mod session;
 mod visor_layout;
+mod watcher;
 
 pub use self::configuration::*;
 pub use self::launcher_presenter::LauncherPresenter;
 pub use self::project::*;
 pub use self::project_presenter::ProjectPresenter;
+pub use self::reload::reload_commands;
+pub use self::session::*;
+pub use self::watcher::ConfigurationWatcher;
+
+const DESKTOP_CONFIG: &str = "desktop";
 
 impl ProjectConfiguration {
     /// Loads the configuration from the the project directory. If the project directory is not set,
@@ -22,9 +31,7 @@ impl ProjectConfiguration {
             return Ok(Self::default());
         };
 
-        const DESKTOP_CONFIG: &str = "desktop";
-
-        let path = projects_dir.join(format!("{DESKTOP_CONFIG}.json"));
+        let path = desktop_config_path(projects_dir);
 
         if !fs::exists(&path)? {
             warn!(
@@ -39,6 +46,32 @@ impl ProjectConfiguration {
 
         ProjectConfiguration::from_json(&json, DESKTOP_CONFIG)
     }
+
+    /// Writes the configuration to "desktop.json" in the project directory.
+    ///
+    /// The file is replaced atomically, so that a crash while saving does not leave a truncated
+    /// configuration behind.
+    pub fn save_to_dir(&self, projects_dir: &Path) -> Result<()> {
+        let json = self.to_json(DESKTOP_CONFIG)?;
+
+        fs::create_dir_all(projects_dir).with_context(|| {
+            format!(
+                "Failed to create project directory: {}",
+                projects_dir.display()
+            )
+        })?;
+
+        let path = desktop_config_path(projects_dir);
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, json)
+            .with_context(|| format!("Failed to write json file: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &path)
+            .with_context(|| format!("Failed to replace json file: {}", path.display()))
+    }
+}
+
+fn desktop_config_path(projects_dir: &Path) -> PathBuf {
+    projects_dir.join(format!("{DESKTOP_CONFIG}.json"))
 }
 
 impl Default for ProjectConfiguration {
@@ -56,10 +89,12 @@ impl Default for ProjectConfiguration {
                     mode: LauncherMode::Visor,
                     tags: Vec::new(),
                     params: Default::default(),
+                    restart: RestartPolicy::Never,
                 }]
                 .into(),
             }]
             .into(),
+            keybindings: Vec::new(),
         }
     }
 }
@@ -3,7 +3,9 @@ use anyhow::{Context, Result, anyhow};
 use derive_more::{From, Into};
 use uuid::Uuid;
 
-use crate::projects::configuration::{LaunchProfile, ProjectConfiguration, ProjectSpec};
+use crate::projects::configuration::{
+    LaunchProfile, LauncherSpec, ProjectConfiguration, ProjectSpec,
+};
 
 #[derive(Debug)]
 pub struct ProjectSet {
@@ -80,7 +82,28 @@ impl ProjectSet {
         Ok(Self { start, projects })
     }
 
-    #[allow(unused)]
+    /// Convert back into a configuration, the inverse of [`Self::from_configuration`].
+    ///
+    /// Ids are not persisted, the startup profile is referred to by name. Keybindings are not part
+    /// of the project set and are left empty.
+    pub fn to_configuration(&self) -> Result<ProjectConfiguration> {
+        let startup = match self.start {
+            Some(id) => Some(
+                self.get_launch_profile(id)
+                    .with_context(|| format!("Startup profile {id:?} not found in project set"))?
+                    .name
+                    .clone(),
+            ),
+            None => None,
+        };
+
+        Ok(ProjectConfiguration {
+            startup,
+            projects: self.projects.iter().map(convert_project_back).collect(),
+            keybindings: Vec::new(),
+        })
+    }
+
     pub fn get_launch_profile(&self, id: LaunchProfileId) -> Option<&LaunchProfile> {
         self.projects
             .iter()
@@ -120,6 +143,7 @@ fn convert_project(project: ProjectSpec) -> Project {
                     mode: launcher.mode,
                     tags: launcher.tags,
                     params: launcher.params,
+                    restart: launcher.restart,
                 },
                 placement: MatrixPlacement {
                     column: launcher.column,
@@ -129,3 +153,107 @@ fn convert_project(project: ProjectSpec) -> Project {
             .collect(),
     }
 }
+
+fn convert_project_back(project: &Project) -> ProjectSpec {
+    ProjectSpec {
+        name: project.properties.name.clone(),
+        launchers: project
+            .launchers
+            .iter()
+            .map(|launcher| LauncherSpec {
+                name: launcher.profile.name.clone(),
+                column: launcher.placement.column,
+                row: launcher.placement.row,
+                mode: launcher.profile.mode,
+                tags: launcher.profile.tags.clone(),
+                params: launcher.profile.params.clone(),
+                restart: launcher.profile.restart,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::projects::{LauncherMode, RestartPolicy};
+
+    #[test]
+    fn unchanged_configuration_round_trips() {
+        let configuration = load(CONFIGURATION);
+        let project_set = ProjectSet::from_configuration(load(CONFIGURATION)).unwrap();
+
+        assert_eq!(save_and_load(&project_set), configuration);
+    }
+
+    #[test]
+    fn mutated_configuration_round_trips() {
+        let mut project_set = ProjectSet::from_configuration(load(CONFIGURATION)).unwrap();
+
+        let tools = &mut project_set.projects[1];
+        tools.launchers.remove(0);
+        tools.launchers[0].placement = (0, 1).into();
+        let added = LaunchProfileId::new();
+        tools.launchers.push(Launcher {
+            id: added,
+            profile: LaunchProfile {
+                name: "monitor".into(),
+                mode: LauncherMode::Band,
+                tags: vec!["ops".into()],
+                params: json!({ "command": "htop" }).as_object().unwrap().clone(),
+                restart: RestartPolicy::OnFailure,
+            },
+            placement: (1, 1).into(),
+        });
+        project_set.projects.push(Project {
+            id: ProjectId::new(),
+            properties: ProjectProperties {
+                name: "scratch".into(),
+            },
+            launchers: Vec::new(),
+        });
+        project_set.start = Some(added);
+
+        let expected = project_set.to_configuration().unwrap();
+        let reloaded = save_and_load(&project_set);
+
+        assert_eq!(reloaded, expected);
+        assert_eq!(reloaded.startup.as_deref(), Some("monitor"));
+        let reloaded_set = ProjectSet::from_configuration(reloaded).unwrap();
+        assert_eq!(reloaded_set.to_configuration().unwrap(), expected);
+    }
+
+    const CONFIGURATION: &str = r#"{
+        "startup": "shell",
+        "projects": [
+            {
+                "name": "home",
+                "launchers": [
+                    { "name": "shell", "column": 0, "row": 0 }
+                ]
+            },
+            {
+                "name": "tools",
+                "launchers": [
+                    { "name": "logs", "column": 0, "row": 0, "mode": "band", "tags": ["ops"] },
+                    { "name": "editor", "column": 1, "row": 0, "params": { "path": "~/src" }, "restart": "always" }
+                ]
+            }
+        ]
+    }"#;
+
+    fn load(json: &str) -> ProjectConfiguration {
+        ProjectConfiguration::from_json(json, "test").unwrap()
+    }
+
+    fn save_and_load(project_set: &ProjectSet) -> ProjectConfiguration {
+        let json = project_set
+            .to_configuration()
+            .unwrap()
+            .to_json("test")
+            .unwrap();
+        load(&json)
+    }
+}
@@ -0,0 +1,224 @@
+//! Bring a live project set in line with a reloaded configuration.
+//!
+//! Projects are identified by their name, launchers by their name inside their project. Matched
+//! launchers keep their id, so their running instances stay untouched.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{LaunchProfileId, Launcher, Project, ProjectId, ProjectSet};
+use crate::desktop_system::ProjectCommand;
+
+/// The commands that change `live` into `reloaded`.
+///
+/// The commands are meant to be planned and applied one after the other, each one against the
+/// result of the previous one. New launchers are added before removed ones are removed, so that
+/// a project that only exchanges its launchers is not removed in between. Because adding and
+/// removing launchers shifts their neighbors, all launchers of such a project are moved to their
+/// configured placement at the end.
+///
+/// The order of the projects is not changed.
+pub fn reload_commands(live: &ProjectSet, reloaded: &ProjectSet) -> Vec<ProjectCommand> {
+    let mut commands = Vec::new();
+    let live_projects: HashMap<&str, &Project> = live
+        .projects
+        .iter()
+        .map(|project| (project.properties.name.as_str(), project))
+        .collect();
+
+    // The live ids of the reloaded launchers.
+    let mut launcher_ids: HashMap<LaunchProfileId, LaunchProfileId> = HashMap::new();
+    let mut matched_projects = HashSet::new();
+    let mut removed_launchers = Vec::new();
+    let mut moves = Vec::new();
+    let mut previous_project = None;
+
+    for project in &reloaded.projects {
+        let Some(live_project) = live_projects.get(project.properties.name.as_str()) else {
+            commands.push(ProjectCommand::AddProject {
+                id: project.id,
+                properties: project.properties.clone(),
+                after: previous_project,
+            });
+            for launcher in &project.launchers {
+                commands.push(add_launcher(project.id, launcher));
+            }
+            previous_project = Some(project.id);
+            continue;
+        };
+        matched_projects.insert(live_project.id);
+        previous_project = Some(live_project.id);
+
+        let mut live_launchers: HashMap<&str, &Launcher> = live_project
+            .launchers
+            .iter()
+            .map(|launcher| (launcher.profile.name.as_str(), launcher))
+            .collect();
+
+        let mut project_moves = Vec::new();
+        let mut launchers_changed = false;
+        for launcher in &project.launchers {
+            match live_launchers.remove(launcher.profile.name.as_str()) {
+                Some(live_launcher) if live_launcher.profile == launcher.profile => {
+                    launcher_ids.insert(launcher.id, live_launcher.id);
+                    project_moves.push((
+                        live_launcher.id,
+                        live_launcher.placement,
+                        launcher.placement,
+                    ));
+                }
+                live_launcher => {
+                    // A changed profile replaces the launcher.
+                    if let Some(live_launcher) = live_launcher {
+                        removed_launchers.push(live_launcher.id);
+                    }
+                    commands.push(add_launcher(live_project.id, launcher));
+                    project_moves.push((launcher.id, launcher.placement, launcher.placement));
+                    launchers_changed = true;
+                }
+            }
+        }
+
+        if !live_launchers.is_empty() {
+            removed_launchers.extend(live_launchers.values().map(|launcher| launcher.id));
+            launchers_changed = true;
+        }
+
+        moves.extend(
+            project_moves
+                .into_iter()
+                .filter(|(_, from, to)| launchers_changed || from != to)
+                .map(|(id, _, placement)| (id, placement)),
+        );
+    }
+
+    commands.extend(
+        removed_launchers
+            .into_iter()
+            .map(ProjectCommand::RemoveLauncher),
+    );
+
+    commands.extend(
+        live.projects
+            .iter()
+            .filter(|project| !matched_projects.contains(&project.id))
+            .map(|project| ProjectCommand::RemoveProject(project.id)),
+    );
+
+    commands.extend(
+        moves
+            .into_iter()
+            .map(|(id, placement)| ProjectCommand::MoveLauncher { id, placement }),
+    );
+
+    let start = reloaded
+        .start
+        .map(|start| launcher_ids.get(&start).copied().unwrap_or(start));
+    if start != live.start {
+        commands.push(ProjectCommand::SetStartupProfile(start));
+    }
+
+    commands
+}
+
+fn add_launcher(project: ProjectId, launcher: &Launcher) -> ProjectCommand {
+    ProjectCommand::AddLauncher {
+        project,
+        id: launcher.id,
+        profile: launcher.profile.clone(),
+        placement: launcher.placement,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projects::{MatrixPlacement, ProjectConfiguration};
+
+    const CONFIGURATION: &str = r#"{
+        "startup": "shell",
+        "projects": [
+            {
+                "name": "home",
+                "launchers": [
+                    { "name": "shell", "column": 0, "row": 0 },
+                    { "name": "browser", "column": 1, "row": 0 }
+                ]
+            },
+            {
+                "name": "tools",
+                "launchers": [
+                    { "name": "logs", "column": 0, "row": 0, "mode": "band" }
+                ]
+            }
+        ]
+    }"#;
+
+    fn project_set(json: &str) -> ProjectSet {
+        ProjectSet::from_configuration(ProjectConfiguration::from_json(json, "test").unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn unchanged_configuration_produces_no_commands() {
+        let live = project_set(CONFIGURATION);
+        let reloaded = project_set(CONFIGURATION);
+
+        assert!(reload_commands(&live, &reloaded).is_empty());
+    }
+
+    #[test]
+    fn moved_launcher_keeps_its_id() {
+        let live = project_set(CONFIGURATION);
+        let reloaded = project_set(&CONFIGURATION.replace(
+            r#""browser", "column": 1, "row": 0"#,
+            r#""browser", "column": 0, "row": 1"#,
+        ));
+        let browser = live.projects[0].launchers[1].id;
+
+        let commands = reload_commands(&live, &reloaded);
+
+        assert_eq!(commands.len(), 1);
+        assert!(matches!(
+            commands[0],
+            ProjectCommand::MoveLauncher { id, placement: MatrixPlacement { column: 0, row: 1 } }
+                if id == browser
+        ));
+    }
+
+    #[test]
+    fn changes_are_added_before_removed() {
+        let live = project_set(CONFIGURATION);
+        let reloaded = project_set(
+            &CONFIGURATION
+                .replace(r#""mode": "band""#, r#""mode": "visor""#)
+                .replace(r#""name": "home""#, r#""name": "work""#),
+        );
+
+        let commands = reload_commands(&live, &reloaded);
+
+        let kinds: Vec<_> = commands
+            .iter()
+            .map(|command| match command {
+                ProjectCommand::AddProject { .. } => "add project",
+                ProjectCommand::AddLauncher { .. } => "add launcher",
+                ProjectCommand::RemoveLauncher(_) => "remove launcher",
+                ProjectCommand::RemoveProject(_) => "remove project",
+                ProjectCommand::MoveLauncher { .. } => "move launcher",
+                ProjectCommand::SetStartupProfile(_) => "startup",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "add project",
+                "add launcher",
+                "add launcher",
+                "add launcher",
+                "remove launcher",
+                "remove project",
+                "move launcher",
+                "startup"
+            ]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! The instances that were running when the desktop exited, so that they can be started again.
+//!
+//! Like on reload, projects are identified by their name and launchers by their name inside their
+//! project. Instances keep their id.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use massive_applications::{InstanceId, InstanceParameters};
+
+use crate::desktop_system::FocusDepth;
+
+const SESSION_FILE: &str = "session";
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// Launchers without instances are not listed.
+    #[serde(default)]
+    pub launchers: Vec<LauncherSession>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus: Option<SessionFocus>,
+    /// Whether the focused instance or an overview of its surroundings is shown.
+    #[serde(default)]
+    pub focus_depth: FocusDepth,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LauncherSession {
+    pub project: String,
+    pub launcher: String,
+    /// In presentation order.
+    pub instances: Vec<InstanceSession>,
+    /// The instance the visor is centered on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<InstanceId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct InstanceSession {
+    pub id: InstanceId,
+    #[serde(default, skip_serializing_if = "InstanceParameters::is_empty")]
+    pub parameters: InstanceParameters,
+}
+
+/// The keyboard focus. Views are represented by their instance.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionFocus {
+    Project { project: String },
+    Launcher { project: String, launcher: String },
+    Instance(InstanceId),
+}
+
+impl Session {
+    /// Loads the session from "session.json" in the project directory. Without a file, the
+    /// session is empty.
+    pub fn from_dir(projects_dir: &Path) -> Result<Self> {
+        let path = session_path(projects_dir);
+        if !fs::exists(&path)? {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read json file: {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse session: {}", path.display()))
+    }
+
+    /// Writes the session to "session.json" in the project directory, replacing it atomically.
+    pub fn save_to_dir(&self, projects_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize session")?;
+
+        fs::create_dir_all(projects_dir).with_context(|| {
+            format!(
+                "Failed to create project directory: {}",
+                projects_dir.display()
+            )
+        })?;
+
+        let path = session_path(projects_dir);
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, json)
+            .with_context(|| format!("Failed to write json file: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &path)
+            .with_context(|| format!("Failed to replace json file: {}", path.display()))
+    }
+}
+
+fn session_path(projects_dir: &Path) -> PathBuf {
+    projects_dir.join(format!("{SESSION_FILE}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn session_survives_a_round_trip() {
+        let instance: InstanceId = Uuid::new_v4().into();
+        let session = Session {
+            launchers: vec![LauncherSession {
+                project: "Work".into(),
+                launcher: "Terminal".into(),
+                instances: vec![
+                    InstanceSession {
+                        id: instance,
+                        parameters: json!({ "command": "htop" }).as_object().unwrap().clone(),
+                    },
+                    InstanceSession {
+                        id: Uuid::new_v4().into(),
+                        parameters: InstanceParameters::new(),
+                    },
+                ],
+                center: Some(instance),
+            }],
+            focus: Some(SessionFocus::Instance(instance)),
+            focus_depth: FocusDepth::Launcher,
+        };
+
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(serde_json::from_str::<Session>(&json).unwrap(), session);
+        assert_eq!(
+            serde_json::from_str::<Session>("{}").unwrap(),
+            Session::default()
+        );
+    }
+}
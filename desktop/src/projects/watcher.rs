@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use tokio::time::{Interval, MissedTickBehavior, interval};
+
+use super::desktop_config_path;
+
+/// Detects changes of "desktop.json" in the project directory by polling its modification time.
+///
+/// Polling also sees files that are replaced by a rename, like editors and
+/// [`ProjectConfiguration::save_to_dir`](super::ProjectConfiguration::save_to_dir) do.
+#[derive(Debug)]
+pub struct ConfigurationWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    interval: Interval,
+}
+
+impl ConfigurationWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(projects_dir: &Path) -> Self {
+        let path = desktop_config_path(projects_dir);
+        let mut interval = interval(Self::POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            modified: modified_time(&path),
+            path,
+            interval,
+        }
+    }
+
+    /// Completes when the file was changed or created. A removed file is not reported.
+    ///
+    /// Cancel safe.
+    pub async fn changed(&mut self) {
+        loop {
+            self.interval.tick().await;
+            let modified = modified_time(&self.path);
+            if modified == self.modified {
+                continue;
+            }
+            self.modified = modified;
+            if modified.is_some() {
+                return;
+            }
+        }
+    }
+
+    /// Accept the current file as seen, so that the desktop's own saves are not reported.
+    pub fn mark_seen(&mut self) {
+        self.modified = modified_time(&self.path);
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,293 @@
+//! Instances that run in a separate process.
+//!
+//! The executable is named by the launcher's parameters. See [`massive_applications::RemoteStart`]
+//! for the protocol.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::process::Stdio;
+
+use anyhow::{Context, Result, bail};
+use log::warn;
+use serde_json::Value;
+use tokio::io::BufReader;
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use massive_applications::{
+    DesktopMessage, InstanceChange, InstanceContext, InstanceMessage, InstanceParameters,
+    RemoteChange, receive_message, send_message,
+};
+use massive_renderer::{ChangeDecoder, RenderPacing};
+use massive_scene::{
+    Change, Id, Location, LocationRenderObj, SceneChange, Transform, Visual, VisualRenderObj,
+    id_generator,
+};
+
+/// The name the remote application is registered with.
+pub const REMOTE_APPLICATION: &str = "remote";
+/// The launcher parameter that names the executable of a remote instance.
+pub const EXECUTABLE_PARAMETER: &str = "executable";
+/// The launcher parameter for the command line arguments of the executable, an array of strings.
+pub const ARGUMENTS_PARAMETER: &str = "args";
+
+pub fn is_remote(parameters: &InstanceParameters) -> bool {
+    parameters.contains_key(EXECUTABLE_PARAMETER)
+}
+
+/// Starts the executable and forwards its submissions and events until it closes its stdout.
+pub async fn run_remote_instance(mut context: InstanceContext) -> Result<()> {
+    let Some(parameters) = context.parameters() else {
+        bail!("Remote instances can't be restored");
+    };
+    let executable = parameters
+        .get(EXECUTABLE_PARAMETER)
+        .and_then(Value::as_str)
+        .with_context(|| format!("Launcher parameter `{EXECUTABLE_PARAMETER}` is missing"))?
+        .to_string();
+    let arguments: Vec<String> = match parameters.get(ARGUMENTS_PARAMETER) {
+        Some(arguments) => serde_json::from_value(arguments.clone()).with_context(|| {
+            format!("Launcher parameter `{ARGUMENTS_PARAMETER}` must be an array of strings")
+        })?,
+        None => Vec::new(),
+    };
+
+    let mut child = Command::new(&executable)
+        .args(&arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Starting `{executable}`"))?;
+    let mut stdin = child.stdin.take().context("Remote instance has no stdin")?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("Remote instance has no stdout")?;
+
+    let start = DesktopMessage::start(
+        context.id(),
+        context.creation_mode(),
+        context.primary_monitor_scale_factor(),
+    )?;
+    send_message(&mut stdin, &start).await?;
+
+    // Receiving is not cancel safe, so it can't be used in `select!`.
+    let (messages_tx, messages_rx) = unbounded_channel();
+    tokio::spawn(async move {
+        let mut stdout = BufReader::new(stdout);
+        while let Some(message) = receive_message::<InstanceMessage>(&mut stdout)
+            .await
+            .transpose()
+        {
+            let failed = message.is_err();
+            if messages_tx.send(message).is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let mut ids = RemoteIds::new(context.view_parent().id());
+    let result = forward(&mut context, &mut stdin, messages_rx, &mut ids).await;
+
+    // Objects the process did not delete would stay in the renderer.
+    context.submit_changes(ids.delete_all(), RenderPacing::Fast)?;
+    result?;
+
+    drop(stdin);
+    let status = child.wait().await?;
+    if !status.success() {
+        bail!("`{executable}` exited with {status}");
+    }
+    Ok(())
+}
+
+async fn forward(
+    context: &mut InstanceContext,
+    stdin: &mut ChildStdin,
+    mut messages: UnboundedReceiver<Result<InstanceMessage>>,
+    ids: &mut RemoteIds,
+) -> Result<()> {
+    let mut decoder = ChangeDecoder::new(context.fonts().clone());
+
+    loop {
+        tokio::select! {
+            message = messages.recv() => {
+                let Some(message) = message else {
+                    return Ok(());
+                };
+                let InstanceMessage::Submission { changes, pacing } = message?;
+                let changes = RemoteChange::decode_all(changes, &mut decoder)?
+                    .into_iter()
+                    .map(|change| ids.map_change(change))
+                    .collect::<Result<Vec<_>>>()?;
+                context.submit_changes(changes, pacing)?;
+            }
+            event = context.wait_for_event() => {
+                let message = DesktopMessage::from(event?);
+                // A failure here means that the process is gone, which is detected by the receiver.
+                if let Err(e) = send_message(stdin, &message).await {
+                    warn!("Failed to send an event to a remote instance: {e:?}");
+                }
+            }
+        }
+    }
+}
+
+/// Maps the scene ids of an instance process into the identity space of the desktop.
+///
+/// Locations without a parent are attached to the view parent of the instance.
+#[derive(Debug)]
+struct RemoteIds {
+    view_parent: Id,
+    ids: HashMap<(TypeId, Id), Id>,
+}
+
+impl RemoteIds {
+    fn new(view_parent: Id) -> Self {
+        Self {
+            view_parent,
+            ids: HashMap::new(),
+        }
+    }
+
+    fn map_change(&mut self, change: InstanceChange) -> Result<InstanceChange> {
+        let InstanceChange::Scene(change) = change else {
+            return Ok(change);
+        };
+
+        let change = match change {
+            SceneChange::Transform(change) => self
+                .map::<Transform, _>(change, |_, transform| Ok(transform))?
+                .into(),
+            SceneChange::Location(change) => self
+                .map::<Location, _>(change, |ids, location| {
+                    Ok(LocationRenderObj {
+                        parent: Some(match location.parent {
+                            Some(parent) => ids.local::<Location>(parent)?,
+                            None => ids.view_parent,
+                        }),
+                        transform: ids.local::<Transform>(location.transform)?,
+                        ..location
+                    })
+                })?
+                .into(),
+            SceneChange::Visual(change) => self
+                .map::<Visual, _>(change, |ids, visual| {
+                    Ok(VisualRenderObj {
+                        location: ids.local::<Location>(visual.location)?,
+                        ..visual
+                    })
+                })?
+                .into(),
+        };
+        Ok(InstanceChange::Scene(change))
+    }
+
+    fn map<O: 'static, T>(
+        &mut self,
+        change: Change<T>,
+        map_value: impl FnOnce(&Self, T) -> Result<T>,
+    ) -> Result<Change<T>> {
+        let key = TypeId::of::<O>();
+        Ok(match change {
+            Change::Create(id, value) => {
+                let value = map_value(self, value)?;
+                if self.ids.contains_key(&(key, id)) {
+                    bail!("Remote object {id:?} was created twice");
+                }
+                let local = id_generator::acquire::<O>();
+                self.ids.insert((key, id), local);
+                Change::Create(local, value)
+            }
+            Change::Update(id, value) => {
+                Change::Update(self.local::<O>(id)?, map_value(self, value)?)
+            }
+            Change::Delete(id) => Change::Delete(
+                self.ids
+                    .remove(&(key, id))
+                    .with_context(|| format!("Remote object {id:?} is unknown"))?,
+            ),
+        })
+    }
+
+    fn local<O: 'static>(&self, id: Id) -> Result<Id> {
+        self.ids
+            .get(&(TypeId::of::<O>(), id))
+            .copied()
+            .with_context(|| format!("Remote object {id:?} is unknown"))
+    }
+
+    /// Delete all remaining objects, visuals first.
+    fn delete_all(&mut self) -> Vec<InstanceChange> {
+        let mut deletes: Vec<_> = self.ids.drain().collect();
+        let order = |type_id: TypeId| {
+            [
+                TypeId::of::<Visual>(),
+                TypeId::of::<Location>(),
+                TypeId::of::<Transform>(),
+            ]
+            .iter()
+            .position(|t| *t == type_id)
+        };
+        deletes.sort_by_key(|((type_id, _), _)| order(*type_id));
+
+        deletes
+            .into_iter()
+            .map(|((type_id, _), id)| {
+                let change: SceneChange = if type_id == TypeId::of::<Visual>() {
+                    Change::<VisualRenderObj>::Delete(id).into()
+                } else if type_id == TypeId::of::<Location>() {
+                    Change::<LocationRenderObj>::Delete(id).into()
+                } else {
+                    Change::<Transform>::Delete(id).into()
+                };
+                InstanceChange::Scene(change)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_ids_are_mapped_into_the_desktop() {
+        let view_parent = id_generator::acquire::<Location>();
+        let mut ids = RemoteIds::new(view_parent);
+        let scene = |change: SceneChange| InstanceChange::Scene(change);
+
+        let InstanceChange::Scene(SceneChange::Transform(Change::Create(transform, _))) = ids
+            .map_change(scene(
+                Change::Create(Id::from_raw(0), Transform::IDENTITY).into(),
+            ))
+            .unwrap()
+        else {
+            panic!("Expected a transform");
+        };
+
+        let location = LocationRenderObj {
+            parent: None,
+            transform: Id::from_raw(0),
+            alpha: 1.0,
+            layer: false,
+        };
+        let InstanceChange::Scene(SceneChange::Location(Change::Create(_, location))) = ids
+            .map_change(scene(Change::Create(Id::from_raw(0), location).into()))
+            .unwrap()
+        else {
+            panic!("Expected a location");
+        };
+        assert_eq!(location.parent, Some(view_parent));
+        assert_eq!(location.transform, transform);
+
+        // Unknown remote ids are rejected.
+        assert!(
+            ids.map_change(scene(Change::<Transform>::Delete(Id::from_raw(1)).into()))
+                .is_err()
+        );
+
+        assert_eq!(ids.delete_all().len(), 2);
+    }
+}
@@ -296,11 +296,7 @@ async fn application(mut ctx: ApplicationContext) -> Result<()> {
             }
         }
 
-        // DI: This check has to be done in the renderer and the renderer has to decide when it
-        // needs to redraw.
-        transform.update_if_changed(application.get_transform(content_size));
-
-        ctx.frame(&scene).render_to(&mut renderer)?;
+        application.render(ctx.frame(&scene), &mut renderer)?;
     }
 }
 
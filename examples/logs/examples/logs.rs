@@ -121,7 +121,7 @@ async fn logs(mut receiver: UnboundedReceiver<Vec<u8>>, mut ctx: ApplicationCont
             }
         }
 
-        frame.render_to(&mut renderer)?;
+        logs.application.render(frame, &mut renderer)?;
     }
 }
 
@@ -140,7 +140,6 @@ struct Logs {
 
     application: Application,
 
-    application_transform: Handle<Transform>,
     layout: Movement<LayoutMovement>,
     location: Handle<Location>,
     lines: VecDeque<LogLine>,
@@ -153,8 +152,11 @@ impl Logs {
         let content_width = 1280;
         let application = Application::default();
 
-        let application_transform = application.get_transform((0, 0)).enter(scene);
-        let application_location = application_transform.to_location().enter(scene);
+        let application_location = application
+            .get_transform((0, 0))
+            .enter(scene)
+            .to_location()
+            .enter(scene);
 
         // Keep interaction transforms separate so the movement owns only animated centering.
         let content_transform = Transform::from_xy(-(content_width as f64) / 2., 0.).enter(scene);
@@ -191,7 +193,6 @@ impl Logs {
         Self {
             fonts,
             application,
-            application_transform,
             layout,
             location,
             lines: VecDeque::new(),
@@ -299,9 +300,6 @@ impl Logs {
             UpdateResponse::Continue => {}
         }
 
-        self.application_transform
-            .update_if_changed(self.application.get_transform((0, 0)));
-
         UpdateResponse::Continue
     }
 
@@ -176,9 +176,7 @@ async fn emojis(mut ctx: ApplicationContext) -> Result<()> {
             }
         }
 
-        transform.update_if_changed(application.get_transform(content_size));
-
-        ctx.frame(&scene).render_to(&mut renderer)?;
+        application.render(ctx.frame(&scene), &mut renderer)?;
     }
 }
 
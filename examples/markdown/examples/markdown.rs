@@ -119,7 +119,6 @@ async fn application(mut ctx: ApplicationContext) -> Result<()> {
                         UpdateResponse::Continue => {}
                     }
 
-                    transform.update_if_changed(application.get_transform(content_size));
                     renderer.resize_redraw(&view_event)?;
                 }
                 ApplicationEvent::View(..)
@@ -129,7 +128,7 @@ async fn application(mut ctx: ApplicationContext) -> Result<()> {
             }
         }
 
-        ctx.frame(&scene).render_to(&mut renderer)?;
+        application.render(ctx.frame(&scene), &mut renderer)?;
     }
 }
 
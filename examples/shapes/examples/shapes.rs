@@ -4,10 +4,12 @@ use anyhow::Result;
 use winit::dpi::LogicalSize;
 
 use massive_applications::ApplicationEvent;
-use massive_geometry::{Color, Rect, Size};
+use massive_geometry::{Color, Point, Rect, Size};
 use massive_scene::{At, Object, ToLocation};
 use massive_shapes::{
-    BeveledRect, Circle, Ellipse, Rect as FilledRect, RoundRect, Shape, StrokeRect,
+    BeveledRect, BoxShadow, Circle, Ellipse, FillRule, Image, ImageData, ImageHandle,
+    ImageSampling, LineCap, LineJoin, Paint, Path, PathStroke, Rect as FilledRect, RoundRect,
+    Shape, StrokeRect,
 };
 use massive_shell::ApplicationContext;
 use massive_shell::shell;
@@ -40,16 +42,16 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
     let mut shapes: Vec<Shape> = vec![
         Shape::Rect(FilledRect {
             rect: Rect::new((0.0, 0.0), Size::new(200.0, 120.0)),
-            color: Color::from((0.9, 0.2, 0.2, 1.0)),
+            paint: Color::from((0.9, 0.2, 0.2, 1.0)).into(),
         }),
         Shape::RoundRect(RoundRect {
             rect: Rect::new((240.0, 0.0), Size::new(200.0, 120.0)),
             corner_radius: 24.0,
-            color: Color::from((0.2, 0.7, 0.3, 1.0)),
+            paint: Color::from((0.2, 0.7, 0.3, 1.0)).into(),
         }),
         Shape::Circle(Circle {
             rect: Rect::new((480.0, 0.0), Size::new(120.0, 120.0)),
-            color: Color::from((0.2, 0.3, 0.9, 1.0)),
+            paint: Color::from((0.2, 0.3, 0.9, 1.0)).into(),
         }),
         Shape::StrokeRect(StrokeRect {
             rect: Rect::new((640.0, 0.0), Size::new(200.0, 120.0)),
@@ -58,7 +60,7 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
         }),
         Shape::Ellipse(Ellipse {
             rect: Rect::new((880.0, 0.0), Size::new(180.0, 120.0)),
-            color: Color::from((0.4, 0.85, 0.4, 1.0)),
+            paint: Color::from((0.4, 0.85, 0.4, 1.0)).into(),
         }),
         // Chamfer rect: all corners beveled (default)
         Shape::BeveledRect(BeveledRect::new(
@@ -122,20 +124,20 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
         // Overlapping translucent stack
         Shape::Rect(FilledRect {
             rect: Rect::new((0.0, 180.0), Size::new(240.0, 160.0)),
-            color: Color::from((0.2, 0.9, 0.9, 0.6)),
+            paint: Color::from((0.2, 0.9, 0.9, 0.6)).into(),
         }),
         Shape::Circle(Circle {
             rect: Rect::new((80.0, 220.0), Size::new(160.0, 160.0)),
-            color: Color::from((0.9, 0.2, 0.9, 0.6)),
+            paint: Color::from((0.9, 0.2, 0.9, 0.6)).into(),
         }),
         Shape::RoundRect(RoundRect {
             rect: Rect::new((160.0, 260.0), Size::new(180.0, 160.0)),
             corner_radius: 32.0,
-            color: Color::from((0.9, 0.6, 0.2, 0.6)),
+            paint: Color::from((0.9, 0.6, 0.2, 0.6)).into(),
         }),
         Shape::Ellipse(Ellipse {
             rect: Rect::new((280.0, 210.0), Size::new(200.0, 120.0)),
-            color: Color::from((0.2, 0.5, 0.85, 0.5)),
+            paint: Color::from((0.2, 0.5, 0.85, 0.5)).into(),
         }),
     ];
 
@@ -146,11 +148,123 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
             let py = 180.0 + y as f64 * 28.0;
             shapes.push(Shape::Circle(Circle {
                 rect: Rect::new((px, py), Size::new(20.0, 20.0)),
-                color: Color::from((0.3, 0.3 + x as f32 * 0.05, 0.5 + y as f32 * 0.08, 0.85)),
+                paint: Color::from((0.3, 0.3 + x as f32 * 0.05, 0.5 + y as f32 * 0.08, 0.85))
+                    .into(),
             }));
         }
     }
 
+    // Gradients
+    shapes.push(Shape::Rect(FilledRect::new(
+        Rect::new((0.0, 620.0), Size::new(240.0, 120.0)),
+        Paint::linear_gradient(
+            (0.0, 0.0),
+            (1.0, 0.0),
+            [
+                (0.0, Color::from((0.9, 0.2, 0.2, 1.0))),
+                (0.5, Color::from((0.95, 0.8, 0.1, 1.0))),
+                (1.0, Color::from((0.2, 0.7, 0.3, 1.0))),
+            ],
+        ),
+    )));
+    shapes.push(Shape::RoundRect(RoundRect::new(
+        Rect::new((280.0, 620.0), Size::new(240.0, 120.0)),
+        24.0,
+        Paint::linear_gradient(
+            (0.0, 0.0),
+            (0.0, 1.0),
+            [
+                (0.0, Color::from((0.2, 0.3, 0.9, 1.0))),
+                (1.0, Color::from((0.2, 0.3, 0.9, 0.0))),
+            ],
+        ),
+    )));
+    shapes.push(Shape::Circle(Circle::new(
+        Rect::new((560.0, 620.0), Size::new(120.0, 120.0)),
+        Paint::radial_gradient(
+            (0.35, 0.35),
+            0.75,
+            [
+                (0.0, Color::WHITE),
+                (1.0, Color::from((0.85, 0.4, 0.7, 1.0))),
+            ],
+        ),
+    )));
+
+    // A card with a drop shadow. The shadow comes first, so that it is drawn below.
+    let card = Rect::new((880.0, 560.0), Size::new(240.0, 160.0));
+    shapes.push(Shape::BoxShadow(
+        BoxShadow::new(card, 16.0, 24.0, Color::from((0.0, 0.0, 0.0, 0.45)))
+            .with_offset((0.0, 12.0))
+            .with_spread(-4.0),
+    ));
+    shapes.push(Shape::RoundRect(RoundRect::new(card, 16.0, Color::WHITE)));
+
+    // An 8x8 checkerboard image, scaled up without smoothing, and its top left quarter smoothed.
+    let checkerboard = ImageHandle::new(ImageData::new(
+        (8, 8),
+        (0..64)
+            .flat_map(|i| {
+                if (i % 8 + i / 8) % 2 == 0 {
+                    [40, 40, 40, 255]
+                } else {
+                    [230, 120, 40, 255]
+                }
+            })
+            .collect(),
+    )?);
+    shapes.push(Shape::Image(
+        Image::new(
+            Rect::new((1180.0, 560.0), Size::new(160.0, 160.0)),
+            checkerboard.clone(),
+        )
+        .with_sampling(ImageSampling::Nearest),
+    ));
+    shapes.push(Shape::Image(
+        Image::new(
+            Rect::new((1360.0, 560.0), Size::new(160.0, 160.0)),
+            checkerboard,
+        )
+        .with_uv((0.0, 0.0), (0.5, 0.5)),
+    ));
+
+    // Paths: a star (even-odd leaves the center empty), a connector curve, and a polyline.
+    let star = (0..5).fold(Path::builder(), |builder, i| {
+        let angle = std::f64::consts::PI * (0.5 + i as f64 * 0.8);
+        let point = (1180.0 + 60.0 * angle.cos(), 240.0 - 60.0 * angle.sin());
+        if i == 0 {
+            builder.move_to(point)
+        } else {
+            builder.line_to(point)
+        }
+    });
+    shapes.push(Shape::Path(
+        star.close()
+            .build()
+            .filled_with(FillRule::EvenOdd, Color::from((0.95, 0.8, 0.1, 1.0)))
+            .stroked(
+                PathStroke::new(3.0, Color::from((0.6, 0.4, 0.0, 1.0))).with_join(LineJoin::Round),
+            ),
+    ));
+    shapes.push(Shape::Path(
+        Path::builder()
+            .move_to((880.0, 180.0))
+            .cubic_to((980.0, 180.0), (980.0, 340.0), (1080.0, 340.0))
+            .build()
+            .stroked(
+                PathStroke::new(4.0, Color::from((0.3, 0.3, 0.3, 1.0))).with_cap(LineCap::Round),
+            ),
+    ));
+    shapes.push(Shape::Path(
+        Path::builder()
+            .move_to((880.0, 420.0))
+            .line_to((940.0, 380.0))
+            .quad_to((1000.0, 340.0), (1060.0, 400.0))
+            .line_to((1120.0, 360.0))
+            .build()
+            .stroked(PathStroke::new(6.0, Color::from((0.2, 0.5, 0.85, 1.0)))),
+    ));
+
     // Compute bounding box of all shapes to derive page size and center transform.
     use massive_geometry::Rect as GeoRect;
     let mut bounds: Option<GeoRect> = None;
@@ -162,6 +276,12 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
             Shape::StrokeRect(r) => r.rect,
             Shape::Ellipse(e) => e.rect,
             Shape::BeveledRect(r) => r.rect,
+            Shape::BoxShadow(s) => s.rect,
+            Shape::Image(i) => i.rect,
+            Shape::Path(p) => match p.bounds() {
+                Some(bounds) => bounds,
+                None => continue,
+            },
             Shape::GlyphRun(..) | Shape::Custom(..) => continue,
         };
         bounds = Some(if let Some(b) = bounds { b.joined(r) } else { r });
@@ -213,6 +333,23 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
                 r.rect.top += offset_y;
                 r.rect.bottom += offset_y;
             }
+            Shape::BoxShadow(s) => {
+                s.rect.left += offset_x;
+                s.rect.right += offset_x;
+                s.rect.top += offset_y;
+                s.rect.bottom += offset_y;
+            }
+            Shape::Image(i) => {
+                i.rect.left += offset_x;
+                i.rect.right += offset_x;
+                i.rect.top += offset_y;
+                i.rect.bottom += offset_y;
+            }
+            Shape::Path(p) => {
+                *p = p
+                    .clone()
+                    .map_points(|point| point + Point::new(offset_x, offset_y));
+            }
             Shape::GlyphRun(_) | Shape::Custom(..) => {}
         }
     }
@@ -224,6 +361,24 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
 
     let _visual = shapes.at(&location).with_decal_order(0).enter(&scene);
 
+    // A frosted glass panel in the center that blurs the shapes behind it.
+    let panel = Rect::new(
+        (
+            page_width as f64 / 2.0 - 200.0,
+            page_height as f64 / 2.0 - 80.0,
+        ),
+        Size::new(400.0, 160.0),
+    );
+    let _panel = Shape::RoundRect(RoundRect::new(
+        panel,
+        20.0,
+        Color::from((1.0, 1.0, 1.0, 0.25)),
+    ))
+    .at(&location)
+    .with_decal_order(1)
+    .with_backdrop_blur(8.0)
+    .enter(&scene);
+
     loop {
         for event in ctx.wait_for_events::<Infallible>().await? {
             match event {
@@ -232,8 +387,6 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
                         UpdateResponse::Exit => return Ok(()),
                         UpdateResponse::Continue => {}
                     }
-                    transform
-                        .update_if_changed(application.get_transform((page_width, page_height)));
                     renderer.resize_redraw(&view_event)?;
                 }
                 ApplicationEvent::View(..)
@@ -243,6 +396,6 @@ async fn run(mut ctx: ApplicationContext) -> Result<()> {
             }
         }
 
-        ctx.frame(&scene).render_to(&mut renderer)?;
+        application.render(ctx.frame(&scene), &mut renderer)?;
     }
 }
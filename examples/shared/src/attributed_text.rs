@@ -3,12 +3,10 @@ use std::ops::Range;
 use serde::{Deserialize, Serialize};
 use serde_tuple::{Deserialize_tuple, Serialize_tuple};
 
-use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight, Wrap};
+use cosmic_text::{Family, FontSystem};
 
 use massive_geometry::{Color, Vector3};
-use massive_shapes::{GlyphRun, TextWeight};
-
-use crate::positioning;
+use massive_shapes::{GlyphRun, ParagraphStyle, TextAttributes, TextShaper, TextWeight};
 
 /// A serializable representation of highlighted code.
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,51 +38,35 @@ pub fn shape_text(
         text.len(),
     );
 
-    // The text covers everything. If the attributes are appearing without adjusted metadata,
-    // something is wrong.
-    //
-    // Set it to an illegal out of bounds `usize::MAX` for now.
-    let base_attrs = Attrs::new().family(Family::Monospace).metadata(usize::MAX);
-    // Optimization: Why is Buffer used when there is no wrapping. BufferLine would
-    // probably be better.
-    let mut buffer = Buffer::new(font_system, Metrics::new(font_size, line_height));
-    buffer.set_size(None, None);
-    // buffer.set_text(font_system, text, attrs, Shaping::Advanced);
-    buffer.set_wrap(Wrap::None);
-    // Create associated metadata.
-    let text_attr_spans = attributes.iter().enumerate().map(|(attribute_index, ta)| {
-        (
-            text.get(ta.range.clone()).unwrap(),
-            base_attrs
-                .clone()
-                .metadata(attribute_index)
-                .weight(Weight(ta.weight.0)),
-        )
-    });
-    buffer.set_rich_text(text_attr_spans, &base_attrs, Shaping::Advanced, None);
-    buffer.shape_until_scroll(font_system, true);
-
-    let mut runs = Vec::new();
-    let mut height: f64 = 0.;
+    let mut shaper = TextShaper::new(text)
+        .with_default_attributes(TextAttributes::default().with_family(Family::Monospace));
+    for ta in attributes {
+        shaper.add_range_attributes(
+            ta.range.clone(),
+            TextAttributes::default()
+                .with_family(Family::Monospace)
+                .with_color(ta.color)
+                .with_weight(ta.weight),
+        );
+    }
 
-    // Detail: For now we keep the redundancy of providing weight to the run and the individual
-    // glyphs (cosmic text 0.15 introduced this).
-    let attributes: Vec<_> = attributes.iter().map(|ta| (ta.color, ta.weight)).collect();
+    let style = ParagraphStyle {
+        line_height: line_height / font_size,
+        ..Default::default()
+    };
+    let paragraph = shaper.layout_paragraph(font_system, font_size, &style);
 
     let translation = translation.into().unwrap_or(Vector3::new(0., 0., 0.));
-
-    for run in buffer.layout_runs() {
-        // Lines are positioned on line_height.
-        let translation = translation + Vector3::new(0., run.line_top as f64, 0.);
-        for run in
-            positioning::to_attributed_glyph_runs(translation, &run, line_height, &attributes)
-        {
-            runs.push(run);
-        }
-        height = height.max(run.line_top as f64 + line_height as f64);
-    }
-
-    (runs, height)
+    let runs = paragraph
+        .runs
+        .into_iter()
+        .map(|run| GlyphRun {
+            translation: run.translation + translation,
+            ..run
+        })
+        .collect();
+
+    (runs, paragraph.size.height as f64)
 }
 
 mod syntax {
@@ -1,40 +1,9 @@
 #![allow(dead_code)]
 
-use itertools::Itertools;
-
-use cosmic_text::{LayoutGlyph, LayoutRun};
+use cosmic_text::{CacheKeyFlags, LayoutGlyph, LayoutRun};
 
 use massive_geometry::{Color, Vector3};
-use massive_shapes::{GlyphKey, GlyphRun, GlyphRunMetrics, RunGlyph, TextWeight};
-
-/// Converts a cosmic_text `LayoutRun` into one or more `GlyphRun`s.
-///
-/// We split `LayoutRun`s if they contain different metadata which points to a color.
-pub fn to_attributed_glyph_runs(
-    translation: Vector3,
-    run: &LayoutRun,
-    line_height: f32,
-    attributes: &[(Color, TextWeight)],
-) -> Vec<GlyphRun> {
-    let metrics = metrics(run, line_height);
-
-    run.glyphs
-        .iter()
-        .chunk_by(|r| r.metadata)
-        .into_iter()
-        .map(|(metadata, sub_run_glyphs)| {
-            let positioned_glyphs = sub_run_glyphs.map(position_glyph);
-            let (color, weight) = attributes[metadata];
-            GlyphRun::new(
-                translation,
-                metrics,
-                color,
-                weight,
-                positioned_glyphs.collect(),
-            )
-        })
-        .collect()
-}
+use massive_shapes::{GlyphKey, GlyphRun, GlyphRunMetrics, RunGlyph, TextStyle, TextWeight};
 
 pub fn to_glyph_run(translation: Vector3, run: &LayoutRun, line_height: f32) -> GlyphRun {
     let metrics = metrics(run, line_height);
@@ -67,13 +36,19 @@ fn position_glyph(glyph: &LayoutGlyph) -> RunGlyph {
     let pos = (glyph.x.round() as i32, glyph.y.round() as i32);
 
     // Ergonomics: create a better constructor.
-    RunGlyph::new(
-        pos,
-        GlyphKey::new(
-            glyph.font_id,
-            glyph.glyph_id,
-            glyph.font_size,
-            TextWeight(glyph.font_weight.0),
-        ),
-    )
+    let key = GlyphKey::new(
+        glyph.font_id,
+        glyph.glyph_id,
+        glyph.font_size,
+        TextWeight(glyph.font_weight.0),
+    );
+    // cosmic-text flags italic glyphs of families without an italic face, the renderer slants
+    // them.
+    let key = if glyph.cache_key_flags.contains(CacheKeyFlags::FAKE_ITALIC) {
+        key.with_style(TextStyle::Italic)
+    } else {
+        key
+    };
+
+    RunGlyph::new(pos, key)
 }
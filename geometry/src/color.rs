@@ -3,6 +3,10 @@ use std::ops::{Add, Div, Mul};
 use serde_tuple::{Deserialize_tuple, Serialize_tuple};
 
 // TODO: WGPU uses f64 for colors, should we do the same?
+/// A color with linear RGB components and a straight (not premultiplied) alpha.
+///
+/// Colors in hex notation, bytes, and HSV are sRGB encoded and converted to linear when a `Color`
+/// is created from them, see [`Color::from_srgb`].
 #[derive(Copy, Clone, PartialEq, Debug, Serialize_tuple, Deserialize_tuple)]
 pub struct Color {
     pub red: f32,
@@ -14,6 +18,7 @@ pub struct Color {
 impl Color {
     pub const WHITE: Self = Self::rgb(1.0, 1.0, 1.0);
     pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
 
     pub const fn rgb(red: f32, green: f32, blue: f32) -> Self {
         Self::new(red, green, blue, 1.0)
@@ -28,6 +33,27 @@ impl Color {
         }
     }
 
+    /// A color from sRGB encoded components.
+    pub const fn from_srgb(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        Self::new(
+            srgb_to_linear(red),
+            srgb_to_linear(green),
+            srgb_to_linear(blue),
+            alpha,
+        )
+    }
+
+    /// The sRGB encoded components, `[red, green, blue, alpha]`.
+    pub fn to_srgb(self) -> [f32; 4] {
+        [
+            linear_to_srgb(self.red),
+            linear_to_srgb(self.green),
+            linear_to_srgb(self.blue),
+            self.alpha,
+        ]
+    }
+
+    /// A color from an sRGB hex value, e.g. `0xff8000`.
     pub const fn rgb_u32(rgb: u32) -> Self {
         let r = (rgb & 0xff0000) >> 16;
         let g = (rgb & 0xff00) >> 8;
@@ -35,9 +61,10 @@ impl Color {
         let r = r as f32 / 255.0;
         let g = g as f32 / 255.0;
         let b = b as f32 / 255.0;
-        Color::rgb(r, g, b)
+        Color::from_srgb(r, g, b, 1.0)
     }
 
+    /// A color from HSV, which is defined on sRGB encoded components.
     // http://stackoverflow.com/questions/359612/how-to-change-rgb-color-to-hsv
     pub const fn hsv(hue: f32, saturation: f32, value: f32) -> Color {
         let hf = (hue / 60.0).floor();
@@ -50,12 +77,12 @@ impl Color {
         let t = value * (1.0 - (1.0 - f) * saturation);
 
         match hi {
-            0 => Color::rgb(v, t, p),
-            1 => Color::rgb(q, v, p),
-            2 => Color::rgb(p, v, t),
-            3 => Color::rgb(p, q, v),
-            4 => Color::rgb(t, p, v),
-            _ => Color::rgb(v, p, q),
+            0 => Color::from_srgb(v, t, p, 1.0),
+            1 => Color::from_srgb(q, v, p, 1.0),
+            2 => Color::from_srgb(p, v, t, 1.0),
+            3 => Color::from_srgb(p, q, v, 1.0),
+            4 => Color::from_srgb(t, p, v, 1.0),
+            _ => Color::from_srgb(v, p, q, 1.0),
         }
     }
 
@@ -117,9 +144,10 @@ impl From<(u8, u8, u8)> for Color {
     }
 }
 
+/// sRGB encoded bytes, alpha is linear.
 impl From<(u8, u8, u8, u8)> for Color {
     fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
-        Self::new(
+        Self::from_srgb(
             r as f32 / 255.,
             g as f32 / 255.,
             b as f32 / 255.,
@@ -139,3 +167,132 @@ impl From<Color> for (f32, f32, f32, f32) {
         (value.red, value.green, value.blue, value.alpha)
     }
 }
+
+/// A color in the OKLab color space, in which distances match perceived differences.
+///
+/// Interpolating in OKLab avoids the dark and desaturated midpoints of interpolating linear RGB.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Oklab {
+    /// Perceived lightness, 0 is black, 1 is white.
+    pub lightness: f32,
+    /// Green (negative) to red (positive).
+    pub a: f32,
+    /// Blue (negative) to yellow (positive).
+    pub b: f32,
+    pub alpha: f32,
+}
+
+// <https://bottosson.github.io/posts/oklab/>
+
+impl From<Color> for Oklab {
+    fn from(color: Color) -> Self {
+        let Color {
+            red,
+            green,
+            blue,
+            alpha,
+        } = color;
+        let l = (0.41222146 * red + 0.53633255 * green + 0.051445995 * blue).cbrt();
+        let m = (0.2119035 * red + 0.6806995 * green + 0.10739696 * blue).cbrt();
+        let s = (0.08830246 * red + 0.28171885 * green + 0.6299787 * blue).cbrt();
+        Self {
+            lightness: 0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+            a: 1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+            b: 0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+            alpha,
+        }
+    }
+}
+
+impl From<Oklab> for Color {
+    fn from(oklab: Oklab) -> Self {
+        let Oklab {
+            lightness,
+            a,
+            b,
+            alpha,
+        } = oklab;
+        let l = (lightness + 0.39633778 * a + 0.21580376 * b).powi(3);
+        let m = (lightness - 0.105561346 * a - 0.06385417 * b).powi(3);
+        let s = (lightness - 0.08948418 * a - 1.2914855 * b).powi(3);
+        Self::new(
+            4.0767417 * l - 3.3077116 * m + 0.23096994 * s,
+            -1.268438 * l + 2.6097574 * m - 0.34131938 * s,
+            -0.0041960864 * l - 0.7034186 * m + 1.7076147 * s,
+            alpha,
+        )
+    }
+}
+
+/// Decodes an sRGB component.
+///
+/// `const`, so that colors can be defined in hex notation in constants.
+const fn srgb_to_linear(c: f32) -> f32 {
+    let c = c as f64;
+    if c <= 0.04045 {
+        return (c / 12.92) as f32;
+    }
+    // `powf` is not `const`: x^2.4 = x^2 * (x^2)^(1/5), the fifth root is computed with Newton's
+    // method.
+    let x = (c + 0.055) / 1.055;
+    let square = x * x;
+    let mut root = 1.0;
+    let mut i = 0;
+    while i < 16 {
+        let fourth = root * root * root * root;
+        root -= (fourth * root - square) / (5.0 * fourth);
+        i += 1;
+    }
+    (square * root) as f32
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn srgb_to_linear_reference(c: f32) -> f32 {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    #[test]
+    fn const_srgb_decoding_matches_powf() {
+        for byte in 0..=255u8 {
+            let c = byte as f32 / 255.0;
+            let linear = srgb_to_linear(c);
+            let expected = srgb_to_linear_reference(c);
+            assert!(
+                (linear - expected).abs() < 1e-6,
+                "{byte}: {linear} != {expected}"
+            );
+        }
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.5) - 0.21404114).abs() < 1e-6);
+    }
+
+    #[test]
+    fn srgb_components_survive_a_round_trip() {
+        for byte in 0..=255u8 {
+            let c = byte as f32 / 255.0;
+            let [red, green, blue, alpha] = Color::from_srgb(c, c, c, 0.5).to_srgb();
+            for component in [red, green, blue] {
+                assert!((component - c).abs() < 1e-5, "{byte}: {component} != {c}");
+            }
+            assert_eq!(alpha, 0.5);
+        }
+        assert_eq!(Color::rgb_u32(0xffffff), Color::WHITE);
+        assert_eq!(Color::rgb_u32(0x000000), Color::BLACK);
+    }
+}
@@ -8,6 +8,11 @@ pub enum CameraMode {
     /// Fit target size within surface, with optional blend factor.
     /// `blend: 0.0` = pixel-perfect, `1.0` = fully fitted to target_size
     Sized { target_size: Size, blend: f64 },
+    /// Orthographic projection with 1:1 pixel mapping, with optional blend factor.
+    /// `blend: 0.0` = perspective (pixel-perfect), `1.0` = fully orthographic
+    ///
+    /// The plane the camera looks at stays pixel-perfect while blending.
+    Orthographic { blend: f64 },
 }
 
 /// A pixel camera.
@@ -63,6 +68,11 @@ impl PixelCamera {
         self
     }
 
+    pub fn with_orthographic(mut self) -> Self {
+        self.mode = CameraMode::Orthographic { blend: 1.0 };
+        self
+    }
+
     /// The matrix that moves and scales the model so that the camera target is at 0,0 and
     /// the target size (if set) fits within the surface.
     pub fn model_camera_matrix(&self, surface_size: SizePx) -> Matrix4 {
@@ -76,6 +86,36 @@ impl PixelCamera {
         Matrix4::from_translation(-Vector3::new(0.0, 0.0, camera_distance))
     }
 
+    /// The distance from the eye to the point the camera looks at, in the camera's coordinate
+    /// system (before `look_at` is applied).
+    pub fn eye_distance(&self, surface_size: SizePx) -> f64 {
+        let (_, surface_height) = surface_size.into();
+        let camera_distance = 1.0 / (self.fovy / 2.0).to_radians().tan();
+        camera_distance * surface_height as f64 / 2.0 / self.target_scale(surface_size)
+    }
+
+    /// The matrix that projects NDC 3D coordinates to the final surface coordinates "2D",
+    /// depending on the camera mode.
+    pub fn projection_matrix(
+        &self,
+        z_range: (f64, f64),
+        surface_size: impl Into<SizePx>,
+    ) -> Matrix4 {
+        let surface_size = surface_size.into();
+        let perspective = self.perspective_matrix(z_range, surface_size);
+        match self.mode {
+            CameraMode::Orthographic { blend } => {
+                let (width, height) = surface_size.into();
+                let orthographic =
+                    Projection::new(width as f64 / height as f64, z_range).orthographic_matrix();
+                // At the camera distance, both project the same, so the blended projection does,
+                // too.
+                perspective * (1.0 - blend) + orthographic * blend
+            }
+            CameraMode::PixelPerfect | CameraMode::Sized { .. } => perspective,
+        }
+    }
+
     /// The matrix that projects NDC 3D coordinates to the final surface coordinates "2D".
     ///
     /// Architecture: If we internally use pixel coordinates, then go through NDC and here back in
@@ -98,9 +138,11 @@ impl PixelCamera {
     }
 
     /// Compute the scale factor, blending between pixel-perfect and target-size modes.
-    fn target_scale(&self, surface_size: SizePx) -> f64 {
+    ///
+    /// This is the number of surface pixels a model pixel at the look at point covers.
+    pub fn target_scale(&self, surface_size: SizePx) -> f64 {
         match self.mode {
-            CameraMode::PixelPerfect => 1.0,
+            CameraMode::PixelPerfect | CameraMode::Orthographic { .. } => 1.0,
             CameraMode::Sized { target_size, blend } => {
                 let (surface_width, surface_height) = surface_size.into();
                 let scale_x = surface_width as f64 / target_size.width;
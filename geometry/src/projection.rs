@@ -19,4 +19,10 @@ impl Projection {
         let (near, far) = self.depth_range;
         Matrix4::perspective_rh(fovy.to_radians(), self.aspect, near, far)
     }
+
+    /// Create an orthographic projection matrix that maps the height to the NDC range -1 to 1.
+    pub fn orthographic_matrix(&self) -> Matrix4 {
+        let (near, far) = self.depth_range;
+        Matrix4::orthographic_rh(-self.aspect, self.aspect, -1.0, 1.0, near, far)
+    }
 }
@@ -0,0 +1,473 @@
+//! A camera controller to inspect 3D layouts from any angle.
+//!
+//! The controller only computes the camera the user navigated to. To animate towards it, use it as
+//! the target of an animated `PixelCamera`.
+
+use massive_geometry::{CameraMode, PixelCamera, Quaternion, SizePx, Vector3};
+use massive_util::Progress;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta};
+use winit::keyboard::{Key, NamedKey};
+
+use crate::{Event, InputEvent, Movement};
+
+/// How the camera moves when it is rotated or moved forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraControl {
+    /// The camera rotates around and zooms towards the point it looks at.
+    #[default]
+    Orbit,
+    /// The camera rotates around the eye and moves in the direction it looks at.
+    FreeFly,
+}
+
+/// Controls a [`PixelCamera`] with the mouse and the keyboard.
+///
+/// - Left mouse button drag: Rotate (around the point looked at or the eye, depending on the
+///   control).
+/// - Middle mouse button drag: Pan.
+/// - Mouse wheel, Up / Down keys: Zoom in and out, or move forward and backward.
+/// - Left / Right keys: Rotate around the point looked at, or move sideways.
+/// - Home: Go back to the initial camera.
+/// - `o`: Toggle between the perspective and the orthographic projection, see
+///   [`CameraController::toggle_orthographic`].
+///
+/// Pressing another mouse button while dragging cancels the drag and restores the camera.
+#[derive(Debug)]
+pub struct CameraController {
+    control: CameraControl,
+    home: PixelCamera,
+    camera: PixelCamera,
+    /// The mode the orthographic projection toggles back to.
+    perspective_mode: CameraMode,
+    drag: Option<Drag>,
+    /// The rotation in radians per pixel dragged.
+    pub rotation_speed: f64,
+    /// The distance in pixels a key press or a mouse wheel line moves the camera.
+    pub step: f64,
+}
+
+#[derive(Debug)]
+struct Drag {
+    movement: Movement,
+    kind: DragKind,
+    /// The camera when the drag began.
+    origin: PixelCamera,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DragKind {
+    Rotate,
+    Pan,
+}
+
+impl CameraController {
+    /// The minimum distance in pixels the pointer needs to move to start a drag.
+    const MIN_DRAG_DISTANCE: f64 = 3.0;
+    pub const DEFAULT_ROTATION_SPEED: f64 = 0.005;
+    pub const DEFAULT_STEP: f64 = 50.0;
+    /// The rotation of a key press.
+    const KEY_ROTATION_DEGREES: f64 = 15.0;
+    /// The zoom factor of a step in orbit control.
+    const ZOOM_FACTOR: f64 = 1.1;
+
+    pub fn new(camera: PixelCamera) -> Self {
+        Self {
+            control: CameraControl::default(),
+            home: camera,
+            camera,
+            perspective_mode: match camera.mode {
+                CameraMode::Orthographic { .. } => CameraMode::PixelPerfect,
+                mode => mode,
+            },
+            drag: None,
+            rotation_speed: Self::DEFAULT_ROTATION_SPEED,
+            step: Self::DEFAULT_STEP,
+        }
+    }
+
+    pub fn with_control(mut self, control: CameraControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    pub fn control(&self) -> CameraControl {
+        self.control
+    }
+
+    pub fn set_control(&mut self, control: CameraControl) {
+        self.control = control;
+    }
+
+    pub fn camera(&self) -> PixelCamera {
+        self.camera
+    }
+
+    /// Replace the camera, for example when it was changed from somewhere else. A drag in
+    /// progress continues from the new camera.
+    pub fn set_camera(&mut self, camera: PixelCamera) {
+        self.camera = camera;
+        if let Some(drag) = &mut self.drag {
+            drag.origin = camera;
+            drag.movement.from = drag.movement.to();
+            drag.movement.delta = Default::default();
+        }
+    }
+
+    /// Set the camera the Home key goes back to.
+    pub fn set_home(&mut self, camera: PixelCamera) {
+        self.home = camera;
+    }
+
+    /// Process an input event.
+    ///
+    /// `surface_size` is the size of the surface the camera renders into.
+    ///
+    /// Returns `true` if the camera changed.
+    pub fn handle_event<E: InputEvent>(&mut self, event: &Event<E>, surface_size: SizePx) -> bool {
+        if let Some(drag) = &mut self.drag {
+            return match drag.movement.track_delta(event) {
+                Some(Progress::Proceed(_)) => {
+                    let drag = self.drag.as_ref().unwrap();
+                    self.camera = self.dragged(drag, surface_size);
+                    true
+                }
+                Some(Progress::Commit) => {
+                    self.drag = None;
+                    false
+                }
+                Some(Progress::Cancel) => {
+                    let origin = self.drag.take().unwrap().origin;
+                    let changed = origin != self.camera;
+                    self.camera = origin;
+                    changed
+                }
+                None => false,
+            };
+        }
+
+        for (button, kind) in [
+            (MouseButton::Left, DragKind::Rotate),
+            (MouseButton::Middle, DragKind::Pan),
+        ] {
+            if let Some(movement) = event.detect_movement(button, Self::MIN_DRAG_DISTANCE) {
+                let drag = Drag {
+                    movement,
+                    kind,
+                    origin: self.camera,
+                };
+                self.camera = self.dragged(&drag, surface_size);
+                self.drag = Some(drag);
+                return true;
+            }
+        }
+
+        if let Some(delta) = event.event().scroll_delta() {
+            let steps = match delta {
+                MouseScrollDelta::LineDelta(_, y) => y as f64,
+                MouseScrollDelta::PixelDelta(position) => position.y / self.step,
+            };
+            return self.forward(steps);
+        }
+
+        if let Some(key_event) = event.event().key_event() {
+            if key_event.state != ElementState::Pressed {
+                return false;
+            }
+            return match &key_event.logical_key {
+                Key::Named(NamedKey::ArrowUp) => self.forward(1.0),
+                Key::Named(NamedKey::ArrowDown) => self.forward(-1.0),
+                Key::Named(NamedKey::ArrowLeft) => self.sideways(-1.0, surface_size),
+                Key::Named(NamedKey::ArrowRight) => self.sideways(1.0, surface_size),
+                Key::Named(NamedKey::Home) => self.replace(self.home),
+                Key::Character(c) if c.as_str() == "o" => self.toggle_orthographic(),
+                _ => false,
+            };
+        }
+
+        false
+    }
+
+    /// Switch to the orthographic projection, or back to the mode the camera had before.
+    ///
+    /// Returns `true` if the camera changed.
+    pub fn toggle_orthographic(&mut self) -> bool {
+        let mut camera = self.camera;
+        camera.mode = match camera.mode {
+            CameraMode::Orthographic { .. } => self.perspective_mode,
+            mode => {
+                self.perspective_mode = mode;
+                CameraMode::Orthographic { blend: 1.0 }
+            }
+        };
+        self.replace(camera)
+    }
+
+    /// The camera of a drag at its current delta.
+    fn dragged(&self, drag: &Drag, surface_size: SizePx) -> PixelCamera {
+        let delta = drag.movement.delta;
+        match drag.kind {
+            DragKind::Rotate => {
+                // Yaw around the model's vertical axis, so that the horizon stays level, pitch
+                // around the camera's horizontal axis.
+                let yaw = Quaternion::from_rotation_y(-delta.x * self.rotation_speed);
+                let pitch = Quaternion::from_rotation_x(delta.y * self.rotation_speed);
+                let rotate = (yaw * drag.origin.look_at.rotate * pitch).normalize();
+                self.rotated(drag.origin, rotate, surface_size)
+            }
+            DragKind::Pan => {
+                // The content follows the pointer on the plane looked at.
+                let target_scale = drag.origin.target_scale(surface_size);
+                let moved = Vector3::new(delta.x, delta.y, 0.0) / target_scale;
+                let mut camera = drag.origin;
+                camera.look_at.translate -= drag.origin.look_at.transform_vector(moved);
+                camera
+            }
+        }
+    }
+
+    /// `camera` with a new rotation, around the point looked at or the eye.
+    fn rotated(
+        &self,
+        camera: PixelCamera,
+        rotate: Quaternion,
+        surface_size: SizePx,
+    ) -> PixelCamera {
+        let mut rotated = camera;
+        rotated.look_at.rotate = rotate;
+        if self.control == CameraControl::FreeFly {
+            let eye_offset = Vector3::new(0.0, 0.0, camera.eye_distance(surface_size));
+            let eye = camera.look_at.transform_point(eye_offset);
+            rotated.look_at.translate = eye - rotated.look_at.transform_vector(eye_offset);
+        }
+        rotated
+    }
+
+    /// Zoom in or move forward by `steps`.
+    fn forward(&mut self, steps: f64) -> bool {
+        if steps == 0.0 {
+            return false;
+        }
+        let mut camera = self.camera;
+        match self.control {
+            // The eye keeps its distance to the point looked at, so zooming scales the model.
+            CameraControl::Orbit => camera.look_at.scale /= Self::ZOOM_FACTOR.powf(steps),
+            CameraControl::FreeFly => {
+                let moved = Vector3::new(0.0, 0.0, -steps * self.step);
+                camera.look_at.translate += camera.look_at.transform_vector(moved);
+            }
+        }
+        self.replace(camera)
+    }
+
+    /// Rotate around the point looked at or move sideways by `steps`.
+    fn sideways(&mut self, steps: f64, surface_size: SizePx) -> bool {
+        let camera = self.camera;
+        let camera = match self.control {
+            CameraControl::Orbit => {
+                let yaw =
+                    Quaternion::from_rotation_y(-steps * Self::KEY_ROTATION_DEGREES.to_radians());
+                let rotate = (yaw * camera.look_at.rotate).normalize();
+                self.rotated(camera, rotate, surface_size)
+            }
+            CameraControl::FreeFly => {
+                let mut camera = camera;
+                let moved = Vector3::new(steps * self.step, 0.0, 0.0);
+                camera.look_at.translate += camera.look_at.transform_vector(moved);
+                camera
+            }
+        };
+        self.replace(camera)
+    }
+
+    fn replace(&mut self, camera: PixelCamera) -> bool {
+        let changed = camera != self.camera;
+        self.camera = camera;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use massive_geometry::{Point, Size};
+    use winit::event::DeviceId;
+
+    use super::*;
+    use crate::{AggregationEvent, EventManager};
+
+    const SURFACE_SIZE: SizePx = SizePx::new(800, 600);
+
+    #[derive(Debug)]
+    enum TestEvent {
+        CursorMoved(Point),
+        MouseInput(ElementState, MouseButton),
+        MouseWheel(MouseScrollDelta),
+    }
+
+    impl InputEvent for TestEvent {
+        fn to_aggregation_event(&self) -> Option<AggregationEvent> {
+            let device_id = DeviceId::dummy();
+            match *self {
+                TestEvent::CursorMoved(position) => Some(AggregationEvent::CursorMoved {
+                    device_id,
+                    position,
+                }),
+                TestEvent::MouseInput(state, button) => Some(AggregationEvent::MouseInput {
+                    device_id,
+                    state,
+                    button,
+                }),
+                TestEvent::MouseWheel(_) => None,
+            }
+        }
+
+        fn device(&self) -> Option<DeviceId> {
+            Some(DeviceId::dummy())
+        }
+
+        fn scroll_delta(&self) -> Option<MouseScrollDelta> {
+            match *self {
+                TestEvent::MouseWheel(delta) => Some(delta),
+                _ => None,
+            }
+        }
+    }
+
+    struct Harness {
+        events: EventManager<TestEvent>,
+        controller: CameraController,
+    }
+
+    impl Harness {
+        fn new(control: CameraControl) -> Self {
+            Self {
+                events: EventManager::default(),
+                controller: CameraController::new(PixelCamera::default()).with_control(control),
+            }
+        }
+
+        fn send(&mut self, event: TestEvent) -> bool {
+            match self.events.add_event(event, Instant::now()) {
+                Some(event) => self.controller.handle_event(&event, SURFACE_SIZE),
+                None => false,
+            }
+        }
+
+        /// Press `button` at `from` and move to `to` without releasing it.
+        fn drag(&mut self, button: MouseButton, from: (f64, f64), to: (f64, f64)) {
+            self.send(TestEvent::CursorMoved(from.into()));
+            self.send(TestEvent::MouseInput(ElementState::Pressed, button));
+            assert!(self.send(TestEvent::CursorMoved(to.into())));
+        }
+
+        fn release(&mut self, button: MouseButton) -> bool {
+            self.send(TestEvent::MouseInput(ElementState::Released, button))
+        }
+
+        fn camera(&self) -> PixelCamera {
+            self.controller.camera()
+        }
+    }
+
+    fn eye(camera: PixelCamera) -> Vector3 {
+        let eye_offset = Vector3::new(0.0, 0.0, camera.eye_distance(SURFACE_SIZE));
+        camera.look_at.transform_point(eye_offset)
+    }
+
+    #[test]
+    fn orbit_rotates_around_the_point_looked_at() {
+        let mut harness = Harness::new(CameraControl::Orbit);
+
+        harness.drag(MouseButton::Left, (100.0, 100.0), (200.0, 100.0));
+
+        let camera = harness.camera();
+        let expected =
+            Quaternion::from_rotation_y(-100.0 * CameraController::DEFAULT_ROTATION_SPEED);
+        assert!(camera.look_at.rotate.abs_diff_eq(expected, 1e-9));
+        assert_eq!(camera.look_at.translate, Vector3::ZERO);
+
+        // Releasing the button keeps the camera.
+        assert!(!harness.release(MouseButton::Left));
+        assert_eq!(harness.camera(), camera);
+    }
+
+    #[test]
+    fn free_fly_rotates_around_the_eye() {
+        let mut harness = Harness::new(CameraControl::FreeFly);
+        let eye_before = eye(harness.camera());
+
+        harness.drag(MouseButton::Left, (100.0, 100.0), (100.0, 160.0));
+
+        let camera = harness.camera();
+        let expected = Quaternion::from_rotation_x(60.0 * CameraController::DEFAULT_ROTATION_SPEED);
+        assert!(camera.look_at.rotate.abs_diff_eq(expected, 1e-9));
+        assert_ne!(camera.look_at.translate, Vector3::ZERO);
+        assert!(eye(camera).abs_diff_eq(eye_before, 1e-6));
+    }
+
+    #[test]
+    fn panning_moves_the_content_with_the_pointer() {
+        for control in [CameraControl::Orbit, CameraControl::FreeFly] {
+            let mut harness = Harness::new(control);
+
+            harness.drag(MouseButton::Middle, (100.0, 100.0), (200.0, 150.0));
+
+            let camera = harness.camera();
+            assert_eq!(camera.look_at.translate, Vector3::new(-100.0, -50.0, 0.0));
+            assert_eq!(camera.look_at.rotate, Quaternion::IDENTITY);
+        }
+    }
+
+    #[test]
+    fn another_button_cancels_a_drag() {
+        let mut harness = Harness::new(CameraControl::Orbit);
+
+        harness.drag(MouseButton::Left, (100.0, 100.0), (200.0, 100.0));
+
+        assert!(harness.send(TestEvent::MouseInput(
+            ElementState::Pressed,
+            MouseButton::Right
+        )));
+        assert_eq!(harness.camera(), PixelCamera::default());
+    }
+
+    #[test]
+    fn orbit_zooms_by_scaling() {
+        let mut harness = Harness::new(CameraControl::Orbit);
+
+        assert!(harness.send(TestEvent::MouseWheel(MouseScrollDelta::LineDelta(0.0, 2.0))));
+
+        let look_at = harness.camera().look_at;
+        assert!((look_at.scale - 1.0 / CameraController::ZOOM_FACTOR.powi(2)).abs() < 1e-9);
+        assert_eq!(look_at.translate, Vector3::ZERO);
+    }
+
+    #[test]
+    fn free_fly_moves_forward() {
+        let mut harness = Harness::new(CameraControl::FreeFly);
+
+        assert!(harness.send(TestEvent::MouseWheel(MouseScrollDelta::LineDelta(0.0, 2.0))));
+
+        let look_at = harness.camera().look_at;
+        assert_eq!(look_at.scale, 1.0);
+        assert_eq!(
+            look_at.translate,
+            Vector3::new(0.0, 0.0, -2.0 * CameraController::DEFAULT_STEP)
+        );
+    }
+
+    #[test]
+    fn orthographic_toggles_back_to_the_previous_mode() {
+        let sized = PixelCamera::default().with_size(Size::new(400.0, 300.0));
+        let mut controller = CameraController::new(sized);
+
+        assert!(controller.toggle_orthographic());
+        assert_eq!(
+            controller.camera().mode,
+            CameraMode::Orthographic { blend: 1.0 }
+        );
+        assert!(controller.toggle_orthographic());
+        assert_eq!(controller.camera(), sized);
+    }
+}
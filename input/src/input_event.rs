@@ -1,6 +1,8 @@
 use std::fmt;
 
-use winit::event::{self, DeviceId, ElementState, Modifiers, MouseButton};
+use winit::event::{
+    self, DeviceId, ElementState, KeyEvent, Modifiers, MouseButton, MouseScrollDelta,
+};
 
 use massive_geometry::Point;
 
@@ -12,6 +14,16 @@ pub trait InputEvent: fmt::Debug + 'static {
 
     /// The device an event is related to.
     fn device(&self) -> Option<DeviceId>;
+
+    /// The key event, if this is a keyboard input event.
+    fn key_event(&self) -> Option<&KeyEvent> {
+        None
+    }
+
+    /// The scroll delta, if this is a mouse wheel event.
+    fn scroll_delta(&self) -> Option<MouseScrollDelta> {
+        None
+    }
 }
 
 /// A distilled event representation to support state aggregation (i.e. tracking positions, button
@@ -87,4 +99,18 @@ impl InputEvent for WindowEvent {
             _ => None,
         }
     }
+
+    fn key_event(&self) -> Option<&KeyEvent> {
+        match self {
+            WindowEvent::KeyboardInput { event, .. } => Some(event),
+            _ => None,
+        }
+    }
+
+    fn scroll_delta(&self) -> Option<MouseScrollDelta> {
+        match self {
+            WindowEvent::MouseWheel { delta, .. } => Some(*delta),
+            _ => None,
+        }
+    }
 }
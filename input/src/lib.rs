@@ -1,4 +1,5 @@
 //! Most of the code here was taken from the BS2 project.
+mod camera_controller;
 mod event;
 mod event_aggregator;
 mod event_history;
@@ -9,6 +10,7 @@ mod mouse_gesture;
 mod sensor;
 mod tracker;
 
+pub use camera_controller::*;
 pub use event::*;
 pub use event_aggregator::*;
 pub use event_manager::*;
@@ -0,0 +1,42 @@
+// The blurred backdrop of a visual.
+
+struct Immediates {
+    view_model: mat4x4<f32>,
+    clip_rect_x: vec2<f32>, // [min_x, max_x]
+    clip_rect_y: vec2<f32>, // [min_y, max_y]
+    alpha: f32,
+}
+
+var<immediate> im: Immediates;
+
+@group(0) @binding(0)
+var t_backdrop: texture_2d<f32>;
+@group(0) @binding(1)
+var s_backdrop: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) model_pos: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = im.view_model * vec4<f32>(position, 1.0);
+    out.model_pos = position.xy;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Clip fragments outside the clip rectangle (exclusive bounds)
+    if (in.model_pos.x < im.clip_rect_x.x || in.model_pos.x >= im.clip_rect_x.y ||
+        in.model_pos.y < im.clip_rect_y.x || in.model_pos.y >= im.clip_rect_y.y) {
+        discard;
+    }
+
+    // The backdrop is sampled in screen space, the blurred texture has the size of the target.
+    let uv = in.clip_position.xy / vec2<f32>(textureDimensions(t_backdrop));
+    // Premultiplied alpha.
+    return textureSampleLevel(t_backdrop, s_backdrop, uv, 0.0) * im.alpha;
+}
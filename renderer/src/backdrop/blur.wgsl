@@ -0,0 +1,49 @@
+// Full screen passes: a separable gaussian blur and a blit.
+
+struct Immediates {
+    // The step between two taps in texels: (1, 0) horizontal, (0, 1) vertical.
+    direction: vec2<f32>,
+    sigma: f32,
+}
+
+var<immediate> im: Immediates;
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// One triangle that covers the whole target.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_blur(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = im.direction / vec2<f32>(textureDimensions(t_source));
+    let radius = i32(ceil(3.0 * im.sigma));
+    var sum = vec4<f32>(0.0);
+    var weights = 0.0;
+    for (var i = -radius; i <= radius; i++) {
+        let x = f32(i);
+        let weight = exp(-(x * x) / (2.0 * im.sigma * im.sigma));
+        sum += weight * textureSampleLevel(t_source, s_source, in.uv + texel * x, 0.0);
+        weights += weight;
+    }
+    return sum / weights;
+}
+
+@fragment
+fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSampleLevel(t_source, s_source, in.uv, 0.0);
+}
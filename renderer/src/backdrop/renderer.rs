@@ -0,0 +1,404 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+
+use massive_geometry::{Rect, SizePx};
+use massive_shapes::Shape;
+
+use crate::{
+    bind_group_entries,
+    pods::{self, AsBytes, VertexLayout},
+    tools::{
+        BindGroupLayoutBuilder, PipelineParams, PipelineVariant, create_full_screen_pipeline,
+        texture_sampler,
+    },
+};
+
+/// Limits the number of taps of the blur kernel (`6 * sigma + 1`).
+const MAX_BLUR_SIGMA: f32 = 32.0;
+
+/// Renders the blurred backdrops of visuals.
+///
+/// When visuals with a backdrop blur are visible, the scene is rendered into an intermediate
+/// texture. Before such a visual is rendered, the intermediate texture is blurred and the blurred
+/// result is drawn below the visual's shapes. At the end, the intermediate texture is copied to the
+/// target.
+#[derive(Debug)]
+pub struct BackdropRenderer {
+    format: wgpu::TextureFormat,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    blur_pipeline: wgpu::RenderPipeline,
+    blit_pipeline: wgpu::RenderPipeline,
+    backdrop_pipeline: wgpu::RenderPipeline,
+    backdrop_decal_pipeline: wgpu::RenderPipeline,
+    /// Created on first use and recreated when the target size changes.
+    textures: Option<BackdropTextures>,
+}
+
+/// The backdrop of one visual.
+#[derive(Debug)]
+pub struct Backdrop {
+    /// The standard deviation of the blur in pixels.
+    pub sigma: f32,
+    /// Two triangles covering the bounds of the visual's shapes.
+    pub vertex_buffer: wgpu::Buffer,
+}
+
+impl Backdrop {
+    const VERTICES: u32 = 6;
+
+    pub fn new(device: &wgpu::Device, blur_radius: f32, shapes: &[Shape]) -> Option<Self> {
+        let bounds = shapes_bounds(shapes)?;
+        let [left, top, right, bottom] = bounds.to_scalars().map(|v| v as f32);
+        // Counter-clockwise on screen, like the quads of the other renderers.
+        let vertices: [pods::Vertex; Self::VERTICES as usize] = [
+            (left, top, 0.0).into(),
+            (left, bottom, 0.0).into(),
+            (right, bottom, 0.0).into(),
+            (left, top, 0.0).into(),
+            (right, bottom, 0.0).into(),
+            (right, top, 0.0).into(),
+        ];
+
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Backdrop Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Some(Self {
+            sigma: blur_radius.clamp(0.5, MAX_BLUR_SIGMA),
+            vertex_buffer,
+        })
+    }
+}
+
+/// The intermediate textures, all of the size of the target.
+#[derive(Debug)]
+struct BackdropTextures {
+    size: SizePx,
+    scene_view: wgpu::TextureView,
+    scene_bind_group: wgpu::BindGroup,
+    horizontal_view: wgpu::TextureView,
+    horizontal_bind_group: wgpu::BindGroup,
+    blurred_view: wgpu::TextureView,
+    blurred_bind_group: wgpu::BindGroup,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+struct BlurImmediates {
+    direction: [f32; 2],
+    sigma: f32,
+    _padding: f32,
+}
+
+impl BackdropRenderer {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat) -> Self {
+        let bind_group_layout = BindGroupLayoutBuilder::fragment_stage()
+            .texture()
+            .sampler()
+            .build("Backdrop Bind Group Layout", device);
+
+        let blur_shader = device.create_shader_module(wgpu::include_wgsl!("blur.wgsl"));
+        let blur_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blur Pipeline Layout"),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: BlurImmediates::size(),
+        });
+
+        let blur_pipeline = create_full_screen_pipeline(
+            "Blur Pipeline",
+            device,
+            &blur_shader,
+            "fs_blur",
+            &blur_layout,
+            target_format,
+            None,
+            None,
+        );
+        // The intermediate texture contains premultiplied colors.
+        let blit_pipeline = create_full_screen_pipeline(
+            "Backdrop Blit Pipeline",
+            device,
+            &blur_shader,
+            "fs_blit",
+            &blur_layout,
+            target_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            None,
+        );
+
+        let backdrop_params = PipelineParams {
+            shader: device.create_shader_module(wgpu::include_wgsl!("backdrop.wgsl")),
+            pipeline_layout: device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Backdrop Pipeline Layout"),
+                bind_group_layouts: &[Some(&bind_group_layout)],
+                immediate_size: pods::Immediates::size(),
+            }),
+            targets: [Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            vertex_layout: [pods::Vertex::layout()],
+            fragment_constants: Vec::new(),
+        };
+
+        let backdrop_pipeline = backdrop_params.create_pipeline(
+            "Backdrop Pipeline",
+            device,
+            "fs_main",
+            PipelineVariant::Standard,
+        );
+        let backdrop_decal_pipeline = backdrop_params.create_pipeline(
+            "Backdrop Pipeline",
+            device,
+            "fs_main",
+            PipelineVariant::Decal,
+        );
+
+        Self {
+            format: target_format,
+            bind_group_layout,
+            sampler: texture_sampler::linear_clamping(device),
+            blur_pipeline,
+            blit_pipeline,
+            backdrop_pipeline,
+            backdrop_decal_pipeline,
+            textures: None,
+        }
+    }
+
+    /// Create the intermediate textures for the target size.
+    pub fn prepare_textures(&mut self, device: &wgpu::Device, size: SizePx) {
+        if self.textures.as_ref().is_none_or(|t| t.size != size) {
+            self.textures = Some(BackdropTextures::new(
+                device,
+                &self.bind_group_layout,
+                &self.sampler,
+                self.format,
+                size,
+            ));
+        }
+    }
+
+    /// The texture the scene is rendered into.
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.textures().scene_view
+    }
+
+    /// Blur the scene texture into the blurred texture.
+    pub fn blur(&self, encoder: &mut wgpu::CommandEncoder, sigma: f32) {
+        let textures = self.textures();
+        let passes = [
+            (
+                [1.0, 0.0],
+                &textures.scene_bind_group,
+                &textures.horizontal_view,
+            ),
+            (
+                [0.0, 1.0],
+                &textures.horizontal_bind_group,
+                &textures.blurred_view,
+            ),
+        ];
+
+        for (direction, source, target) in passes {
+            let mut pass = begin_full_screen_pass(
+                encoder,
+                "Blur Pass",
+                target,
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            );
+            pass.set_pipeline(&self.blur_pipeline);
+            pass.set_bind_group(0, source, &[]);
+            let immediates = BlurImmediates {
+                direction,
+                sigma,
+                _padding: 0.0,
+            };
+            pass.set_immediates(0, immediates.as_bytes());
+            pass.draw(0..3, 0..1);
+        }
+    }
+
+    /// The pipeline that draws backdrops. Immediates can only be set after a pipeline is.
+    pub fn backdrop_pipeline(&self, variant: PipelineVariant) -> &wgpu::RenderPipeline {
+        match variant {
+            PipelineVariant::Standard => &self.backdrop_pipeline,
+            PipelineVariant::Decal => &self.backdrop_decal_pipeline,
+        }
+    }
+
+    /// Draw the blurred texture into the bounds of the backdrop.
+    ///
+    /// The [`Self::backdrop_pipeline`] and the immediates of the visual must be set.
+    pub fn render_backdrop(&self, pass: &mut wgpu::RenderPass, backdrop: &Backdrop) {
+        pass.set_bind_group(0, &self.textures().blurred_bind_group, &[]);
+        pass.set_vertex_buffer(0, backdrop.vertex_buffer.slice(..));
+        pass.draw(0..Backdrop::VERTICES, 0..1);
+    }
+
+    /// Copy the scene texture to the target.
+    pub fn blit(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut pass = begin_full_screen_pass(encoder, "Backdrop Blit Pass", target, load);
+        pass.set_pipeline(&self.blit_pipeline);
+        pass.set_bind_group(0, &self.textures().scene_bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    fn textures(&self) -> &BackdropTextures {
+        self.textures
+            .as_ref()
+            .expect("Backdrop textures are not prepared")
+    }
+}
+
+impl BackdropTextures {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        format: wgpu::TextureFormat,
+        size: SizePx,
+    ) -> Self {
+        let create = |label| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: size.width,
+                    height: size.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: bind_group_entries!(0 => &view, 1 => sampler),
+            });
+            (view, bind_group)
+        };
+
+        let (scene_view, scene_bind_group) = create("Backdrop Scene");
+        let (horizontal_view, horizontal_bind_group) = create("Backdrop Horizontal Blur");
+        let (blurred_view, blurred_bind_group) = create("Backdrop Blurred");
+
+        Self {
+            size,
+            scene_view,
+            scene_bind_group,
+            horizontal_view,
+            horizontal_bind_group,
+            blurred_view,
+            blurred_bind_group,
+        }
+    }
+}
+
+/// The union of the bounds of all shapes that have a rect. Text and shadows are not included.
+fn shapes_bounds(shapes: &[Shape]) -> Option<Rect> {
+    shapes
+        .iter()
+        .filter_map(|shape| match shape {
+            Shape::Rect(r) => Some(r.rect),
+            Shape::RoundRect(r) => Some(r.rect),
+            Shape::Circle(c) => Some(c.rect),
+            Shape::Ellipse(e) => Some(e.rect),
+            Shape::BeveledRect(r) => Some(r.rect),
+            Shape::StrokeRect(r) => Some(r.rect),
+            Shape::Image(i) => Some(i.rect),
+            Shape::Path(p) => p.bounds(),
+            Shape::BoxShadow(..) | Shape::GlyphRun(..) | Shape::Custom(..) => None,
+        })
+        .reduce(|a, b| a.joined(b))
+}
+
+fn begin_full_screen_pass<'encoder>(
+    encoder: &'encoder mut wgpu::CommandEncoder,
+    label: &str,
+    target: &wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+) -> wgpu::RenderPass<'encoder> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+            depth_slice: None,
+        })],
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use massive_geometry::Color;
+    use massive_scene::{At, ChangeCollector, Object, Scene, ToLocation, Transform};
+    use massive_shapes::Rect;
+
+    use super::*;
+    use crate::{HeadlessRenderer, render_device::test_device};
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn backdrop_blur_blurs_only_inside_the_visual() {
+        let size = SizePx::new(64, 64);
+        let mut headless = HeadlessRenderer::new(test_device(), size, |builder| {
+            builder.with_background_color(Color::BLACK).with_shapes()
+        });
+
+        // The camera looks at the origin: A white left half and a transparent visual with a
+        // backdrop blur over the rows 16..48.
+        let scene = Scene::new(Arc::new(ChangeCollector::default()));
+        let location = Transform::IDENTITY
+            .enter(&scene)
+            .to_location()
+            .enter(&scene);
+        let _background = vec![Shape::Rect(Rect::new((-32., -32., 0., 32.), Color::WHITE))]
+            .at(&location)
+            .enter(&scene);
+        let _glass = vec![Shape::Rect(Rect::new(
+            (-32., -16., 32., 16.),
+            Color::TRANSPARENT,
+        ))]
+        .at(&location)
+        .with_backdrop_blur(4.0)
+        .enter(&scene);
+
+        let image = headless.render(scene.take_changes()).unwrap();
+        let red = |x, y| image.pixel(x, y)[0];
+
+        // Outside the visual, the edge stays sharp.
+        assert_eq!((red(30, 8), red(34, 8)), (255, 0));
+        // Inside, it is blurred.
+        assert!(
+            red(30, 32) < 255 && red(34, 32) > 0,
+            "{} {}",
+            red(30, 32),
+            red(34, 32)
+        );
+        assert!(red(30, 32) > red(34, 32));
+        // Far from the edge, the blur doesn't change anything.
+        assert_eq!((red(4, 32), red(60, 32)), (255, 0));
+    }
+}
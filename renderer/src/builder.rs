@@ -2,6 +2,8 @@ use massive_geometry::{Color, SizePx};
 
 use crate::{
     FontManager, RenderDevice, Renderer, RendererConfig,
+    images::ImageRenderer,
+    path_renderer::PathRenderer,
     shape_renderer::{self, ShapeRenderer},
     text_layer::TextLayerRenderer,
 };
@@ -9,7 +11,8 @@ use crate::{
 #[derive(Debug)]
 pub struct RendererBuilder {
     pub device: RenderDevice,
-    pub surface: wgpu::Surface<'static>,
+    /// The window surface to render into. `None` renders offscreen.
+    pub surface: Option<wgpu::Surface<'static>>,
     pub initial_size: SizePx,
     pub config: RendererConfig,
 }
@@ -24,7 +27,19 @@ impl RendererBuilder {
         Self {
             device,
             initial_size,
-            surface,
+            surface: Some(surface),
+            config: RendererConfig::new(surface_format),
+        }
+    }
+
+    /// A builder for a renderer that renders into a texture, for example with a device from
+    /// [`RenderDevice::headless`].
+    pub fn offscreen(device: RenderDevice, size: SizePx) -> Self {
+        let surface_format = device.surface_format;
+        Self {
+            device,
+            initial_size: size,
+            surface: None,
             config: RendererConfig::new(surface_format),
         }
     }
@@ -42,13 +57,30 @@ impl RendererBuilder {
             ),
             1,
         );
+        self.config.add_batch_producer(
+            PathRenderer::new(&self.device.device, self.device.surface_format),
+            1,
+        );
+        self.config.add_batch_producer(
+            ImageRenderer::new(&self.device.device, self.device.surface_format),
+            ImageRenderer::PIPELINES,
+        );
+        self
+    }
+
+    /// Sets the texture size in bytes each glyph atlas is compacted to when it grows beyond it.
+    ///
+    /// Must be called before [`Self::with_text`].
+    pub fn with_glyph_atlas_budget(mut self, budget: usize) -> Self {
+        self.config.glyph_atlas_budget = budget;
         self
     }
 
     pub fn with_text(mut self, fonts: FontManager) -> Self {
         self.config.add_batch_producer(
-            TextLayerRenderer::new(&self.device.device, fonts, self.device.surface_format),
-            2,
+            TextLayerRenderer::new(&self.device.device, fonts, self.device.surface_format)
+                .with_atlas_budget(self.config.glyph_atlas_budget),
+            TextLayerRenderer::PIPELINES,
         );
         self
     }
@@ -59,6 +91,9 @@ impl RendererBuilder {
     }
 
     pub fn build(self) -> Renderer {
-        Renderer::new(self.device, self.surface, self.initial_size, self.config)
+        match self.surface {
+            Some(surface) => Renderer::new(self.device, surface, self.initial_size, self.config),
+            None => Renderer::new_offscreen(self.device, self.initial_size, self.config),
+        }
     }
 }
@@ -9,8 +9,12 @@ use massive_shapes::Shape;
 
 use crate::{
     FontManager,
+    glyph::GlyphAtlas,
+    images::ImageRenderer,
+    path_renderer::PathRenderer,
     renderer::{PreparationContext, RenderBatch},
     shape_renderer::{self, ShapeRenderer},
+    stats::FrameStats,
     text_layer::TextLayerRenderer,
     tools::PipelineVariant,
 };
@@ -23,6 +27,8 @@ pub struct RendererConfig {
     pub surface_format: wgpu::TextureFormat,
     pub background_color: Option<Color>,
     pub measure: bool,
+    /// The texture size in bytes each glyph atlas of the text renderer is compacted to.
+    pub glyph_atlas_budget: usize,
     pub batch_producers: Vec<BatchProducerInstance>,
 }
 
@@ -33,6 +39,7 @@ impl RendererConfig {
             background_color: Some(DEFAULT_BACKGROUND_COLOR),
             batch_producers: Vec::new(),
             measure: false,
+            glyph_atlas_budget: GlyphAtlas::DEFAULT_BUDGET,
         }
     }
 
@@ -46,7 +53,16 @@ impl RendererConfig {
             ShapeRenderer::new::<shape_renderer::Vertex>(device, surface_format),
             1,
         );
-        config.add_batch_producer(TextLayerRenderer::new(device, fonts, surface_format), 2);
+        config.add_batch_producer(PathRenderer::new(device, surface_format), 1);
+        config.add_batch_producer(
+            ImageRenderer::new(device, surface_format),
+            ImageRenderer::PIPELINES,
+        );
+        config.add_batch_producer(
+            TextLayerRenderer::new(device, fonts, surface_format)
+                .with_atlas_budget(config.glyph_atlas_budget),
+            TextLayerRenderer::PIPELINES,
+        );
         config
     }
 
@@ -106,6 +122,14 @@ pub trait BatchProducer: Send {
         variant: PipelineVariant,
     ) -> Vec<wgpu::RenderPipeline>;
 
+    /// Prepare for a new frame, called once per frame before any batches are produced.
+    ///
+    /// Returns `true` if the batches produced before must be produced again, for example because
+    /// a texture they refer to was replaced.
+    fn begin_frame(&mut self, _context: &PreparationContext) -> bool {
+        false
+    }
+
     /// Produce batches for the pipelines.
     fn produce_batches(
         &mut self,
@@ -113,6 +137,10 @@ pub trait BatchProducer: Send {
         shapes: &[Shape],
         batches: &mut [Option<RenderBatch>],
     ) -> Result<()>;
+
+    /// Add producer specific statistics to the current frame, called once per frame after all
+    /// batches were produced.
+    fn collect_stats(&mut self, _stats: &mut FrameStats) {}
 }
 
 impl BatchProducer for TextLayerRenderer {
@@ -121,13 +149,20 @@ impl BatchProducer for TextLayerRenderer {
         device: &wgpu::Device,
         variant: PipelineVariant,
     ) -> Vec<wgpu::RenderPipeline> {
+        // Backgrounds are drawn behind, and the other decorations over the glyphs.
         [
+            self.create_decoration_pipeline(device, variant),
             self.create_sdf_pipeline(device, variant),
             self.create_color_pipeline(device, variant),
+            self.create_decoration_pipeline(device, variant),
         ]
         .into()
     }
 
+    fn begin_frame(&mut self, context: &PreparationContext) -> bool {
+        self.maintain_atlases(context)
+    }
+
     /// We should require only &self here, everything that has cache semantics, should not require
     /// &mut self.
     fn produce_batches(
@@ -136,7 +171,7 @@ impl BatchProducer for TextLayerRenderer {
         shapes: &[Shape],
         batches: &mut [Option<RenderBatch>],
     ) -> Result<()> {
-        debug_assert_eq!(batches.len(), 2);
+        debug_assert_eq!(batches.len(), Self::PIPELINES);
 
         let runs = shapes.iter().filter_map(|shape| {
             if let Shape::GlyphRun(run) = shape {
@@ -146,11 +181,15 @@ impl BatchProducer for TextLayerRenderer {
             }
         });
 
-        let mut produced_batches = self.runs_to_batches(context, runs)?;
-        batches[0] = produced_batches[0].take();
-        batches[1] = produced_batches[1].take();
+        for (receiver, batch) in batches.iter_mut().zip(self.runs_to_batches(context, runs)?) {
+            *receiver = batch;
+        }
         Ok(())
     }
+
+    fn collect_stats(&mut self, stats: &mut FrameStats) {
+        stats.atlases.extend(self.take_atlas_stats());
+    }
 }
 
 impl BatchProducer for ShapeRenderer {
@@ -169,7 +208,61 @@ impl BatchProducer for ShapeRenderer {
         batch_receiver: &mut [Option<RenderBatch>],
     ) -> Result<()> {
         debug_assert_eq!(batch_receiver.len(), 1);
-        batch_receiver[0] = self.batch_from_shapes(context.device, shapes);
+        batch_receiver[0] = self.batch_from_shapes(context, shapes);
         Ok(())
     }
 }
+
+impl BatchProducer for PathRenderer {
+    fn create_pipelines(
+        &self,
+        device: &wgpu::Device,
+        variant: PipelineVariant,
+    ) -> Vec<wgpu::RenderPipeline> {
+        [self.create_pipeline(device, variant)].into()
+    }
+
+    fn produce_batches(
+        &mut self,
+        context: &PreparationContext,
+        shapes: &[Shape],
+        batch_receiver: &mut [Option<RenderBatch>],
+    ) -> Result<()> {
+        debug_assert_eq!(batch_receiver.len(), 1);
+        batch_receiver[0] = self.batch_from_shapes(context.device, shapes)?;
+        Ok(())
+    }
+}
+
+impl BatchProducer for ImageRenderer {
+    fn create_pipelines(
+        &self,
+        device: &wgpu::Device,
+        variant: PipelineVariant,
+    ) -> Vec<wgpu::RenderPipeline> {
+        // One pipeline for each sampling mode, they differ only in their bind groups.
+        (0..Self::PIPELINES)
+            .map(|_| self.create_pipeline(device, variant))
+            .collect()
+    }
+
+    fn produce_batches(
+        &mut self,
+        context: &PreparationContext,
+        shapes: &[Shape],
+        batch_receiver: &mut [Option<RenderBatch>],
+    ) -> Result<()> {
+        debug_assert_eq!(batch_receiver.len(), Self::PIPELINES);
+        for (receiver, batch) in batch_receiver
+            .iter_mut()
+            .zip(self.batches_from_shapes(context, shapes)?)
+        {
+            *receiver = batch;
+        }
+        Ok(())
+    }
+
+    fn collect_stats(&mut self, stats: &mut FrameStats) {
+        stats.atlases.push(self.take_atlas_stats());
+    }
+}
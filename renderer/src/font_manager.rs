@@ -1,14 +1,17 @@
-use std::sync::Arc;
+use std::{collections::HashSet, fs, path::Path, sync::Arc};
 
+use anyhow::{Context, Result, bail};
 use cosmic_text::{
-    Fallback, Font, FontSystem,
-    fontdb::{self, Source},
+    Attrs, Fallback, Family, Font, FontSystem,
+    fallback::FontFallbackIter,
+    fontdb::{self, Language, Source},
 };
 use derive_more::Deref;
 use parking_lot::Mutex;
 
 pub use cosmic_text::Weight as FontWeight;
 pub use fontdb::ID as FontId;
+pub use unicode_script::Script;
 
 #[derive(Debug, Clone, Deref)]
 pub struct FontManager(Arc<Mutex<FontSystem>>);
@@ -61,6 +64,118 @@ impl FontManager {
             .to_vec()
     }
 
+    /// Loads the font file at `path` and returns the ids of its faces.
+    pub fn load_font_file(&self, path: impl AsRef<Path>) -> Result<Vec<FontId>> {
+        let path = path.as_ref();
+        let data = fs::read(path)
+            .with_context(|| format!("Failed to read font file: {}", path.display()))?;
+        let ids = self.load_font(data);
+        if ids.is_empty() {
+            bail!("No font faces found in: {}", path.display());
+        }
+        Ok(ids)
+    }
+
+    /// Loads all font files in `dir` and its subdirectories and returns the ids of their faces.
+    ///
+    /// Files that can't be read or parsed are skipped.
+    pub fn load_fonts_dir(&self, dir: impl AsRef<Path>) -> Result<Vec<FontId>> {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            bail!("Font directory does not exist: {}", dir.display());
+        }
+
+        let mut font_system = self.lock();
+        let db = font_system.db_mut();
+        let existing: HashSet<FontId> = db.faces().map(|face| face.id).collect();
+        db.load_fonts_dir(dir);
+        Ok(db
+            .faces()
+            .map(|face| face.id)
+            .filter(|id| !existing.contains(id))
+            .collect())
+    }
+
+    /// Makes the faces available under the additional family name `alias`, so that
+    /// `Family::Name(alias)` resolves to them.
+    ///
+    /// The faces and their ids stay as they are. Each face is registered a second time under the
+    /// alias only, and the returned ids are the ids of these alias faces.
+    pub fn add_family_alias(&self, faces: &[FontId], alias: &str) -> Vec<FontId> {
+        let mut font_system = self.lock();
+        let db = font_system.db_mut();
+        faces
+            .iter()
+            .filter_map(|id| {
+                let mut face = db.face(*id)?.clone();
+                face.families = vec![(alias.to_string(), Language::English_UnitedStates)];
+                Some(db.push_face_info(face))
+            })
+            .collect()
+    }
+
+    /// Resolves the faces the characters of `text` in `script` are rendered with when `family`
+    /// is requested.
+    ///
+    /// This follows the fallback rules of shaping, but resolves characters individually: Each one
+    /// resolves to the first face in the fallback chain that has a glyph for it.
+    pub fn resolve_fonts(&self, text: &str, script: Script, family: Family<'_>) -> FontResolution {
+        let mut font_system = self.lock();
+        let attrs = Attrs::new().family(family);
+        let font_matches = font_system.get_font_matches(&attrs);
+        let families = [&family];
+        let scripts = [script];
+
+        let mut chars: Vec<(char, Option<FontId>)> = text
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .map(|c| (c, None))
+            .collect();
+        let mut chain = Vec::new();
+
+        let mut fonts = FontFallbackIter::new(
+            &mut font_system,
+            &font_matches,
+            &families,
+            &scripts,
+            text,
+            attrs.weight,
+        );
+        while chars.iter().any(|(_, id)| id.is_none()) {
+            let Some(font) = fonts.next() else {
+                break;
+            };
+            if chain.contains(&font.id()) {
+                continue;
+            }
+            let charmap = font.as_swash().charmap();
+            for (c, id) in chars.iter_mut().filter(|(_, id)| id.is_none()) {
+                if charmap.map(*c) != 0 {
+                    *id = Some(font.id());
+                }
+            }
+            chain.push(font.id());
+        }
+
+        let db = font_system.db();
+        let fallback_chain = chain
+            .into_iter()
+            .map(|id| ResolvedFace {
+                id,
+                family: db
+                    .face(id)
+                    .and_then(|face| face.families.first())
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        FontResolution {
+            fallback_chain,
+            chars,
+        }
+    }
+
     // Feature: Encapsulate font and create platform independent metrics.
     pub fn get_font(&self, id: FontId, weight: FontWeight) -> Option<Arc<Font>> {
         self.lock().get_font(id, weight)
@@ -79,6 +194,38 @@ impl FontManager {
     }
 }
 
+/// How the characters of a text resolve to font faces, see [`FontManager::resolve_fonts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontResolution {
+    /// The faces that were tried in fallback order, up to the one that completed the text.
+    pub fallback_chain: Vec<ResolvedFace>,
+    /// The face each visible character resolved to, `None` if no face has a glyph for it.
+    pub chars: Vec<(char, Option<FontId>)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedFace {
+    pub id: FontId,
+    /// The first family name of the face.
+    pub family: String,
+}
+
+impl FontResolution {
+    /// The face the first character resolved to.
+    pub fn face(&self) -> Option<&ResolvedFace> {
+        let id = self.chars.iter().find_map(|(_, id)| *id)?;
+        self.fallback_chain.iter().find(|face| face.id == id)
+    }
+
+    /// The characters no face has a glyph for.
+    pub fn missing(&self) -> impl Iterator<Item = char> + '_ {
+        self.chars
+            .iter()
+            .filter(|(_, id)| id.is_none())
+            .map(|(c, _)| *c)
+    }
+}
+
 struct NoFallback;
 impl Fallback for NoFallback {
     fn common_fallback(&self) -> &[&'static str] {
@@ -93,3 +240,66 @@ impl Fallback for NoFallback {
         &[]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fonts_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/shared/src/fonts")
+    }
+
+    fn montserrat() -> (FontManager, FontId) {
+        let fonts = FontManager::bare("en-US");
+        let ids = fonts
+            .load_font_file(fonts_dir().join("Montserrat/Montserrat-Regular.ttf"))
+            .unwrap();
+        (fonts, ids[0])
+    }
+
+    #[test]
+    fn resolve_fonts_reports_the_face_and_missing_characters() {
+        let (fonts, id) = montserrat();
+
+        let resolution =
+            fonts.resolve_fonts("Ab \u{4e2d}", Script::Latin, Family::Name("Montserrat"));
+
+        assert_eq!(resolution.face().map(|face| face.id), Some(id));
+        assert_eq!(resolution.fallback_chain[0].family, "Montserrat");
+        // Whitespace is not resolved.
+        assert_eq!(
+            resolution.chars,
+            [('A', Some(id)), ('b', Some(id)), ('\u{4e2d}', None)]
+        );
+        assert_eq!(resolution.missing().collect::<Vec<_>>(), ['\u{4e2d}']);
+    }
+
+    #[test]
+    fn load_fonts_dir_returns_the_new_faces() {
+        let (fonts, montserrat) = montserrat();
+
+        let ids = fonts
+            .load_fonts_dir(fonts_dir().join("JetBrainsMono-2.304/fonts/variable"))
+            .unwrap();
+
+        assert!(!ids.is_empty());
+        assert!(!ids.contains(&montserrat));
+        assert!(fonts.load_fonts_dir(fonts_dir().join("missing")).is_err());
+    }
+
+    #[test]
+    fn family_aliases_keep_the_original_faces() {
+        let (fonts, id) = montserrat();
+
+        let aliases = fonts.add_family_alias(&[id], "Body");
+
+        assert_eq!(aliases.len(), 1);
+        assert_ne!(aliases[0], id);
+        let resolution = fonts.resolve_fonts("A", Script::Latin, Family::Name("Body"));
+        assert_eq!(resolution.face().map(|face| face.id), Some(aliases[0]));
+        let resolution = fonts.resolve_fonts("A", Script::Latin, Family::Name("Montserrat"));
+        assert_eq!(resolution.face().map(|face| face.id), Some(id));
+    }
+}
@@ -1,5 +1,10 @@
 //! A  wgpu glyph atlas for u8 textures. Inspired by glyphon's TextAtlas.
-use std::{collections::HashMap, fmt};
+//!
+//! The atlas grows until it exceeds its byte budget or gets fragmented. Then it is compacted: The
+//! most recently used glyphs are re-packed into a new texture and the others are evicted. Batches
+//! that were created before refer to the previous texture and must be produced again, see
+//! [`GlyphAtlas::maintain`].
+use std::{cmp::Reverse, collections::HashMap, fmt, mem};
 
 use anyhow::{Result, bail};
 use cosmic_text::{Placement, SwashContent, SwashImage};
@@ -10,18 +15,45 @@ use euclid::size2;
 use massive_geometry::SizePx;
 use tracing::instrument;
 use wgpu::{
-    Device, Extent3d, Origin3d, Queue, TexelCopyTextureInfo, Texture, TextureAspect,
-    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+    CommandEncoder, Device, Extent3d, Origin3d, Queue, TexelCopyTextureInfo, Texture,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
     TextureViewDescriptor,
 };
 
-use crate::glyph::glyph_rasterization::RasterizedGlyphKey;
+use crate::{glyph::glyph_rasterization::RasterizedGlyphKey, stats::AtlasStats};
 
 pub struct GlyphAtlas {
     texture: AtlasTexture,
     allocator: BucketedAtlasAllocator,
     /// Storage of the available and (padded) Images.
-    images: HashMap<RasterizedGlyphKey, (Allocation, Placement)>,
+    images: HashMap<RasterizedGlyphKey, Entry>,
+    /// The texture size in bytes the atlas is compacted to.
+    budget: usize,
+    /// Counts up with every [`Self::maintain`], entries remember when they were used last.
+    tick: u64,
+    /// Set when the atlas grew beyond its budget or was fragmented.
+    compaction_pending: bool,
+    /// The tick of the last compaction.
+    last_compaction: Option<u64>,
+    /// Grow events since the last [`Self::take_stats`].
+    grow_events: usize,
+    /// Glyphs evicted since the last [`Self::take_stats`].
+    evictions: usize,
+    /// Compactions since the last [`Self::take_stats`].
+    compactions: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    allocation: Allocation,
+    placement: Placement,
+    last_used: u64,
+}
+
+impl Entry {
+    fn texels(&self) -> usize {
+        self.placement.width as usize * self.placement.height as usize
+    }
 }
 
 impl fmt::Debug for GlyphAtlas {
@@ -29,6 +61,13 @@ impl fmt::Debug for GlyphAtlas {
         f.debug_struct("GlyphAtlas")
             .field("texture", &self.texture)
             .field("images", &self.images)
+            .field("budget", &self.budget)
+            .field("tick", &self.tick)
+            .field("compaction_pending", &self.compaction_pending)
+            .field("last_compaction", &self.last_compaction)
+            .field("grow_events", &self.grow_events)
+            .field("evictions", &self.evictions)
+            .field("compactions", &self.compactions)
             .finish()
     }
 }
@@ -37,10 +76,25 @@ impl GlyphAtlas {
     // TODO: Measure what we usually need and make this a arg to new.
     const INITIAL_SIZE: u32 = 128;
     const GROWTH_FACTOR: u32 = 2;
+    /// The default texture size in bytes, reached at 4096x4096 for sdf and 2048x2048 for color
+    /// glyphs.
+    pub const DEFAULT_BUDGET: usize = 16 * 1024 * 1024;
+    /// Growing while less than this part of the texture is allocated means that the atlas is
+    /// fragmented.
+    const FRAGMENTATION_THRESHOLD: f32 = 0.5;
+    /// The part of the texture that is filled with glyphs after a compaction at most, so that
+    /// there is room for new ones.
+    const FILL_TARGET: f32 = 0.75;
+    /// The minimum number of frames between compactions.
+    ///
+    /// When the glyphs of every frame exceed the budget, compacting every frame would evict and
+    /// rasterize the same glyphs over and over.
+    const COMPACTION_INTERVAL: u64 = 60;
 
     pub fn new(device: &Device, texture_format: TextureFormat) -> Self {
         assert!(
-            texture_format == TextureFormat::R8Unorm || texture_format == TextureFormat::Rgba8Unorm
+            texture_format == TextureFormat::R8Unorm
+                || texture_format == TextureFormat::Rgba8UnormSrgb
         );
 
         let max_texture_dimension_2d = device.limits().max_texture_dimension_2d;
@@ -52,29 +106,59 @@ impl GlyphAtlas {
             texture,
             allocator,
             images: HashMap::default(),
+            budget: Self::DEFAULT_BUDGET,
+            tick: 0,
+            compaction_pending: false,
+            last_compaction: None,
+            grow_events: 0,
+            evictions: 0,
+            compactions: 0,
         }
     }
 
+    /// Sets the texture size in bytes the atlas is compacted to.
+    ///
+    /// The atlas may exceed the budget while the glyphs of a frame are stored, but is compacted to
+    /// fit in it on a following [`Self::maintain`].
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
     #[allow(unused)]
     pub fn size(&self) -> SizePx {
         let dim = self.texture.dim();
         (dim, dim).into()
     }
 
+    /// Report the current occupancy and the grow, eviction, and compaction events since the
+    /// previous report.
+    pub fn take_stats(&mut self, name: &'static str) -> AtlasStats {
+        AtlasStats {
+            name,
+            dim: self.texture.dim(),
+            allocated_texels: self.allocator.allocated_space() as usize,
+            grow_events: mem::take(&mut self.grow_events),
+            evictions: mem::take(&mut self.evictions),
+            compactions: mem::take(&mut self.compactions),
+        }
+    }
+
     pub fn texture_view(&self) -> &TextureView {
         self.texture.view()
     }
 
     // Optimization: Size of allocation rectangle is _always_ equal to the size of the placement.
-    pub fn get(&self, key: &RasterizedGlyphKey) -> Option<(Rectangle, Placement)> {
-        self.images.get(key).map(|(allocation, placement)| {
+    pub fn get(&mut self, key: &RasterizedGlyphKey) -> Option<(Rectangle, Placement)> {
+        self.images.get_mut(key).map(|entry| {
+            entry.last_used = self.tick;
+            let placement = entry.placement;
             let image_size = size2(placement.width as i32, placement.height as i32);
             (
                 Rectangle::new(
-                    allocation.rectangle.min,
-                    allocation.rectangle.min + image_size,
+                    entry.allocation.rectangle.min,
+                    entry.allocation.rectangle.min + image_size,
                 ),
-                *placement,
+                placement,
             )
         })
     }
@@ -100,8 +184,14 @@ impl GlyphAtlas {
                 debug_assert!(allocated_size.height >= size.height);
                 self.copy_image_to_atlas(queue, &image, allocation.rectangle.min);
                 // commit
-                self.images
-                    .insert(key.clone(), (allocation, image.placement));
+                self.images.insert(
+                    key.clone(),
+                    Entry {
+                        allocation,
+                        placement: image.placement,
+                        last_used: self.tick,
+                    },
+                );
                 let final_rect =
                     Rectangle::new(allocation.rectangle.min, allocation.rectangle.min + size);
                 return Ok(final_rect);
@@ -111,6 +201,76 @@ impl GlyphAtlas {
         }
     }
 
+    /// Starts a new frame and compacts the atlas if it grew beyond its budget or got fragmented.
+    ///
+    /// Must be called once per frame, before rectangles of the atlas are collected for batches.
+    ///
+    /// Returns `true` if the atlas was compacted. Then all batches that were created before refer
+    /// to glyphs that moved into a new texture and must be produced again.
+    pub fn maintain(&mut self, device: &Device, queue: &Queue) -> bool {
+        self.tick += 1;
+        let interval_passed = self
+            .last_compaction
+            .is_none_or(|last| self.tick - last >= Self::COMPACTION_INTERVAL);
+        if !(self.compaction_pending && interval_passed) {
+            return false;
+        }
+        self.compaction_pending = false;
+        self.last_compaction = Some(self.tick);
+        self.compact(device, queue);
+        true
+    }
+
+    /// Re-pack the most recently used glyphs into a new texture that fits in the budget and evict
+    /// the others.
+    fn compact(&mut self, device: &Device, queue: &Queue) {
+        let max_dim = device.limits().max_texture_dimension_2d;
+        let budget_dim = budget_dim(self.texture.bytes_per_texel(), self.budget, max_dim);
+
+        let mut entries: Vec<_> = self.images.drain().collect();
+        let total = entries.len();
+        let (kept, dim) = plan_compaction(
+            &mut entries,
+            |(_, entry)| (entry.last_used, entry.texels()),
+            budget_dim,
+        );
+
+        let current_dim = self.texture.dim();
+        log::info!(
+            "Compacting glyph atlas from {current_dim} to {dim}, keeping {kept} of {total} glyphs"
+        );
+
+        let texture = AtlasTexture::new(device, self.texture.format(), dim);
+        let mut allocator = BucketedAtlasAllocator::new(size2(dim as i32, dim as i32));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Atlas compaction encoder"),
+        });
+
+        for (key, mut entry) in entries.into_iter().take(kept) {
+            let size = size2(entry.placement.width as i32, entry.placement.height as i32);
+            // The bucketed allocator may not pack everything that fits in theory.
+            let Some(allocation) = allocator.allocate(size) else {
+                continue;
+            };
+            Self::copy_rect(
+                &mut encoder,
+                self.texture.texture(),
+                entry.allocation.rectangle.min,
+                texture.texture(),
+                allocation.rectangle.min,
+                (entry.placement.width, entry.placement.height).into(),
+            );
+            entry.allocation = allocation;
+            self.images.insert(key, entry);
+        }
+        queue.submit([encoder.finish()]);
+
+        self.evictions += total - self.images.len();
+        self.texture = texture;
+        self.allocator = allocator;
+        self.compactions += 1;
+    }
+
     fn grow(&mut self, device: &Device, queue: &Queue) -> Result<()> {
         // TODO: allocate additional textures, if this fails. TODO: try to copy from texture to
         // texture when growing (COPY_SRC). Does this cost performance, measure on all backends?
@@ -120,6 +280,18 @@ impl GlyphAtlas {
         let new_dim =
             (current_dim * Self::GROWTH_FACTOR).min(device.limits().max_texture_dimension_2d);
 
+        // Rectangles of the current batch must keep their position, so compaction has to wait
+        // until the next maintenance.
+        let occupancy = self.allocator.allocated_space() as f32 / (current_dim as f32).powi(2);
+        let budget_dim = budget_dim(
+            self.texture.bytes_per_texel(),
+            self.budget,
+            device.limits().max_texture_dimension_2d,
+        );
+        if new_dim > budget_dim || occupancy < Self::FRAGMENTATION_THRESHOLD {
+            self.compaction_pending = true;
+        }
+
         if new_dim == current_dim {
             // TODO: Support multiple atlas textures.
             bail!("Atlas reached its maximum size of {current_dim}x{current_dim}");
@@ -138,6 +310,7 @@ impl GlyphAtlas {
         self.texture = new_texture;
         // After growing, the allocated rectangles retain their position.
         self.allocator.grow(size2(new_dim as i32, new_dim as i32));
+        self.grow_events += 1;
 
         // Performance: Just copy from the old texture and then throw it away?
         // self.upload_all(queue);
@@ -171,6 +344,43 @@ impl GlyphAtlas {
         queue.submit([encoder.finish()]);
     }
 
+    fn copy_rect(
+        encoder: &mut CommandEncoder,
+        from: &Texture,
+        from_pos: Point,
+        to: &Texture,
+        to_pos: Point,
+        size: SizePx,
+    ) {
+        encoder.copy_texture_to_texture(
+            TexelCopyTextureInfo {
+                texture: from,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: from_pos.x as u32,
+                    y: from_pos.y as u32,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            TexelCopyTextureInfo {
+                texture: to,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: to_pos.x as u32,
+                    y: to_pos.y as u32,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
     /// Upload the image to the GPU into the atlas texture at the given position.
     #[instrument(skip_all)]
     fn copy_image_to_atlas(&self, queue: &Queue, image: &SwashImage, pos: Point) {
@@ -205,6 +415,51 @@ impl GlyphAtlas {
     }
 }
 
+/// The largest texture dimension that fits in the `budget` in bytes.
+fn budget_dim(bytes_per_texel: usize, budget: usize, max_dim: u32) -> u32 {
+    let mut dim = GlyphAtlas::INITIAL_SIZE.min(max_dim);
+    loop {
+        let next = (dim * GlyphAtlas::GROWTH_FACTOR).min(max_dim);
+        if next == dim || next as usize * next as usize * bytes_per_texel > budget {
+            return dim;
+        }
+        dim = next;
+    }
+}
+
+/// Sorts the `entries` by their last use, most recent first, and returns how many of them a
+/// compaction keeps and the dimension of the texture they are packed into.
+///
+/// `use_and_texels` returns the tick an entry was last used and its size in texels.
+fn plan_compaction<T>(
+    entries: &mut [T],
+    use_and_texels: impl Fn(&T) -> (u64, usize),
+    budget_dim: u32,
+) -> (usize, u32) {
+    let fill = |dim: u32| (dim as f32 * dim as f32 * GlyphAtlas::FILL_TARGET) as usize;
+
+    entries.sort_by_key(|entry| Reverse(use_and_texels(entry).0));
+
+    let max_texels = fill(budget_dim);
+    let mut kept_texels = 0;
+    let mut kept = 0;
+    for entry in entries.iter() {
+        let (_, texels) = use_and_texels(entry);
+        if kept_texels + texels > max_texels {
+            break;
+        }
+        kept_texels += texels;
+        kept += 1;
+    }
+
+    let mut dim = GlyphAtlas::INITIAL_SIZE.min(budget_dim);
+    while dim < budget_dim && fill(dim) < kept_texels {
+        dim = (dim * GlyphAtlas::GROWTH_FACTOR).min(budget_dim);
+    }
+
+    (kept, dim)
+}
+
 #[derive(Debug)]
 struct AtlasTexture {
     view: TextureView,
@@ -239,6 +494,13 @@ impl AtlasTexture {
         self.texture().format()
     }
 
+    pub fn bytes_per_texel(&self) -> usize {
+        match self.format() {
+            TextureFormat::R8Unorm => 1,
+            _ => 4,
+        }
+    }
+
     pub fn dim(&self) -> u32 {
         self.texture().width()
     }
@@ -251,3 +513,43 @@ impl AtlasTexture {
         &self.view
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_dim_is_the_largest_power_of_two_in_the_budget() {
+        assert_eq!(budget_dim(1, GlyphAtlas::DEFAULT_BUDGET, 8192), 4096);
+        assert_eq!(budget_dim(4, GlyphAtlas::DEFAULT_BUDGET, 8192), 2048);
+        assert_eq!(budget_dim(1, GlyphAtlas::DEFAULT_BUDGET, 1024), 1024);
+        assert_eq!(budget_dim(1, 1000, 8192), GlyphAtlas::INITIAL_SIZE);
+        assert_eq!(budget_dim(1, 1000, 64), 64);
+    }
+
+    #[test]
+    fn compaction_keeps_the_most_recently_used_entries() {
+        // (last used, texels)
+        let mut entries = [(1, 4096), (3, 4096), (2, 4096), (4, 4096)];
+
+        // 256 * 256 * 0.75 fits 12 entries.
+        assert_eq!(plan_compaction(&mut entries, |e| *e, 256), (4, 256));
+        assert_eq!(entries.map(|(used, _)| used), [4, 3, 2, 1]);
+
+        // 128 * 128 * 0.75 fits 3 entries.
+        assert_eq!(plan_compaction(&mut entries, |e| *e, 128), (3, 128));
+    }
+
+    #[test]
+    fn compaction_picks_the_smallest_texture_for_the_kept_entries() {
+        let mut entries = [(1, 100), (2, 100)];
+        assert_eq!(plan_compaction(&mut entries, |e| *e, 4096), (2, 128));
+
+        // 128 * 128 * 0.75 = 12288 texels, 256 * 256 * 0.75 = 49152 texels.
+        let mut entries = [(1, 10000), (2, 10000), (3, 10000)];
+        assert_eq!(plan_compaction(&mut entries, |e| *e, 4096), (3, 256));
+
+        let mut entries: [(u64, usize); 0] = [];
+        assert_eq!(plan_compaction(&mut entries, |e| *e, 4096), (0, 128));
+    }
+}
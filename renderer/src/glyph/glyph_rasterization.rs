@@ -1,7 +1,7 @@
 use cosmic_text::{self as text, fontdb};
 use swash::{
     scale::{Render, ScaleContext, Source, StrikeWith},
-    zeno::Format,
+    zeno::{Angle, Format, Transform},
 };
 use text::SwashContent;
 
@@ -13,6 +13,9 @@ use super::{
 };
 use crate::glyph::GlyphRasterizationParam;
 
+/// The slant of synthesized obliques, like cosmic-text uses.
+const SYNTHETIC_OBLIQUE_DEGREES: f32 = 14.0;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RasterizedGlyphKey {
     pub glyph: GlyphKey,
@@ -60,6 +63,13 @@ pub fn rasterize_glyph(
         }
     };
 
+    // Families without a slanted face get an oblique synthesized from their upright face.
+    let synthesize_oblique = glyph_key.style.is_slanted()
+        && font_system
+            .db()
+            .face(glyph_key.font_id)
+            .is_some_and(|face| face.style == fontdb::Style::Normal);
+
     // Build the scaler
     let mut scaler = context
         .builder(font.as_swash())
@@ -67,7 +77,10 @@ pub fn rasterize_glyph(
         .hint(param.hinted)
         // Detail: the font ignores the font weight (for variable fonts), even though get_font()
         // added the font_weight parameter in cosmic-text 0.15,
-        .variations(&[("wght", glyph_key.weight.0 as f32)])
+        .variations(&[
+            ("wght", glyph_key.weight.0 as f32),
+            ("wdth", glyph_key.stretch.0 as f32),
+        ])
         .build();
 
     // Select our source order
@@ -81,6 +94,12 @@ pub fn rasterize_glyph(
     ])
     // Select a subpixel format
     .format(Format::Alpha)
+    .transform(synthesize_oblique.then(|| {
+        Transform::skew(
+            Angle::from_degrees(SYNTHETIC_OBLIQUE_DEGREES),
+            Angle::from_degrees(0.0),
+        )
+    }))
     // Render the image
     .render(&mut scaler, glyph_key.glyph_id)
 }
@@ -162,3 +181,74 @@ fn pad_image_data(image: &[u8], width: usize, height: usize, pixel_size: usize)
     }
     padded_image
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use massive_shapes::{TextStyle, TextWeight};
+
+    use super::*;
+    use crate::FontManager;
+
+    /// Montserrat, and the same face registered as italic.
+    fn fonts() -> (FontManager, fontdb::ID, fontdb::ID) {
+        let fonts = FontManager::bare("en-US");
+        let upright = fonts.load_font(
+            fs::read(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("../examples/shared/src/fonts/Montserrat/Montserrat-Regular.ttf"),
+            )
+            .unwrap(),
+        )[0];
+        let italic = {
+            let mut font_system = fonts.lock();
+            let db = font_system.db_mut();
+            let mut face = db.face(upright).unwrap().clone();
+            face.style = fontdb::Style::Italic;
+            db.push_face_info(face)
+        };
+        (fonts, upright, italic)
+    }
+
+    fn rasterize(fonts: &FontManager, font_id: fontdb::ID, style: TextStyle) -> text::SwashImage {
+        let glyph_id = fonts
+            .get_font(font_id, fontdb::Weight::NORMAL)
+            .unwrap()
+            .as_swash()
+            .charmap()
+            .map('l');
+        let key = GlyphKey::new(font_id, glyph_id, 32.0, TextWeight::NORMAL).with_style(style);
+        rasterize_glyph(
+            &mut fonts.lock(),
+            &mut ScaleContext::default(),
+            key,
+            SwashRasterizationParam { hinted: false },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn upright_faces_get_a_synthetic_oblique() {
+        let (fonts, upright, _) = fonts();
+
+        let normal = rasterize(&fonts, upright, TextStyle::Normal);
+        let italic = rasterize(&fonts, upright, TextStyle::Italic);
+        let oblique = rasterize(&fonts, upright, TextStyle::Oblique);
+
+        // The slant widens the vertical stroke of the `l`.
+        assert!(italic.placement.width > normal.placement.width);
+        assert_eq!(oblique.data, italic.data);
+    }
+
+    #[test]
+    fn slanted_faces_are_rendered_as_they_are() {
+        let (fonts, _, italic_face) = fonts();
+
+        let normal = rasterize(&fonts, italic_face, TextStyle::Normal);
+        let italic = rasterize(&fonts, italic_face, TextStyle::Italic);
+
+        assert_eq!(italic.placement.width, normal.placement.width);
+        assert_eq!(italic.data, normal.data);
+    }
+}
@@ -0,0 +1,152 @@
+//! Gradient stops, rasterized into the rows of a texture that is sampled by the shape shader.
+
+use std::{collections::HashMap, sync::Arc};
+
+use wgpu::util::{DeviceExt, TextureDataOrder};
+
+use massive_geometry::Color;
+use massive_shapes::GradientStop;
+
+/// The number of texels a gradient is rasterized to.
+pub const RAMP_WIDTH: u32 = 256;
+
+/// Collects the gradients of one batch and assigns each a row in the ramp texture.
+#[derive(Debug, Default)]
+pub struct GradientRamps {
+    /// Gradients are identified by the address of their shared allocation.
+    rows: HashMap<*const (), u32>,
+    texels: Vec<u8>,
+}
+
+impl GradientRamps {
+    /// Returns the row of a gradient, rasterizes it if it is not yet in the texture.
+    pub fn row<T>(&mut self, gradient: &Arc<T>, stops: &[GradientStop]) -> u32 {
+        let key = Arc::as_ptr(gradient) as *const ();
+        let next_row = self.rows.len() as u32;
+        *self.rows.entry(key).or_insert_with(|| {
+            self.texels.extend(rasterize(stops));
+            next_row
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn create_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
+        create_ramp_texture(device, queue, self.rows.len() as u32, &self.texels)
+    }
+}
+
+/// A texture with one transparent row, for batches without gradients.
+pub fn create_empty_ramp_texture(device: &wgpu::Device) -> wgpu::Texture {
+    // wgpu zero-initializes textures.
+    device.create_texture(&ramp_texture_descriptor(1))
+}
+
+fn create_ramp_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    rows: u32,
+    texels: &[u8],
+) -> wgpu::Texture {
+    device.create_texture_with_data(
+        queue,
+        &ramp_texture_descriptor(rows),
+        TextureDataOrder::LayerMajor,
+        texels,
+    )
+}
+
+fn ramp_texture_descriptor(rows: u32) -> wgpu::TextureDescriptor<'static> {
+    wgpu::TextureDescriptor {
+        label: Some("Gradient Ramps"),
+        size: wgpu::Extent3d {
+            width: RAMP_WIDTH,
+            height: rows,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        // sRGB, so that 8 bits per component are enough for dark colors. Texels are decoded to
+        // linear when sampled.
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    }
+}
+
+/// Rasterize gradient stops into sRGB encoded RGBA8 texels.
+///
+/// Colors are interpolated in linear space. Stops don't need to be sorted. Before the first and
+/// after the last stop, the color of the nearest stop is extended.
+fn rasterize(stops: &[GradientStop]) -> Vec<u8> {
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+    (0..RAMP_WIDTH)
+        .flat_map(|x| {
+            let t = x as f32 / (RAMP_WIDTH - 1) as f32;
+            color_at(&stops, t)
+                .to_srgb()
+                .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+        })
+        .collect()
+}
+
+fn color_at(sorted_stops: &[GradientStop], t: f32) -> Color {
+    let Some(first) = sorted_stops.first() else {
+        return Color::TRANSPARENT;
+    };
+    if t <= first.offset {
+        return first.color;
+    }
+
+    for pair in sorted_stops.windows(2) {
+        let [from, to] = [pair[0], pair[1]];
+        if t <= to.offset {
+            let span = to.offset - from.offset;
+            let f = if span > 0.0 {
+                (t - from.offset) / span
+            } else {
+                1.0
+            };
+            return lerp(from.color, to.color, f);
+        }
+    }
+
+    sorted_stops.last().unwrap().color
+}
+
+fn lerp(a: Color, b: Color, f: f32) -> Color {
+    Color::new(
+        a.red + (b.red - a.red) * f,
+        a.green + (b.green - a.green) * f,
+        a.blue + (b.blue - a.blue) * f,
+        a.alpha + (b.alpha - a.alpha) * f,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_are_interpolated_and_extended() {
+        let stops: [GradientStop; 2] = [(0.75, Color::WHITE).into(), (0.25, Color::BLACK).into()];
+        let texels = rasterize(&stops);
+        let texel = |x: usize| &texels[x * 4..x * 4 + 4];
+
+        assert_eq!(texel(0), [0, 0, 0, 255]);
+        assert_eq!(texel(RAMP_WIDTH as usize - 1), [255, 255, 255, 255]);
+        // Linear 0.5, sRGB encoded.
+        let middle = texel(RAMP_WIDTH as usize / 2)[0];
+        assert!((186..=190).contains(&middle));
+    }
+
+    #[test]
+    fn no_stops_are_transparent() {
+        assert!(rasterize(&[]).iter().all(|c| *c == 0));
+    }
+}
@@ -0,0 +1,324 @@
+use std::{collections::HashMap, fmt, mem, sync::Weak};
+
+use anyhow::{Result, bail};
+use etagere::{Allocation, BucketedAtlasAllocator, Rectangle};
+use euclid::size2;
+
+use massive_shapes::{ImageData, ImageHandle};
+
+use crate::{stats::AtlasStats, texture::View};
+
+/// All images that are in use, uploaded once into one RGBA8 texture.
+///
+/// Images are identified by the address of their shared allocation. Their space is released after
+/// the last handle was dropped.
+pub struct ImageAtlas {
+    texture: wgpu::Texture,
+    view: View,
+    allocator: BucketedAtlasAllocator,
+    images: HashMap<usize, AtlasImage>,
+    /// Grow events since the last [`Self::take_stats`].
+    grow_events: usize,
+}
+
+struct AtlasImage {
+    image: Weak<ImageData>,
+    allocation: Allocation,
+}
+
+impl fmt::Debug for ImageAtlas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageAtlas")
+            .field("dim", &self.texture.width())
+            .field("images", &self.images.len())
+            .finish()
+    }
+}
+
+impl ImageAtlas {
+    const INITIAL_SIZE: u32 = 512;
+    const GROWTH_FACTOR: u32 = 2;
+    /// Images are sRGB encoded, they are decoded to linear when sampled.
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        let dim = Self::INITIAL_SIZE.min(device.limits().max_texture_dimension_2d);
+        let texture = create_texture(device, dim);
+        let view = View::from_texture(device, &texture);
+        Self {
+            texture,
+            view,
+            allocator: BucketedAtlasAllocator::new(size2(dim as i32, dim as i32)),
+            images: HashMap::new(),
+            grow_events: 0,
+        }
+    }
+
+    /// The view of the current texture. It changes when the atlas grows.
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    /// Report the current occupancy and the grow events since the previous report.
+    pub fn take_stats(&mut self) -> AtlasStats {
+        AtlasStats {
+            name: "image",
+            dim: self.texture.width(),
+            allocated_texels: self.allocator.allocated_space() as usize,
+            grow_events: mem::take(&mut self.grow_events),
+            evictions: 0,
+            compactions: 0,
+        }
+    }
+
+    /// Release the space of images that are not referenced anymore.
+    pub fn release_dropped(&mut self) {
+        self.images.retain(|_, image| {
+            let alive = image.image.strong_count() > 0;
+            if !alive {
+                self.allocator.deallocate(image.allocation.id);
+            }
+            alive
+        });
+    }
+
+    /// Returns the rectangle of the image in the atlas, uploads it if it is not yet stored.
+    ///
+    /// Returns `None` if the image is larger than the maximum texture size of the device and can
+    /// never be stored.
+    pub fn rect(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &ImageHandle,
+    ) -> Result<Option<Rectangle>> {
+        let size = size2(image.size().width as i32, image.size().height as i32);
+
+        if let Some(stored) = self.images.get(&(image.as_ptr() as usize))
+            && stored.image.strong_count() > 0
+        {
+            let min = stored.allocation.rectangle.min;
+            return Ok(Some(Rectangle::new(min, min + size)));
+        }
+
+        let max_dim = device.limits().max_texture_dimension_2d;
+        if image.size().width > max_dim || image.size().height > max_dim {
+            return Ok(None);
+        }
+
+        let allocation = loop {
+            if let Some(allocation) = self.allocator.allocate(size) {
+                break allocation;
+            }
+            self.grow(device, queue)?;
+        };
+
+        self.upload(queue, image, allocation);
+        if let Some(replaced) = self.images.insert(
+            image.as_ptr() as usize,
+            AtlasImage {
+                image: image.downgrade(),
+                allocation,
+            },
+        ) {
+            // A dropped image that had the same address.
+            self.allocator.deallocate(replaced.allocation.id);
+        }
+
+        let min = allocation.rectangle.min;
+        Ok(Some(Rectangle::new(min, min + size)))
+    }
+
+    fn upload(&self, queue: &wgpu::Queue, image: &ImageData, allocation: Allocation) {
+        let min = allocation.rectangle.min;
+        let size = image.size();
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: min.x as u32,
+                    y: min.y as u32,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            image.pixels(),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Grow the texture and copy the current content.
+    ///
+    /// Batches that were produced before keep the previous texture alive and stay valid.
+    fn grow(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<()> {
+        let current_dim = self.texture.width();
+        let new_dim =
+            (current_dim * Self::GROWTH_FACTOR).min(device.limits().max_texture_dimension_2d);
+
+        if new_dim == current_dim {
+            bail!("Image atlas reached its maximum size of {current_dim}x{current_dim}");
+        }
+
+        log::info!("Growing image atlas from {current_dim} to {new_dim}");
+
+        let texture = create_texture(device, new_dim);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Image atlas copy encoder"),
+        });
+        encoder.copy_texture_to_texture(
+            self.texture.as_image_copy(),
+            texture.as_image_copy(),
+            wgpu::Extent3d {
+                width: current_dim,
+                height: current_dim,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit([encoder.finish()]);
+
+        self.view = View::from_texture(device, &texture);
+        self.texture = texture;
+        // The allocated rectangles retain their position.
+        self.allocator.grow(size2(new_dim as i32, new_dim as i32));
+        self.grow_events += 1;
+        Ok(())
+    }
+}
+
+fn create_texture(device: &wgpu::Device, dim: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Image Atlas"),
+        size: wgpu::Extent3d {
+            width: dim,
+            height: dim,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: ImageAtlas::FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            // COPY_SRC is needed when the atlas grows.
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_device::test_device;
+
+    fn image(width: u32, height: u32) -> ImageHandle {
+        ImageData::new((width, height), vec![0xff; (width * height * 4) as usize])
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn images_are_keyed_by_their_allocation() {
+        let device = test_device();
+        let mut atlas = ImageAtlas::new(&device.device);
+        let handle = image(16, 8);
+        let rect = atlas
+            .rect(&device.device, &device.queue, &handle)
+            .unwrap()
+            .unwrap();
+        assert_eq!(rect.size(), size2(16, 8));
+
+        // Clones share the allocation, equal pixels in another allocation are another image.
+        let clone_rect = atlas
+            .rect(&device.device, &device.queue, &handle.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(clone_rect, rect);
+        assert_eq!(atlas.allocator.allocated_space(), 16 * 8);
+
+        let other_rect = atlas
+            .rect(&device.device, &device.queue, &image(16, 8))
+            .unwrap()
+            .unwrap();
+        assert_ne!(other_rect, rect);
+        assert_eq!(atlas.allocator.allocated_space(), 2 * 16 * 8);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn dropped_images_are_released() {
+        let device = test_device();
+        let mut atlas = ImageAtlas::new(&device.device);
+        let kept = image(16, 16);
+        let dropped = image(32, 32);
+        atlas
+            .rect(&device.device, &device.queue, &kept)
+            .unwrap()
+            .unwrap();
+        atlas
+            .rect(&device.device, &device.queue, &dropped)
+            .unwrap()
+            .unwrap();
+
+        drop(dropped);
+        assert_eq!(atlas.allocator.allocated_space(), 16 * 16 + 32 * 32);
+        atlas.release_dropped();
+        assert_eq!(atlas.allocator.allocated_space(), 16 * 16);
+        assert_eq!(atlas.images.len(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn atlas_grows_and_keeps_the_stored_rectangles() {
+        let device = test_device();
+        let mut atlas = ImageAtlas::new(&device.device);
+        let first = image(400, 400);
+        let rect = atlas
+            .rect(&device.device, &device.queue, &first)
+            .unwrap()
+            .unwrap();
+
+        let second = image(400, 400);
+        atlas
+            .rect(&device.device, &device.queue, &second)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            atlas.texture.width(),
+            ImageAtlas::INITIAL_SIZE * ImageAtlas::GROWTH_FACTOR
+        );
+        assert_eq!(
+            atlas
+                .rect(&device.device, &device.queue, &first)
+                .unwrap()
+                .unwrap(),
+            rect
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn images_larger_than_the_texture_limit_are_skipped() {
+        let device = test_device();
+        let mut atlas = ImageAtlas::new(&device.device);
+        let max_dim = device.device.limits().max_texture_dimension_2d;
+
+        let rect = atlas
+            .rect(&device.device, &device.queue, &image(max_dim + 1, 1))
+            .unwrap();
+        assert_eq!(rect, None);
+        assert_eq!(atlas.texture.width(), ImageAtlas::INITIAL_SIZE);
+        assert!(atlas.images.is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+// Images, sampled from the image atlas.
+
+struct Immediates {
+    view_model: mat4x4<f32>,
+    clip_rect_x: vec2<f32>, // [min_x, max_x]
+    clip_rect_y: vec2<f32>, // [min_y, max_y]
+    alpha: f32,
+}
+
+var<immediate> im: Immediates;
+
+struct TextureSize {
+    value: vec2<f32>,
+    _padding: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var t_atlas: texture_2d<f32>;
+@group(0) @binding(1)
+var<uniform> atlas_size: TextureSize;
+@group(0) @binding(2)
+var s_atlas: sampler;
+@group(0) @binding(3)
+var<uniform> color: vec4<f32>;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    // In atlas texels.
+    @location(1) tex_coords: vec2<f32>,
+    // [left, top, right, bottom] in atlas texels.
+    @location(2) tex_bounds: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) tex_bounds: vec4<f32>,
+    @location(2) model_pos: vec2<f32>,
+}
+
+@vertex
+fn vs_main(vertex_input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = im.view_model * vec4<f32>(vertex_input.position, 1.0);
+    out.tex_coords = vertex_input.tex_coords;
+    out.tex_bounds = vertex_input.tex_bounds;
+    out.model_pos = vertex_input.position.xy;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Clip fragments outside the clip rectangle (exclusive bounds)
+    if (in.model_pos.x < im.clip_rect_x.x || in.model_pos.x >= im.clip_rect_x.y ||
+        in.model_pos.y < im.clip_rect_y.x || in.model_pos.y >= im.clip_rect_y.y) {
+        discard;
+    }
+
+    // Keep linear filtering from picking up texels of neighboring images in the atlas.
+    let tex_coords = clamp(in.tex_coords, in.tex_bounds.xy + 0.5, in.tex_bounds.zw - 0.5);
+    let sample = textureSample(t_atlas, s_atlas, tex_coords / atlas_size.value) * color;
+    return vec4<f32>(sample.rgb, sample.a * im.alpha);
+}
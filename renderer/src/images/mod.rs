@@ -0,0 +1,4 @@
+mod atlas;
+mod renderer;
+
+pub use renderer::*;
@@ -0,0 +1,221 @@
+use anyhow::Result;
+use bytemuck::{Pod, Zeroable};
+use etagere::Rectangle;
+use glam::Vec2;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+
+use massive_geometry::Color;
+use massive_shapes::{Image, ImageSampling, Shape};
+
+use super::atlas::ImageAtlas;
+use crate::{
+    ColorBuffer,
+    pods::{self, AsBytes, VertexLayout},
+    renderer::{PreparationContext, RenderBatch},
+    stats::AtlasStats,
+    texture,
+    tools::{PipelineParams, PipelineVariant, texture_sampler},
+};
+
+const FRAGMENT_SHADER_ENTRY: &str = "fs_main";
+
+/// Renders [`Shape::Image`].
+///
+/// Produces one batch for each sampling mode, in the order of [`ImageSampling`].
+#[derive(Debug)]
+pub struct ImageRenderer {
+    pipeline_params: PipelineParams,
+    bind_group_layout: texture::BindGroupLayout,
+    /// Indexed by [`sampling_index`].
+    samplers: [wgpu::Sampler; 2],
+    /// Images are drawn unmodulated.
+    color: ColorBuffer,
+    atlas: ImageAtlas,
+}
+
+impl ImageRenderer {
+    pub const PIPELINES: usize = 2;
+
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("image.wgsl"));
+        let bind_group_layout = texture::BindGroupLayout::new(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Image Pipeline Layout"),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: pods::Immediates::size(),
+        });
+
+        let targets = [Some(wgpu::ColorTargetState {
+            format: target_format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        Self {
+            pipeline_params: PipelineParams {
+                shader,
+                pipeline_layout,
+                targets,
+                vertex_layout: [Vertex::layout()],
+                fragment_constants: Vec::new(),
+            },
+            bind_group_layout,
+            samplers: [
+                texture_sampler::linear_clamping(device),
+                texture_sampler::nearest_clamping(device),
+            ],
+            color: ColorBuffer::new(device, Color::WHITE),
+            atlas: ImageAtlas::new(device),
+        }
+    }
+
+    pub fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        variant: PipelineVariant,
+    ) -> wgpu::RenderPipeline {
+        self.pipeline_params.create_pipeline(
+            "Image Pipeline",
+            device,
+            FRAGMENT_SHADER_ENTRY,
+            variant,
+        )
+    }
+
+    /// Upload new images and build a batch for each sampling mode. Other shapes are ignored.
+    pub fn batches_from_shapes(
+        &mut self,
+        context: &PreparationContext,
+        shapes: &[Shape],
+    ) -> Result<[Option<RenderBatch>; Self::PIPELINES]> {
+        self.atlas.release_dropped();
+
+        let mut vertices: [Vec<Vertex>; Self::PIPELINES] = Default::default();
+        for shape in shapes {
+            let Shape::Image(image) = shape else {
+                continue;
+            };
+            if image.image.size().is_empty() {
+                continue;
+            }
+            let Some(atlas_rect) = self
+                .atlas
+                .rect(context.device, context.queue, &image.image)?
+            else {
+                let size = image.image.size();
+                log::warn!(
+                    "Skipping image of {}x{}, it exceeds the maximum texture size",
+                    size.width,
+                    size.height
+                );
+                continue;
+            };
+            vertices[sampling_index(image.sampling)].extend(quad_vertices(image, atlas_rect));
+        }
+
+        // Bind groups are created after all uploads, because the atlas may have grown.
+        Ok([0, 1].map(|index| self.batch(context.device, &vertices[index], index)))
+    }
+
+    pub fn take_atlas_stats(&mut self) -> AtlasStats {
+        self.atlas.take_stats()
+    }
+
+    fn batch(
+        &self,
+        device: &wgpu::Device,
+        vertices: &[Vertex],
+        sampling_index: usize,
+    ) -> Option<RenderBatch> {
+        if vertices.is_empty() {
+            return None;
+        }
+
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Image Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let bind_group = self.bind_group_layout.create_bind_group(
+            device,
+            self.atlas.view(),
+            &self.samplers[sampling_index],
+            &self.color,
+        );
+
+        Some(RenderBatch {
+            fs_bind_group: Some(bind_group),
+            vertex_buffer,
+            count: vertices.len() / 4,
+            index_buffer: None,
+        })
+    }
+}
+
+fn sampling_index(sampling: ImageSampling) -> usize {
+    match sampling {
+        ImageSampling::Linear => 0,
+        ImageSampling::Nearest => 1,
+    }
+}
+
+fn quad_vertices(image: &Image, atlas_rect: Rectangle) -> [Vertex; 4] {
+    let [left, top, right, bottom] = image.rect.to_scalars().map(|v| v as f32);
+
+    // Texture coordinates are in atlas texels, they are normalized in the shader.
+    let (min, max) = (atlas_rect.min.to_f32(), atlas_rect.max.to_f32());
+    let bounds = [min.x, min.y, max.x, max.y];
+    let texel = |uv: Vec2| {
+        [
+            min.x + uv.x * (max.x - min.x),
+            min.y + uv.y * (max.y - min.y),
+        ]
+    };
+    let (lt, rb) = image.uv;
+    let (lb, rt) = (Vec2::new(lt.x, rb.y), Vec2::new(rb.x, lt.y));
+
+    [
+        Vertex::new((left, top, 0.0), texel(lt), bounds),
+        Vertex::new((left, bottom, 0.0), texel(lb), bounds),
+        Vertex::new((right, bottom, 0.0), texel(rb), bounds),
+        Vertex::new((right, top, 0.0), texel(rt), bounds),
+    ]
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: pods::Vertex,
+    pub tex_coords: [f32; 2],
+    /// The image in the atlas (left, top, right, bottom) in texels. Sampling is clamped to it.
+    pub tex_bounds: [f32; 4],
+}
+
+impl Vertex {
+    pub fn new(
+        position: impl Into<pods::Vertex>,
+        tex_coords: [f32; 2],
+        tex_bounds: [f32; 4],
+    ) -> Self {
+        Self {
+            position: position.into(),
+            tex_coords,
+            tex_bounds,
+        }
+    }
+}
+
+impl VertexLayout for Vertex {
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRS: [wgpu::VertexAttribute; 3] =
+            wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32x4];
+
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRS,
+        }
+    }
+}
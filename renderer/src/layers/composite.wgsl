@@ -0,0 +1,40 @@
+// Composites an offscreen layer with its group alpha.
+
+struct Immediates {
+    alpha: f32,
+}
+
+var<immediate> im: Immediates;
+
+@group(0) @binding(0)
+var t_color: texture_2d<f32>;
+@group(0) @binding(1)
+var t_depth: texture_2d<f32>;
+
+// One triangle that covers the whole target.
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> FragmentOutput {
+    // The layer has the size of the target.
+    let texel = vec2<i32>(position.xy);
+    let color = textureLoad(t_color, texel, 0);
+    if (color.a == 0.0) {
+        discard;
+    }
+
+    // The depth of the layer's content, so that the layer is occluded like its visuals would be.
+    var out: FragmentOutput;
+    out.color = color * im.alpha;
+    out.depth = textureLoad(t_depth, texel, 0).r;
+    return out;
+}
@@ -0,0 +1,204 @@
+use bytemuck::{Pod, Zeroable};
+
+use massive_geometry::SizePx;
+
+use crate::{
+    bind_group_entries,
+    pods::AsBytes,
+    tools::{BindGroupLayoutBuilder, DEPTH_FORMAT, create_full_screen_pipeline},
+};
+
+/// Renders the visuals of a layer location into offscreen textures and composites them.
+///
+/// Every layer gets its own color and depth texture of the size of the target. The layer is
+/// composited with its depth, so that it is occluded like its visuals would be.
+#[derive(Debug)]
+pub struct LayerRenderer {
+    format: wgpu::TextureFormat,
+    bind_group_layout: wgpu::BindGroupLayout,
+    composite_pipeline: wgpu::RenderPipeline,
+    /// Reused between frames, grows to the maximum number of layers rendered at once.
+    targets: Vec<LayerTarget>,
+}
+
+#[derive(Debug)]
+struct LayerTarget {
+    size: SizePx,
+    color_view: wgpu::TextureView,
+    depth_view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+struct CompositeImmediates {
+    alpha: f32,
+    _padding: [f32; 3],
+}
+
+impl LayerRenderer {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat) -> Self {
+        let bind_group_layout = BindGroupLayoutBuilder::fragment_stage()
+            .texture()
+            .depth_texture()
+            .build("Layer Bind Group Layout", device);
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("composite.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layer Composite Pipeline Layout"),
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: CompositeImmediates::size(),
+        });
+
+        // Layers are rendered into transparent textures, so they contain premultiplied colors.
+        let composite_pipeline = create_full_screen_pipeline(
+            "Layer Composite Pipeline",
+            device,
+            &shader,
+            "fs_main",
+            &pipeline_layout,
+            target_format,
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: Some(true),
+                depth_compare: Some(wgpu::CompareFunction::LessEqual),
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+        );
+
+        Self {
+            format: target_format,
+            bind_group_layout,
+            composite_pipeline,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Make sure that there are `count` layer targets of the given size.
+    pub fn prepare_targets(&mut self, device: &wgpu::Device, size: SizePx, count: usize) {
+        self.targets.retain(|target| target.size == size);
+        while self.targets.len() < count {
+            self.targets.push(LayerTarget::new(
+                device,
+                &self.bind_group_layout,
+                self.format,
+                size,
+            ));
+        }
+    }
+
+    /// The color and depth view the layer at `index` is rendered into.
+    pub fn target_views(&self, index: usize) -> (&wgpu::TextureView, &wgpu::TextureView) {
+        let target = &self.targets[index];
+        (&target.color_view, &target.depth_view)
+    }
+
+    /// Draw the layer at `index` into a pass that has a depth attachment.
+    pub fn composite(&self, pass: &mut wgpu::RenderPass, index: usize, alpha: f32) {
+        pass.set_pipeline(&self.composite_pipeline);
+        pass.set_bind_group(0, &self.targets[index].bind_group, &[]);
+        let immediates = CompositeImmediates {
+            alpha,
+            _padding: [0.0; 3],
+        };
+        pass.set_immediates(0, immediates.as_bytes());
+        pass.draw(0..3, 0..1);
+    }
+}
+
+impl LayerTarget {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        format: wgpu::TextureFormat,
+        size: SizePx,
+    ) -> Self {
+        let create_view = |label, format| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: size.width,
+                        height: size.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        let color_view = create_view("Layer Color", format);
+        let depth_view = create_view("Layer Depth", DEPTH_FORMAT);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Layer Bind Group"),
+            layout,
+            entries: bind_group_entries!(0 => &color_view, 1 => &depth_view),
+        });
+
+        Self {
+            size,
+            color_view,
+            depth_view,
+            bind_group,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use massive_geometry::Color;
+    use massive_scene::{At, ChangeCollector, Object, Scene, ToLocation, Transform};
+    use massive_shapes::{Rect, Shape};
+
+    use super::*;
+    use crate::{HeadlessRenderer, render_device::test_device};
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn decals_outside_of_layers_are_drawn_over_them() {
+        let size = SizePx::new(64, 64);
+        let mut headless = HeadlessRenderer::new(test_device(), size, |builder| {
+            builder.with_background_color(Color::BLACK).with_shapes()
+        });
+
+        // The camera looks at the origin: A fading layer over the pixels 16..48 and a decal over
+        // the pixels 28..36.
+        let scene = Scene::new(Arc::new(ChangeCollector::default()));
+        let transform = Transform::IDENTITY.enter(&scene);
+        let location = transform.to_location().enter(&scene);
+        let layer = transform
+            .to_location()
+            .with_alpha(0.5)
+            .with_layer(true)
+            .enter(&scene);
+        let _layer_content = vec![Shape::Rect(Rect::new((-16., -16., 16., 16.), Color::WHITE))]
+            .at(&layer)
+            .enter(&scene);
+        let _decal = vec![Shape::Rect(Rect::new(
+            (-4., -4., 4., 4.),
+            Color::rgb(1.0, 0.0, 0.0),
+        ))]
+        .at(&location)
+        .with_decal_order(0)
+        .enter(&scene);
+
+        let image = headless.render(scene.take_changes()).unwrap();
+
+        assert_eq!(image.pixel(32, 32), [255, 0, 0, 255]);
+        // Half of white in sRGB.
+        let [red, green, blue, _] = image.pixel(20, 20);
+        assert!((180..=196).contains(&red), "layer: {red}");
+        assert_eq!((red, red), (green, blue));
+        assert_eq!(image.pixel(8, 8), [0, 0, 0, 255]);
+    }
+}
@@ -1,3 +1,4 @@
+mod backdrop;
 mod color_buffer;
 mod glyph;
 mod pods;
@@ -5,6 +6,12 @@ mod pods;
 mod builder;
 mod config;
 mod font_manager;
+mod gradient_ramps;
+mod images;
+mod layers;
+mod offscreen;
+mod path_renderer;
+mod recording;
 mod render_batches;
 mod render_device;
 mod render_geometry;
@@ -15,6 +22,7 @@ mod shape_renderer;
 mod size_buffer;
 mod stats;
 mod text_layer;
+mod texture;
 mod tools;
 mod transactions;
 
@@ -22,16 +30,19 @@ pub use builder::*;
 pub use color_buffer::*;
 pub use config::*;
 pub use font_manager::*;
+pub use offscreen::*;
+pub use recording::*;
 pub use render_device::*;
 pub use render_geometry::RenderGeometry;
 pub use render_submission::*;
 pub use renderer::{PresentationMode, Renderer};
 pub use size_buffer::*;
+pub use stats::*;
 pub use transactions::*;
 
 pub use cosmic_text as text;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
 pub enum RenderPacing {
     #[default]
     // Render as fast as possible to be able to represent input changes.
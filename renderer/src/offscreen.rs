@@ -0,0 +1,439 @@
+//! Rendering into a texture instead of a window surface.
+//!
+//! Used for headless rendering, for example to compare the output of batch producers against
+//! golden images in tests.
+
+use std::sync::mpsc;
+
+use anyhow::{Context, Result, bail};
+
+use massive_geometry::{PixelCamera, SizePx};
+use massive_scene::{SceneChangeSet, id_generator};
+
+use crate::{RenderDevice, RenderGeometry, Renderer, RendererBuilder};
+
+/// An RGBA8 image, rows top to bottom, without padding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub size: SizePx,
+    pub pixels: Vec<u8>,
+}
+
+/// The result of comparing two images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDifference {
+    /// Number of pixels where at least one channel differs by more than the tolerance.
+    pub differing_pixels: usize,
+    /// The largest difference of a single channel.
+    pub max_channel_delta: u8,
+}
+
+impl ImageDifference {
+    pub fn is_match(&self) -> bool {
+        self.differing_pixels == 0
+    }
+}
+
+impl RgbaImage {
+    pub const BYTES_PER_PIXEL: usize = 4;
+
+    pub fn new(size: SizePx, pixels: Vec<u8>) -> Result<Self> {
+        let expected = size.width as usize * size.height as usize * Self::BYTES_PER_PIXEL;
+        if pixels.len() != expected {
+            bail!(
+                "Pixel buffer of {} bytes does not match image size {}x{}",
+                pixels.len(),
+                size.width,
+                size.height
+            );
+        }
+        Ok(Self { size, pixels })
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = (y as usize * self.size.width as usize + x as usize) * Self::BYTES_PER_PIXEL;
+        self.pixels[index..index + Self::BYTES_PER_PIXEL]
+            .try_into()
+            .unwrap()
+    }
+
+    /// Compare with another image of the same size.
+    ///
+    /// `tolerance` is the maximum per channel difference that is still considered equal. Software
+    /// rasterizers and GPUs differ slightly in edge coverage and blending precision.
+    pub fn compare(&self, other: &RgbaImage, tolerance: u8) -> Result<ImageDifference> {
+        if self.size != other.size {
+            bail!(
+                "Image sizes differ: {}x{} vs. {}x{}",
+                self.size.width,
+                self.size.height,
+                other.size.width,
+                other.size.height
+            );
+        }
+
+        let mut difference = ImageDifference {
+            differing_pixels: 0,
+            max_channel_delta: 0,
+        };
+
+        for (a, b) in self
+            .pixels
+            .chunks_exact(Self::BYTES_PER_PIXEL)
+            .zip(other.pixels.chunks_exact(Self::BYTES_PER_PIXEL))
+        {
+            let delta = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+            difference.max_channel_delta = difference.max_channel_delta.max(delta);
+            if delta > tolerance {
+                difference.differing_pixels += 1;
+            }
+        }
+
+        Ok(difference)
+    }
+}
+
+/// The color target of an offscreen renderer.
+#[derive(Debug)]
+pub struct OffscreenTexture {
+    texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+}
+
+impl OffscreenTexture {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, size: SizePx) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+
+    pub fn size(&self) -> SizePx {
+        let size = self.texture.size();
+        (size.width, size.height).into()
+    }
+
+    /// Copy the texture into CPU memory, blocking until the GPU has finished.
+    pub fn read(&self, device: &RenderDevice) -> Result<RgbaImage> {
+        let format = self.texture.format();
+        let swap_red_blue = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => {
+                bail!("Reading back offscreen textures of format {format:?} is not supported")
+            }
+        };
+
+        let size = self.size();
+        let unpadded_bytes_per_row = size.width * RgbaImage::BYTES_PER_PIXEL as u32;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Readback Buffer"),
+            size: padded_bytes_per_row as u64 * size.height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Offscreen Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            self.texture.size(),
+        );
+        let submission_index = device.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                sender
+                    .send(result)
+                    .expect("Readback receiver dropped before the buffer was mapped")
+            });
+        device
+            .device
+            .poll(wgpu::PollType::Wait {
+                submission_index: Some(submission_index),
+                timeout: None,
+            })
+            .context("Waiting for the offscreen readback")?;
+        receiver
+            .recv()
+            .context("Readback callback was not invoked")?
+            .context("Mapping the offscreen readback buffer")?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * size.height) as usize);
+        {
+            let mapped = buffer.slice(..).get_mapped_range();
+            for row in mapped.chunks_exact(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        if swap_red_blue {
+            for pixel in pixels.chunks_exact_mut(RgbaImage::BYTES_PER_PIXEL) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        RgbaImage::new(size, pixels)
+    }
+}
+
+/// A renderer on a headless device that renders scene changes into images.
+///
+/// Meant for tests that compare the rendered output against expected or golden images.
+#[derive(Debug)]
+pub struct HeadlessRenderer {
+    pub renderer: Renderer,
+    pub geometry: RenderGeometry,
+}
+
+impl HeadlessRenderer {
+    /// The format of the rendered images. Colors are stored sRGB encoded.
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    /// Create a renderer of `size` on a device from [`RenderDevice::headless`] with
+    /// [`Self::FORMAT`].
+    ///
+    /// `build` adds the batch producers and configures the renderer.
+    pub fn new(
+        device: RenderDevice,
+        size: SizePx,
+        build: impl FnOnce(RendererBuilder) -> RendererBuilder,
+    ) -> Self {
+        let renderer = build(RendererBuilder::offscreen(device, size)).build();
+        Self {
+            renderer,
+            geometry: RenderGeometry::new(size, PixelCamera::default()),
+        }
+    }
+
+    /// Apply the changes and render them with the current camera.
+    pub fn render(&mut self, changes: SceneChangeSet) -> Result<RgbaImage> {
+        let changes = changes.release();
+        id_generator::gc(&changes);
+        self.renderer.apply_changes(changes)?;
+        self.renderer.prepare()?;
+        self.renderer
+            .render_offscreen(&self.geometry.view_projection())
+    }
+}
+
+#[cfg(feature = "snapshots")]
+mod snapshots {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::BufWriter;
+    use std::path::Path;
+
+    use anyhow::{Context, Result, bail};
+    use log::warn;
+
+    use super::RgbaImage;
+
+    /// Set this environment variable to (re-)write golden images instead of comparing against them.
+    pub const UPDATE_GOLDEN_IMAGES_VAR: &str = "MASSIVE_UPDATE_GOLDEN_IMAGES";
+
+    impl RgbaImage {
+        pub fn save_png(&self, path: &Path) -> Result<()> {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create PNG file: {}", path.display()))?;
+            let mut encoder =
+                png::Encoder::new(BufWriter::new(file), self.size.width, self.size.height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder
+                .write_header()
+                .with_context(|| format!("Failed to write PNG header: {}", path.display()))?;
+            writer
+                .write_image_data(&self.pixels)
+                .with_context(|| format!("Failed to write PNG data: {}", path.display()))
+        }
+
+        pub fn load_png(path: &Path) -> Result<Self> {
+            let file = File::open(path)
+                .with_context(|| format!("Failed to open PNG file: {}", path.display()))?;
+            let mut decoder = png::Decoder::new(file);
+            decoder.set_transformations(png::Transformations::EXPAND);
+            let mut reader = decoder
+                .read_info()
+                .with_context(|| format!("Failed to read PNG header: {}", path.display()))?;
+            let mut pixels = vec![0; reader.output_buffer_size()];
+            let info = reader
+                .next_frame(&mut pixels)
+                .with_context(|| format!("Failed to read PNG data: {}", path.display()))?;
+            if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+                bail!(
+                    "Expected an 8 bit RGBA PNG, got {:?} {:?}: {}",
+                    info.color_type,
+                    info.bit_depth,
+                    path.display()
+                );
+            }
+            pixels.truncate(info.buffer_size());
+            RgbaImage::new((info.width, info.height).into(), pixels)
+        }
+
+        /// Compare against a golden image.
+        ///
+        /// If the golden image does not exist or [`UPDATE_GOLDEN_IMAGES_VAR`] is set, the golden
+        /// image is written instead. On a mismatch, the actual image is saved next to the golden
+        /// one with the extension `actual.png` and an error is returned.
+        pub fn assert_matches_golden(&self, golden: &Path, tolerance: u8) -> Result<()> {
+            if env::var_os(UPDATE_GOLDEN_IMAGES_VAR).is_some() || !fs::exists(golden)? {
+                warn!("Writing golden image: {}", golden.display());
+                if let Some(dir) = golden.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                return self.save_png(golden);
+            }
+
+            let expected = Self::load_png(golden)?;
+            let difference = self.compare(&expected, tolerance)?;
+            if !difference.is_match() {
+                let actual = golden.with_extension("actual.png");
+                self.save_png(&actual)?;
+                bail!(
+                    "Image does not match golden image {}: {} pixels differ, max channel delta: {}, actual image: {}",
+                    golden.display(),
+                    difference.differing_pixels,
+                    difference.max_channel_delta,
+                    actual.display()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "snapshots")]
+pub use snapshots::UPDATE_GOLDEN_IMAGES_VAR;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use massive_geometry::Color;
+    use massive_scene::{At, ChangeCollector, Object, Scene, ToLocation, Transform};
+    use massive_shapes::{Rect, Shape};
+
+    use super::*;
+    use crate::render_device::test_device;
+
+    #[test]
+    fn images_are_compared_within_the_tolerance() {
+        let image = RgbaImage::new((2, 1).into(), vec![10, 20, 30, 255, 0, 0, 0, 255]).unwrap();
+        let other = RgbaImage::new((2, 1).into(), vec![12, 20, 30, 255, 0, 0, 9, 255]).unwrap();
+
+        assert!(image.compare(&image, 0).unwrap().is_match());
+        assert_eq!(
+            image.compare(&other, 2).unwrap(),
+            ImageDifference {
+                differing_pixels: 1,
+                max_channel_delta: 9
+            }
+        );
+        assert!(image.compare(&other, 9).unwrap().is_match());
+
+        let transposed = RgbaImage::new((1, 2).into(), image.pixels.clone()).unwrap();
+        assert!(image.compare(&transposed, 0).is_err());
+        assert!(RgbaImage::new((2, 2).into(), vec![0; 4]).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn shape_renderer_draws_a_rect() {
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+        let size = SizePx::new(64, 64);
+        let mut headless = HeadlessRenderer::new(test_device(), size, |builder| {
+            builder.with_background_color(Color::BLACK).with_shapes()
+        });
+
+        // The camera looks at the origin, so the rect ends up in the center of the image.
+        let scene = Scene::new(Arc::new(ChangeCollector::default()));
+        let location = Transform::IDENTITY
+            .enter(&scene)
+            .to_location()
+            .enter(&scene);
+        let _visual = vec![Shape::Rect(Rect::new(
+            (-16., -8., 16., 8.),
+            Color::rgb(1.0, 0.0, 0.0),
+        ))]
+        .at(&location)
+        .enter(&scene);
+
+        let image = headless.render(scene.take_changes()).unwrap();
+
+        let mut expected = Vec::new();
+        for y in 0..size.height {
+            for x in 0..size.width {
+                let inside = (16..48).contains(&x) && (24..40).contains(&y);
+                expected.extend(if inside { RED } else { BLACK });
+            }
+        }
+        let expected = RgbaImage::new(size, expected).unwrap();
+
+        // Anti-aliasing bleeds slightly into the pixels diagonal to the corners, a misplaced edge
+        // would differ by a full channel.
+        let difference = image.compare(&expected, 64).unwrap();
+        assert!(difference.is_match(), "{difference:?}");
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn golden_images_are_written_and_then_compared() {
+        use std::{env, fs};
+
+        let dir = env::temp_dir().join(format!("massive-golden-{}", std::process::id()));
+        let golden = dir.join("image.png");
+        let image = RgbaImage::new((2, 1).into(), vec![10, 20, 30, 255, 0, 0, 0, 128]).unwrap();
+
+        image.assert_matches_golden(&golden, 0).unwrap();
+        assert_eq!(RgbaImage::load_png(&golden).unwrap(), image);
+        image.assert_matches_golden(&golden, 0).unwrap();
+
+        let mut changed = image.clone();
+        changed.pixels[0] = 20;
+        assert!(changed.assert_matches_golden(&golden, 5).is_err());
+        assert_eq!(
+            RgbaImage::load_png(&golden.with_extension("actual.png")).unwrap(),
+            changed
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
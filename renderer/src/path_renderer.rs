@@ -0,0 +1,324 @@
+use anyhow::{Context, Result};
+use bytemuck::{Pod, Zeroable};
+use lyon_tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+    StrokeVertex, VertexBuffers,
+    math::point,
+    path::{self as lyon_path, Path as LyonPath},
+};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+
+use massive_geometry::Point;
+use massive_shapes::{FillRule, LineCap, LineJoin, Path, PathSegment, Shape};
+
+use crate::{
+    pods::{self, AsBytes, VertexLayout},
+    renderer::RenderBatch,
+    tools::{PipelineParams, PipelineVariant},
+};
+
+const FRAGMENT_SHADER_ENTRY: &str = "fs_main";
+
+/// Maximum distance in model space between a curve and its flattened approximation.
+const TESSELLATION_TOLERANCE: f32 = 0.1;
+
+/// Renders [`Shape::Path`] by tessellating fills and strokes into triangles.
+///
+/// The triangles are not anti-aliased.
+pub struct PathRenderer {
+    pipeline_params: PipelineParams,
+    fill_tessellator: FillTessellator,
+    stroke_tessellator: StrokeTessellator,
+}
+
+impl PathRenderer {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("path_renderer.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Path Pipeline Layout"),
+            bind_group_layouts: &[],
+            immediate_size: pods::Immediates::size(),
+        });
+
+        let targets = [Some(wgpu::ColorTargetState {
+            format: target_format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        let vertex_layout = [Vertex::layout()];
+
+        Self {
+            pipeline_params: PipelineParams {
+                shader,
+                pipeline_layout,
+                targets,
+                vertex_layout,
+                fragment_constants: Vec::new(),
+            },
+            fill_tessellator: FillTessellator::new(),
+            stroke_tessellator: StrokeTessellator::new(),
+        }
+    }
+
+    pub fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        variant: PipelineVariant,
+    ) -> wgpu::RenderPipeline {
+        self.pipeline_params.create_pipeline(
+            "Path Pipeline",
+            device,
+            FRAGMENT_SHADER_ENTRY,
+            variant,
+        )
+    }
+
+    /// Tessellate all paths of `shapes` into one batch. Other shapes are ignored.
+    pub fn batch_from_shapes(
+        &mut self,
+        device: &wgpu::Device,
+        shapes: &[Shape],
+    ) -> Result<Option<RenderBatch>> {
+        let mut geometry: VertexBuffers<Vertex, u32> = VertexBuffers::new();
+
+        for shape in shapes {
+            if let Shape::Path(path) = shape {
+                self.tessellate(path, &mut geometry)?;
+            }
+        }
+
+        if geometry.indices.is_empty() {
+            return Ok(None);
+        }
+
+        orient_triangles(&geometry.vertices, &mut geometry.indices);
+
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Path Vertex Buffer"),
+            contents: bytemuck::cast_slice(&geometry.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Path Index Buffer"),
+            contents: bytemuck::cast_slice(&geometry.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Ok(Some(RenderBatch {
+            fs_bind_group: None,
+            vertex_buffer,
+            count: geometry.indices.len(),
+            index_buffer: Some(index_buffer),
+        }))
+    }
+
+    fn tessellate(&mut self, path: &Path, geometry: &mut VertexBuffers<Vertex, u32>) -> Result<()> {
+        if path.fill.is_none() && path.stroke.is_none() {
+            return Ok(());
+        }
+
+        let tessellation_path = to_lyon_path(&path.segments);
+
+        // The stroke is tessellated last so that it is drawn on top of the fill.
+        if let Some(fill) = &path.fill {
+            let options =
+                FillOptions::tolerance(TESSELLATION_TOLERANCE).with_fill_rule(match fill.rule {
+                    FillRule::NonZero => lyon_path::FillRule::NonZero,
+                    FillRule::EvenOdd => lyon_path::FillRule::EvenOdd,
+                });
+            let color = fill.color.into();
+            self.fill_tessellator
+                .tessellate_path(
+                    &tessellation_path,
+                    &options,
+                    &mut BuffersBuilder::new(geometry, |vertex: FillVertex| {
+                        Vertex::new(vertex.position().to_array(), color)
+                    }),
+                )
+                .context("Tessellating path fill")?;
+        }
+
+        if let Some(stroke) = &path.stroke {
+            let options = StrokeOptions::tolerance(TESSELLATION_TOLERANCE)
+                .with_line_width(stroke.width)
+                .with_line_join(match stroke.join {
+                    LineJoin::Miter => lyon_path::LineJoin::Miter,
+                    LineJoin::Round => lyon_path::LineJoin::Round,
+                    LineJoin::Bevel => lyon_path::LineJoin::Bevel,
+                })
+                .with_line_cap(match stroke.cap {
+                    LineCap::Butt => lyon_path::LineCap::Butt,
+                    LineCap::Round => lyon_path::LineCap::Round,
+                    LineCap::Square => lyon_path::LineCap::Square,
+                });
+            let color = stroke.color.into();
+            self.stroke_tessellator
+                .tessellate_path(
+                    &tessellation_path,
+                    &options,
+                    &mut BuffersBuilder::new(geometry, |vertex: StrokeVertex| {
+                        Vertex::new(vertex.position().to_array(), color)
+                    }),
+                )
+                .context("Tessellating path stroke")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn to_lyon_path(segments: &[PathSegment]) -> LyonPath {
+    let to_point = |p: Point| point(p.x as f32, p.y as f32);
+
+    let mut builder = LyonPath::builder();
+    // Lyon requires every sub-path to begin explicitly, so we track if one is open and where the
+    // pen is.
+    let mut open = false;
+    let mut start = Point::ORIGIN;
+    let mut current = Point::ORIGIN;
+
+    for segment in segments {
+        let draws = !matches!(segment, PathSegment::MoveTo(_) | PathSegment::Close);
+        if draws && !open {
+            builder.begin(to_point(current));
+            start = current;
+            open = true;
+        }
+
+        match *segment {
+            PathSegment::MoveTo(to) => {
+                if open {
+                    builder.end(false);
+                }
+                builder.begin(to_point(to));
+                open = true;
+                start = to;
+                current = to;
+            }
+            PathSegment::LineTo(to) => {
+                builder.line_to(to_point(to));
+                current = to;
+            }
+            PathSegment::QuadTo(control, to) => {
+                builder.quadratic_bezier_to(to_point(control), to_point(to));
+                current = to;
+            }
+            PathSegment::CubicTo(control1, control2, to) => {
+                builder.cubic_bezier_to(to_point(control1), to_point(control2), to_point(to));
+                current = to;
+            }
+            PathSegment::Close => {
+                if open {
+                    builder.end(true);
+                    open = false;
+                }
+                current = start;
+            }
+        }
+    }
+
+    if open {
+        builder.end(false);
+    }
+
+    builder.build()
+}
+
+/// Lyon does not guarantee a winding order, but the pipelines cull back faces. Orient all triangles
+/// like the quads of the other renderers: counter-clockwise on screen.
+fn orient_triangles(vertices: &[Vertex], indices: &mut [u32]) {
+    for triangle in indices.chunks_exact_mut(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position());
+        let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        if cross > 0.0 {
+            triangle.swap(1, 2);
+        }
+    }
+}
+
+/// Vertex format for `path_renderer.wgsl`.
+/// locations: 0=position, 1=color
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub color: pods::Color,
+}
+
+impl Vertex {
+    pub fn new(position: [f32; 2], color: pods::Color) -> Self {
+        Self {
+            position: [position[0], position[1], 0.0],
+            color,
+        }
+    }
+
+    fn position(&self) -> [f32; 2] {
+        [self.position[0], self.position[1]]
+    }
+}
+
+impl VertexLayout for Vertex {
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRS: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            0 => Float32x3, // position
+            1 => Float32x4  // color
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: core::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massive_geometry::Color;
+
+    use super::*;
+
+    #[test]
+    fn triangles_face_like_quads() {
+        let vertices = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]
+            .map(|(x, y)| Vertex::new([x, y], Color::BLACK.into()));
+        // Clockwise on screen (y points down).
+        let mut indices = [0, 1, 2];
+        orient_triangles(&vertices, &mut indices);
+        assert_eq!(indices, [0, 2, 1]);
+
+        let mut indices = [0, 2, 1];
+        orient_triangles(&vertices, &mut indices);
+        assert_eq!(indices, [0, 2, 1]);
+    }
+
+    #[test]
+    fn sub_paths_begin_and_end_implicitly() {
+        let segments = [
+            PathSegment::LineTo((10.0, 0.0).into()),
+            PathSegment::LineTo((10.0, 10.0).into()),
+            PathSegment::Close,
+            PathSegment::LineTo((0.0, 10.0).into()),
+            PathSegment::MoveTo((20.0, 20.0).into()),
+            PathSegment::QuadTo((30.0, 20.0).into(), (30.0, 30.0).into()),
+        ];
+
+        let path = to_lyon_path(&segments);
+        let events = path.iter().collect::<Vec<_>>();
+
+        let begins = events
+            .iter()
+            .filter(|e| matches!(e, lyon_path::Event::Begin { .. }))
+            .count();
+        let closed = events
+            .iter()
+            .filter(|e| matches!(e, lyon_path::Event::End { close: true, .. }))
+            .count();
+        assert_eq!(begins, 3);
+        assert_eq!(closed, 1);
+    }
+}
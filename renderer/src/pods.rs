@@ -126,7 +126,6 @@ impl From<Vector3> for Vertex {
     }
 }
 
-#[allow(unused)]
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct ColorVertex {
@@ -134,7 +133,6 @@ pub struct ColorVertex {
     pub color: Color,
 }
 
-#[allow(unused)]
 impl ColorVertex {
     pub fn new(position: impl Into<Vertex>, color: impl Into<Color>) -> Self {
         Self {
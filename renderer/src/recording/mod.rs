@@ -0,0 +1,7 @@
+mod recorder;
+mod replayer;
+mod types;
+
+pub use recorder::*;
+pub use replayer::*;
+pub use types::EncodedChanges;
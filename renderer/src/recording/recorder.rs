@@ -0,0 +1,353 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Arc, Weak},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use derive_more::Debug;
+use parking_lot::Mutex;
+
+use massive_scene::{
+    Change, HandleChangeReceiver, LocationRenderObj, SceneChange, SceneChangeSet, Transform,
+    VisualRenderObj,
+};
+use massive_shapes::{GlyphRun, ImageData, ImageHandle, Paint, PathSegment, Shape};
+
+use super::types::*;
+use crate::{FontId, FontManager};
+
+/// A [`HandleChangeReceiver`] that records every change set that is taken from it.
+///
+/// All changes are forwarded to the wrapped receiver. Recording errors are logged and don't
+/// interrupt the application.
+#[derive(Debug)]
+pub struct ChangeRecorder {
+    receiver: Arc<dyn HandleChangeReceiver>,
+    writer: Mutex<RecordingWriter>,
+}
+
+impl ChangeRecorder {
+    pub fn new(receiver: Arc<dyn HandleChangeReceiver>, writer: RecordingWriter) -> Self {
+        Self {
+            receiver,
+            writer: writer.into(),
+        }
+    }
+}
+
+impl HandleChangeReceiver for ChangeRecorder {
+    fn send(&self, change: SceneChange) {
+        self.receiver.send(change);
+    }
+
+    fn take_changes(&self) -> SceneChangeSet {
+        let changes = self.receiver.take_changes();
+        if let Err(e) = self.writer.lock().write_frame(&changes) {
+            log::error!("Failed to record scene changes: {e:?}");
+        }
+        changes
+    }
+}
+
+/// Writes timestamped change sets.
+#[derive(Debug)]
+pub struct RecordingWriter {
+    #[debug(skip)]
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+    encoder: ChangeEncoder,
+}
+
+impl RecordingWriter {
+    pub fn create(path: impl AsRef<Path>, fonts: FontManager) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("Creating recording file `{}`", path.display()))?;
+        Self::new(BufWriter::new(file), fonts)
+    }
+
+    pub fn new(mut writer: impl Write + Send + 'static, fonts: FontManager) -> Result<Self> {
+        let header = RecordingHeader {
+            version: RECORDING_VERSION,
+        };
+        write_message(&mut writer, &header)?;
+        Ok(Self {
+            writer: Box::new(writer),
+            start: Instant::now(),
+            encoder: ChangeEncoder::new(fonts),
+        })
+    }
+
+    /// Write one change set. Empty change sets are skipped.
+    pub fn write_frame(&mut self, changes: &[SceneChange]) -> Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let frame = RecordedFrame {
+            time: self.start.elapsed(),
+            changes: self.encoder.encode(changes),
+        };
+
+        write_message(&mut self.writer, &frame)?;
+        // Recordings are most useful when the application crashes, so don't keep anything back.
+        self.writer.flush().context("Flushing recording")
+    }
+}
+
+/// Converts changes into their serializable records.
+///
+/// Fonts and images are included once, the first time they are referenced.
+#[derive(Debug)]
+pub struct ChangeEncoder {
+    fonts: FontManager,
+    font_indices: HashMap<FontId, u32>,
+    /// Images are identified by the address of their shared allocation.
+    image_indices: HashMap<usize, (Weak<ImageData>, u32)>,
+    next_image_index: u32,
+    new_fonts: Vec<FontRecord>,
+    new_images: Vec<ImageRecord>,
+}
+
+impl ChangeEncoder {
+    pub fn new(fonts: FontManager) -> Self {
+        Self {
+            fonts,
+            font_indices: HashMap::new(),
+            image_indices: HashMap::new(),
+            next_image_index: 0,
+            new_fonts: Vec::new(),
+            new_images: Vec::new(),
+        }
+    }
+
+    pub fn encode(&mut self, changes: &[SceneChange]) -> EncodedChanges {
+        let changes = changes.iter().map(|change| self.change(change)).collect();
+        EncodedChanges {
+            fonts: self.new_fonts.drain(..).collect(),
+            images: self.new_images.drain(..).collect(),
+            changes,
+        }
+    }
+
+    fn change(&mut self, change: &SceneChange) -> ChangeRecord {
+        match change {
+            SceneChange::Transform(change) => {
+                ChangeRecord::Transform(object_change(change, transform_record))
+            }
+            SceneChange::Location(change) => {
+                ChangeRecord::Location(object_change(change, location_record))
+            }
+            SceneChange::Visual(change) => {
+                ChangeRecord::Visual(object_change(change, |visual| self.visual(visual)))
+            }
+        }
+    }
+
+    fn visual(&mut self, visual: &VisualRenderObj) -> VisualRecord {
+        VisualRecord {
+            location: *visual.location,
+            decal_order: visual.decal_order.map(|order| order as u64),
+            clip_bounds: visual.clip_bounds.map(|bounds| (bounds.min, bounds.max)),
+            backdrop_blur: visual.backdrop_blur,
+            shapes: visual
+                .shapes
+                .iter()
+                .filter_map(|shape| self.shape(shape))
+                .collect(),
+        }
+    }
+
+    fn shape(&mut self, shape: &Shape) -> Option<ShapeRecord> {
+        let record = match shape {
+            Shape::Rect(r) => ShapeRecord::Rect {
+                rect: r.rect.to_scalars(),
+                paint: paint_record(&r.paint),
+            },
+            Shape::RoundRect(r) => ShapeRecord::RoundRect {
+                rect: r.rect.to_scalars(),
+                corner_radius: r.corner_radius,
+                paint: paint_record(&r.paint),
+            },
+            Shape::Circle(c) => ShapeRecord::Circle {
+                rect: c.rect.to_scalars(),
+                paint: paint_record(&c.paint),
+            },
+            Shape::Ellipse(e) => ShapeRecord::Ellipse {
+                rect: e.rect.to_scalars(),
+                paint: paint_record(&e.paint),
+            },
+            Shape::BeveledRect(r) => ShapeRecord::BeveledRect {
+                rect: r.rect.to_scalars(),
+                chamfer: r.chamfer,
+                corner_mask: r.corner_mask,
+                paint: paint_record(&r.paint),
+            },
+            Shape::StrokeRect(r) => ShapeRecord::StrokeRect {
+                rect: r.rect.to_scalars(),
+                stroke: (r.stroke.width, r.stroke.height),
+                color: r.color,
+            },
+            Shape::BoxShadow(s) => ShapeRecord::BoxShadow {
+                rect: s.rect.to_scalars(),
+                corner_radius: s.corner_radius,
+                offset: (s.offset.x, s.offset.y),
+                blur_radius: s.blur_radius,
+                spread: s.spread,
+                color: s.color,
+            },
+            Shape::Image(i) => ShapeRecord::Image {
+                rect: i.rect.to_scalars(),
+                uv: [i.uv.0.x, i.uv.0.y, i.uv.1.x, i.uv.1.y],
+                sampling: i.sampling,
+                image: self.image_index(&i.image),
+            },
+            Shape::Path(p) => ShapeRecord::Path(PathRecord {
+                segments: p.segments.iter().map(path_segment_record).collect(),
+                fill: p.fill.map(|fill| (fill.rule, fill.color)),
+                stroke: p.stroke.map(|stroke| PathStrokeRecord {
+                    width: stroke.width,
+                    join: stroke.join,
+                    cap: stroke.cap,
+                    color: stroke.color,
+                }),
+            }),
+            Shape::GlyphRun(run) => ShapeRecord::GlyphRun(self.glyph_run(run)),
+            Shape::Custom(custom) => {
+                log::warn!("Custom shapes can't be recorded: {custom:?}");
+                return None;
+            }
+        };
+        Some(record)
+    }
+
+    fn glyph_run(&mut self, run: &GlyphRun) -> GlyphRunRecord {
+        GlyphRunRecord {
+            translation: run.translation.to_array(),
+            metrics: (
+                run.metrics.max_ascent,
+                run.metrics.max_descent,
+                run.metrics.width,
+            ),
+            text_color: run.text_color,
+            text_weight: run.text_weight,
+            glyphs: run
+                .glyphs
+                .iter()
+                .map(|glyph| GlyphRecord {
+                    pos: glyph.pos,
+                    font: self.font_index(glyph.key.font_id),
+                    glyph_id: glyph.key.glyph_id,
+                    font_size_bits: glyph.key.font_size_bits,
+                    weight: glyph.key.weight,
+                    style: glyph.key.style,
+                    stretch: glyph.key.stretch,
+                })
+                .collect(),
+            decorations: run.decorations().to_vec(),
+        }
+    }
+
+    fn font_index(&mut self, font_id: FontId) -> u32 {
+        if let Some(index) = self.font_indices.get(&font_id) {
+            return *index;
+        }
+
+        let index = self.font_indices.len() as u32;
+        let record = {
+            let font_system = self.fonts.lock();
+            let face = font_system.db().face(font_id);
+            FontRecord {
+                index,
+                post_script_name: face
+                    .map(|face| face.post_script_name.clone())
+                    .unwrap_or_default(),
+                family: face.and_then(|face| face.families.first().map(|(name, _)| name.clone())),
+                weight: face.map(|face| face.weight.0).unwrap_or(400),
+                italic: face.is_some_and(|face| face.style != cosmic_text::Style::Normal),
+            }
+        };
+        self.font_indices.insert(font_id, index);
+        self.new_fonts.push(record);
+        index
+    }
+
+    fn image_index(&mut self, image: &ImageHandle) -> u32 {
+        let key = image.as_ptr() as usize;
+        if let Some((weak, index)) = self.image_indices.get(&key)
+            && weak.strong_count() > 0
+        {
+            return *index;
+        }
+
+        let index = self.next_image_index;
+        self.next_image_index += 1;
+        self.image_indices.insert(key, (image.downgrade(), index));
+        self.new_images.push(ImageRecord {
+            index,
+            size: image.size().to_tuple(),
+            pixels: image.pixels().to_vec(),
+        });
+        index
+    }
+}
+
+fn object_change<T, R>(change: &Change<T>, mut record: impl FnMut(&T) -> R) -> ObjectChange<R> {
+    match change {
+        Change::Create(id, value) => ObjectChange::Create(**id, record(value)),
+        Change::Update(id, value) => ObjectChange::Update(**id, record(value)),
+        Change::Delete(id) => ObjectChange::Delete(**id),
+    }
+}
+
+fn transform_record(transform: &Transform) -> TransformRecord {
+    TransformRecord {
+        translate: transform.translate.to_array(),
+        rotate: transform.rotate.to_array(),
+        scale: transform.scale,
+    }
+}
+
+fn location_record(location: &LocationRenderObj) -> LocationRecord {
+    LocationRecord {
+        parent: location.parent.map(|id| *id),
+        transform: *location.transform,
+        alpha: location.alpha,
+        layer: location.layer,
+    }
+}
+
+fn paint_record(paint: &Paint) -> PaintRecord {
+    let stops = |stops: &[massive_shapes::GradientStop]| {
+        stops.iter().map(|stop| (stop.offset, stop.color)).collect()
+    };
+    match paint {
+        Paint::Solid(color) => PaintRecord::Solid(*color),
+        Paint::LinearGradient(gradient) => PaintRecord::LinearGradient {
+            start: gradient.start,
+            end: gradient.end,
+            stops: stops(&gradient.stops),
+        },
+        Paint::RadialGradient(gradient) => PaintRecord::RadialGradient {
+            center: gradient.center,
+            radius: gradient.radius,
+            stops: stops(&gradient.stops),
+        },
+    }
+}
+
+fn path_segment_record(segment: &PathSegment) -> PathSegmentRecord {
+    match *segment {
+        PathSegment::MoveTo(to) => PathSegmentRecord::MoveTo(to),
+        PathSegment::LineTo(to) => PathSegmentRecord::LineTo(to),
+        PathSegment::QuadTo(control, to) => PathSegmentRecord::QuadTo(control, to),
+        PathSegment::CubicTo(control1, control2, to) => {
+            PathSegmentRecord::CubicTo(control1, control2, to)
+        }
+        PathSegment::Close => PathSegmentRecord::Close,
+    }
+}
@@ -0,0 +1,468 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use cosmic_text::{Family, Style, Weight, fontdb};
+use derive_more::Debug;
+
+use massive_geometry::{Bounds, Quaternion, Rect, Size, Vector3};
+use massive_scene::{Change, Id, LocationRenderObj, SceneChange, Transform, VisualRenderObj};
+use massive_shapes::{
+    BeveledRect, BoxShadow, Circle, Ellipse, GlyphKey, GlyphRun, GlyphRunMetrics, GradientStop,
+    Image, ImageData, ImageHandle, LinearGradient, Paint, Path as ShapePath, PathFill, PathSegment,
+    PathStroke, RadialGradient, Rect as FilledRect, RoundRect, RunGlyph, Shape, StrokeRect,
+};
+
+use super::types::*;
+use crate::{FontId, FontManager, Renderer};
+
+/// Reads a recording and feeds its change sets into a renderer, one frame at a time.
+///
+/// Recorded fonts are resolved in the given font manager, see [`ChangeDecoder`].
+#[derive(Debug)]
+pub struct Replayer {
+    #[debug(skip)]
+    reader: Box<dyn Read + Send>,
+    decoder: ChangeDecoder,
+}
+
+/// The changes of one recorded frame.
+#[derive(Debug)]
+pub struct ReplayedFrame {
+    /// The time since the recording started.
+    pub time: Duration,
+    pub changes: Vec<SceneChange>,
+}
+
+impl Replayer {
+    pub fn open(path: impl AsRef<Path>, fonts: FontManager) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("Opening recording `{}`", path.display()))?;
+        Self::new(BufReader::new(file), fonts)
+    }
+
+    pub fn new(mut reader: impl Read + Send + 'static, fonts: FontManager) -> Result<Self> {
+        let Some(header) = read_message::<RecordingHeader>(&mut reader)? else {
+            bail!("Recording is empty");
+        };
+        if header.version != RECORDING_VERSION {
+            bail!(
+                "Unsupported recording version {}, expected {RECORDING_VERSION}",
+                header.version
+            );
+        }
+
+        Ok(Self {
+            reader: Box::new(reader),
+            decoder: ChangeDecoder::new(fonts),
+        })
+    }
+
+    /// Read the next frame. Returns `None` at the end of the recording.
+    pub fn next_frame(&mut self) -> Result<Option<ReplayedFrame>> {
+        let Some(frame) = read_message::<RecordedFrame>(&mut self.reader)? else {
+            return Ok(None);
+        };
+
+        Ok(Some(ReplayedFrame {
+            time: frame.time,
+            changes: self.decoder.decode(frame.changes)?,
+        }))
+    }
+
+    /// Apply the changes of the next frame to the renderer.
+    ///
+    /// Returns the time of the frame relative to the start of the recording, or `None` at the end
+    /// of the recording. The renderer does not need to have seen any other changes, because the
+    /// recorded ids are used as is.
+    pub fn apply_next_frame(&mut self, renderer: &mut Renderer) -> Result<Option<Duration>> {
+        let Some(frame) = self.next_frame()? else {
+            return Ok(None);
+        };
+        renderer.apply_changes(frame.changes)?;
+        Ok(Some(frame.time))
+    }
+}
+
+/// Converts records produced by a [`ChangeEncoder`] back into scene changes.
+///
+/// Fonts are resolved in the given font manager by their PostScript name, or their family and
+/// style if the exact font is not available. Ids are not changed.
+///
+/// [`ChangeEncoder`]: crate::ChangeEncoder
+#[derive(Debug)]
+pub struct ChangeDecoder {
+    fonts: FontManager,
+    font_ids: HashMap<u32, FontId>,
+    // Detail: Images are kept for the lifetime of the decoder, because the encoder may refer to
+    // them again.
+    images: HashMap<u32, ImageHandle>,
+}
+
+impl ChangeDecoder {
+    pub fn new(fonts: FontManager) -> Self {
+        Self {
+            fonts,
+            font_ids: HashMap::new(),
+            images: HashMap::new(),
+        }
+    }
+
+    pub fn decode(&mut self, changes: EncodedChanges) -> Result<Vec<SceneChange>> {
+        for font in changes.fonts {
+            let id = self.resolve_font(&font)?;
+            self.font_ids.insert(font.index, id);
+        }
+
+        for image in changes.images {
+            let data = ImageData::new(image.size, image.pixels).context("Decoding an image")?;
+            self.images.insert(image.index, data.into());
+        }
+
+        changes
+            .changes
+            .into_iter()
+            .map(|change| self.change(change))
+            .collect()
+    }
+
+    fn resolve_font(&self, font: &FontRecord) -> Result<FontId> {
+        let font_system = self.fonts.lock();
+        let db = font_system.db();
+
+        if let Some(face) = db
+            .faces()
+            .find(|face| face.post_script_name == font.post_script_name)
+        {
+            return Ok(face.id);
+        }
+
+        let families = match &font.family {
+            Some(family) => vec![Family::Name(family), Family::SansSerif],
+            None => vec![Family::SansSerif],
+        };
+        let query = fontdb::Query {
+            families: &families,
+            weight: Weight(font.weight),
+            style: if font.italic {
+                Style::Italic
+            } else {
+                Style::Normal
+            },
+            ..Default::default()
+        };
+
+        let id = db.query(&query).with_context(|| {
+            format!(
+                "No replacement for the recorded font `{}`",
+                font.post_script_name
+            )
+        })?;
+        log::warn!(
+            "Recorded font `{}` is not available, using a replacement",
+            font.post_script_name
+        );
+        Ok(id)
+    }
+
+    fn change(&self, change: ChangeRecord) -> Result<SceneChange> {
+        Ok(match change {
+            ChangeRecord::Transform(change) => {
+                scene_change(change, |transform| Ok(transform_from_record(transform)))?.into()
+            }
+            ChangeRecord::Location(change) => {
+                scene_change(change, |location| Ok(location_from_record(location)))?.into()
+            }
+            ChangeRecord::Visual(change) => {
+                scene_change(change, |visual| self.visual(visual))?.into()
+            }
+        })
+    }
+
+    fn visual(&self, visual: VisualRecord) -> Result<VisualRenderObj> {
+        let shapes: Vec<Shape> = visual
+            .shapes
+            .into_iter()
+            .map(|shape| self.shape(shape))
+            .collect::<Result<_>>()?;
+
+        Ok(VisualRenderObj {
+            location: Id::from_raw(visual.location),
+            decal_order: visual.decal_order.map(|order| order as usize),
+            clip_bounds: visual.clip_bounds.map(|(min, max)| Bounds::new(min, max)),
+            backdrop_blur: visual.backdrop_blur,
+            shapes: shapes.into(),
+        })
+    }
+
+    fn shape(&self, shape: ShapeRecord) -> Result<Shape> {
+        Ok(match shape {
+            ShapeRecord::Rect { rect, paint } => {
+                FilledRect::new(rect_from_scalars(rect), paint_from_record(paint)).into()
+            }
+            ShapeRecord::RoundRect {
+                rect,
+                corner_radius,
+                paint,
+            } => RoundRect::new(
+                rect_from_scalars(rect),
+                corner_radius,
+                paint_from_record(paint),
+            )
+            .into(),
+            ShapeRecord::Circle { rect, paint } => {
+                Circle::new(rect_from_scalars(rect), paint_from_record(paint)).into()
+            }
+            ShapeRecord::Ellipse { rect, paint } => {
+                Ellipse::new(rect_from_scalars(rect), paint_from_record(paint)).into()
+            }
+            ShapeRecord::BeveledRect {
+                rect,
+                chamfer,
+                corner_mask,
+                paint,
+            } => BeveledRect {
+                rect: rect_from_scalars(rect),
+                chamfer,
+                corner_mask,
+                paint: paint_from_record(paint),
+            }
+            .into(),
+            ShapeRecord::StrokeRect {
+                rect,
+                stroke,
+                color,
+            } => StrokeRect::new(rect_from_scalars(rect), Size::from(stroke), color).into(),
+            ShapeRecord::BoxShadow {
+                rect,
+                corner_radius,
+                offset,
+                blur_radius,
+                spread,
+                color,
+            } => BoxShadow::new(rect_from_scalars(rect), corner_radius, blur_radius, color)
+                .with_offset(offset)
+                .with_spread(spread)
+                .into(),
+            ShapeRecord::Image {
+                rect,
+                uv: [left, top, right, bottom],
+                sampling,
+                image,
+            } => {
+                let image = self
+                    .images
+                    .get(&image)
+                    .with_context(|| format!("Unknown image {image}"))?;
+                Image::new(rect_from_scalars(rect), image.clone())
+                    .with_uv((left, top), (right, bottom))
+                    .with_sampling(sampling)
+                    .into()
+            }
+            ShapeRecord::Path(path) => {
+                let segments: Vec<_> = path
+                    .segments
+                    .into_iter()
+                    .map(path_segment_from_record)
+                    .collect();
+                ShapePath {
+                    segments: segments.into(),
+                    fill: path.fill.map(|(rule, color)| PathFill { rule, color }),
+                    stroke: path.stroke.map(|stroke| PathStroke {
+                        width: stroke.width,
+                        join: stroke.join,
+                        cap: stroke.cap,
+                        color: stroke.color,
+                    }),
+                }
+                .into()
+            }
+            ShapeRecord::GlyphRun(run) => self.glyph_run(run)?.into(),
+        })
+    }
+
+    fn glyph_run(&self, run: GlyphRunRecord) -> Result<GlyphRun> {
+        let glyphs = run
+            .glyphs
+            .into_iter()
+            .map(|glyph| {
+                let font_id = *self
+                    .font_ids
+                    .get(&glyph.font)
+                    .with_context(|| format!("Unknown font {}", glyph.font))?;
+                let key = GlyphKey {
+                    font_id,
+                    glyph_id: glyph.glyph_id,
+                    font_size_bits: glyph.font_size_bits,
+                    weight: glyph.weight,
+                    style: glyph.style,
+                    stretch: glyph.stretch,
+                };
+                Ok(RunGlyph::new(glyph.pos, key))
+            })
+            .collect::<Result<_>>()?;
+
+        let (max_ascent, max_descent, width) = run.metrics;
+        Ok(GlyphRun::new(
+            Vector3::from_array(run.translation),
+            GlyphRunMetrics {
+                max_ascent,
+                max_descent,
+                width,
+            },
+            run.text_color,
+            run.text_weight,
+            glyphs,
+        )
+        .with_decorations(run.decorations))
+    }
+}
+
+fn scene_change<R, T>(
+    change: ObjectChange<R>,
+    value: impl FnOnce(R) -> Result<T>,
+) -> Result<Change<T>> {
+    Ok(match change {
+        ObjectChange::Create(id, record) => Change::Create(Id::from_raw(id), value(record)?),
+        ObjectChange::Update(id, record) => Change::Update(Id::from_raw(id), value(record)?),
+        ObjectChange::Delete(id) => Change::Delete(Id::from_raw(id)),
+    })
+}
+
+fn transform_from_record(transform: TransformRecord) -> Transform {
+    Transform {
+        translate: Vector3::from_array(transform.translate),
+        rotate: Quaternion::from_array(transform.rotate),
+        scale: transform.scale,
+    }
+}
+
+fn location_from_record(location: LocationRecord) -> LocationRenderObj {
+    LocationRenderObj {
+        parent: location.parent.map(Id::from_raw),
+        transform: Id::from_raw(location.transform),
+        alpha: location.alpha,
+        layer: location.layer,
+    }
+}
+
+fn paint_from_record(paint: PaintRecord) -> Paint {
+    let stops = |stops: Vec<(f32, massive_geometry::Color)>| {
+        stops.into_iter().map(GradientStop::from).collect()
+    };
+    match paint {
+        PaintRecord::Solid(color) => Paint::Solid(color),
+        PaintRecord::LinearGradient {
+            start,
+            end,
+            stops: s,
+        } => LinearGradient {
+            start,
+            end,
+            stops: stops(s),
+        }
+        .into(),
+        PaintRecord::RadialGradient {
+            center,
+            radius,
+            stops: s,
+        } => RadialGradient {
+            center,
+            radius,
+            stops: stops(s),
+        }
+        .into(),
+    }
+}
+
+fn path_segment_from_record(segment: PathSegmentRecord) -> PathSegment {
+    match segment {
+        PathSegmentRecord::MoveTo(to) => PathSegment::MoveTo(to),
+        PathSegmentRecord::LineTo(to) => PathSegment::LineTo(to),
+        PathSegmentRecord::QuadTo(control, to) => PathSegment::QuadTo(control, to),
+        PathSegmentRecord::CubicTo(control1, control2, to) => {
+            PathSegment::CubicTo(control1, control2, to)
+        }
+        PathSegmentRecord::Close => PathSegment::Close,
+    }
+}
+
+fn rect_from_scalars([left, top, right, bottom]: [f64; 4]) -> Rect {
+    Rect {
+        left,
+        top,
+        right,
+        bottom,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use massive_geometry::{Color, Rect};
+    use massive_scene::{Change, Id, SceneChange, VisualRenderObj};
+    use massive_shapes::{Image, ImageData, ImageHandle, Rect as FilledRect, Shape};
+
+    use super::Replayer;
+    use crate::{FontManager, RecordingWriter};
+
+    #[test]
+    fn recorded_changes_are_replayed() {
+        let path = std::env::temp_dir().join(format!("recording-{}.bin", std::process::id()));
+        let fonts = FontManager::bare("en-US");
+
+        let image = ImageHandle::new(ImageData::new((1, 1), vec![255, 0, 0, 255]).unwrap());
+        let shapes: Vec<Shape> = vec![
+            FilledRect::new(Rect::new((0.0, 0.0), (10.0, 10.0)), Color::BLACK).into(),
+            Image::new(Rect::new((10.0, 0.0), (10.0, 10.0)), image.clone()).into(),
+            Image::new(Rect::new((20.0, 0.0), (10.0, 10.0)), image).into(),
+        ];
+        let visual = VisualRenderObj {
+            location: Id::from_raw(1),
+            decal_order: None,
+            clip_bounds: None,
+            backdrop_blur: Some(4.0),
+            shapes: shapes.clone().into(),
+        };
+
+        let mut writer = RecordingWriter::create(&path, fonts.clone()).unwrap();
+        writer
+            .write_frame(&[Change::Create(Id::from_raw(2), visual).into()])
+            .unwrap();
+        writer
+            .write_frame(&[Change::<VisualRenderObj>::Delete(Id::from_raw(2)).into()])
+            .unwrap();
+        drop(writer);
+
+        let mut replayer = Replayer::open(&path, fonts).unwrap();
+        let created = replayer.next_frame().unwrap().unwrap();
+        let deleted = replayer.next_frame().unwrap().unwrap();
+        assert!(replayer.next_frame().unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+
+        let [SceneChange::Visual(Change::Create(id, replayed))] = created.changes.as_slice() else {
+            panic!("Unexpected changes: {:?}", created.changes);
+        };
+        assert_eq!(*id, Id::from_raw(2));
+        assert_eq!(replayed.location, Id::from_raw(1));
+        assert_eq!(replayed.backdrop_blur, Some(4.0));
+        assert_eq!(replayed.shapes[0], shapes[0]);
+
+        // The image is recorded once and shared again after replay.
+        let [Shape::Image(first), Shape::Image(second)] = &replayed.shapes[1..] else {
+            panic!("Expected two images: {:?}", replayed.shapes);
+        };
+        assert_eq!(first.image, second.image);
+        assert_eq!(first.image.pixels(), [255, 0, 0, 255]);
+
+        assert!(matches!(
+            deleted.changes.as_slice(),
+            [SceneChange::Visual(Change::Delete(..))]
+        ));
+    }
+}
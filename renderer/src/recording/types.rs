@@ -0,0 +1,276 @@
+//! The serialized form of scene changes.
+//!
+//! Ids are the raw scene ids. Fonts and images are stored once per recording and referenced by
+//! their index in the recording. A recording is a header followed by frames, each one a length
+//! prefixed postcard message.
+
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use massive_geometry::{Color, Point};
+use massive_shapes::{
+    FillRule, ImageSampling, LineCap, LineJoin, RunDecoration, TextStretch, TextStyle, TextWeight,
+};
+
+pub const RECORDING_VERSION: u32 = 3;
+
+/// The maximum size of a serialized message. Larger messages are rejected, so that a corrupt length
+/// prefix can not make the replayer allocate arbitrary amounts of memory. Frames carry the pixels
+/// of their new images, so this is generous.
+pub const MAX_MESSAGE_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u32,
+}
+
+/// The changes of one change set.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// The time since the recording started.
+    pub time: Duration,
+    pub changes: EncodedChanges,
+}
+
+/// Encoded scene changes.
+///
+/// Fonts and images are only included when they are referenced for the first time by the encoder
+/// that produced them, so the changes must be decoded in order by one decoder.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EncodedChanges {
+    /// Fonts that are referenced for the first time.
+    pub fonts: Vec<FontRecord>,
+    /// Images that are referenced for the first time.
+    pub images: Vec<ImageRecord>,
+    pub changes: Vec<ChangeRecord>,
+}
+
+impl EncodedChanges {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Identifies a font independently of the font database it was loaded into.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FontRecord {
+    pub index: u32,
+    pub post_script_name: String,
+    pub family: Option<String>,
+    pub weight: u16,
+    pub italic: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageRecord {
+    pub index: u32,
+    pub size: (u32, u32),
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ChangeRecord {
+    Transform(ObjectChange<TransformRecord>),
+    Location(ObjectChange<LocationRecord>),
+    Visual(ObjectChange<VisualRecord>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ObjectChange<T> {
+    Create(u32, T),
+    Update(u32, T),
+    Delete(u32),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransformRecord {
+    pub translate: [f64; 3],
+    /// Quaternion: x, y, z, w.
+    pub rotate: [f64; 4],
+    pub scale: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocationRecord {
+    pub parent: Option<u32>,
+    pub transform: u32,
+    pub alpha: f32,
+    pub layer: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VisualRecord {
+    pub location: u32,
+    pub decal_order: Option<u64>,
+    pub clip_bounds: Option<(Point, Point)>,
+    pub backdrop_blur: Option<f32>,
+    pub shapes: Vec<ShapeRecord>,
+}
+
+/// Rects are stored as left, top, right, bottom.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ShapeRecord {
+    Rect {
+        rect: [f64; 4],
+        paint: PaintRecord,
+    },
+    RoundRect {
+        rect: [f64; 4],
+        corner_radius: f32,
+        paint: PaintRecord,
+    },
+    Circle {
+        rect: [f64; 4],
+        paint: PaintRecord,
+    },
+    Ellipse {
+        rect: [f64; 4],
+        paint: PaintRecord,
+    },
+    BeveledRect {
+        rect: [f64; 4],
+        chamfer: f32,
+        corner_mask: u8,
+        paint: PaintRecord,
+    },
+    StrokeRect {
+        rect: [f64; 4],
+        stroke: (f64, f64),
+        color: Color,
+    },
+    BoxShadow {
+        rect: [f64; 4],
+        corner_radius: f32,
+        offset: (f32, f32),
+        blur_radius: f32,
+        spread: f32,
+        color: Color,
+    },
+    Image {
+        rect: [f64; 4],
+        uv: [f32; 4],
+        sampling: ImageSampling,
+        image: u32,
+    },
+    Path(PathRecord),
+    GlyphRun(GlyphRunRecord),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PaintRecord {
+    Solid(Color),
+    LinearGradient {
+        start: Point,
+        end: Point,
+        stops: Vec<(f32, Color)>,
+    },
+    RadialGradient {
+        center: Point,
+        radius: f64,
+        stops: Vec<(f32, Color)>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PathRecord {
+    pub segments: Vec<PathSegmentRecord>,
+    pub fill: Option<(FillRule, Color)>,
+    pub stroke: Option<PathStrokeRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PathSegmentRecord {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PathStrokeRecord {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    pub color: Color,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlyphRunRecord {
+    pub translation: [f64; 3],
+    /// Max ascent, max descent, width.
+    pub metrics: (u32, u32, u32),
+    pub text_color: Color,
+    pub text_weight: TextWeight,
+    pub glyphs: Vec<GlyphRecord>,
+    pub decorations: Vec<RunDecoration>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlyphRecord {
+    pub pos: (i32, i32),
+    pub font: u32,
+    pub glyph_id: u16,
+    pub font_size_bits: u32,
+    pub weight: TextWeight,
+    pub style: TextStyle,
+    pub stretch: TextStretch,
+}
+
+pub fn write_message(writer: &mut impl Write, message: &impl Serialize) -> Result<()> {
+    let bytes = postcard::to_stdvec(message).context("Serializing recording message")?;
+    if bytes.len() > MAX_MESSAGE_SIZE {
+        bail!(
+            "Recording message of {} bytes exceeds the maximum message size of {MAX_MESSAGE_SIZE} bytes",
+            bytes.len()
+        );
+    }
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Reads the next message, returns `None` at the end of the recording.
+///
+/// Messages larger than [`MAX_MESSAGE_SIZE`] are rejected with an error before they are read.
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> Result<Option<T>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e).context("Reading recording"),
+    }
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        bail!(
+            "Recording message of {length} bytes exceeds the maximum message size of {MAX_MESSAGE_SIZE} bytes"
+        );
+    }
+    let mut bytes = vec![0u8; length];
+    reader
+        .read_exact(&mut bytes)
+        .context("Reading recording message")?;
+    let message = postcard::from_bytes(&bytes).context("Deserializing recording message")?;
+    Ok(Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_messages_are_rejected() {
+        let mut stream = Vec::new();
+        write_message(&mut stream, &RECORDING_VERSION).unwrap();
+        let read: Option<u32> = read_message(&mut stream.as_slice()).unwrap();
+        assert_eq!(read, Some(RECORDING_VERSION));
+
+        let length = (MAX_MESSAGE_SIZE as u32 + 1).to_le_bytes();
+        assert!(read_message::<u32>(&mut length.as_slice()).is_err());
+    }
+}
@@ -63,6 +63,11 @@ impl RenderBatches {
         }
     }
 
+    /// The ids of all visuals.
+    pub fn ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.visuals_to_decal_order.keys().copied()
+    }
+
     pub fn render_visuals(&self) -> impl Iterator<Item = &RenderVisual> {
         self.normal_visuals.values().chain(
             self.decal_visuals_by_order
@@ -171,6 +176,7 @@ mod tests {
             location_id,
             decal_order,
             clip_bounds: None,
+            backdrop: None,
             batches: PipelineBatches::new(0),
         }
     }
@@ -7,27 +7,54 @@ const REQUIRED_ADAPTER_FEATURES: wgpu::Features = wgpu::Features::IMMEDIATES;
 pub struct RenderDevice {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    /// The format that is rendered into.
+    ///
+    /// Shaders output linear colors. If this is an sRGB format, they are encoded when written and
+    /// blending happens in linear space.
     pub surface_format: wgpu::TextureFormat,
+    /// The format of the surface's textures. If it differs from `surface_format`, they are viewed
+    /// in `surface_format`.
+    pub surface_texture_format: wgpu::TextureFormat,
     pub alpha_mode: wgpu::CompositeAlphaMode,
 }
 
 impl RenderDevice {
+    /// A device that renders to `surface`.
+    ///
+    /// With `srgb_surface`, an sRGB format is rendered into, so that linear colors are encoded for
+    /// display and blending is correct. If the surface does not support one, its textures are
+    /// viewed as sRGB. Without, the surface's preferred non-sRGB format is used, so that colors are
+    /// written as they are, which is only correct for surfaces that expect linear colors, like
+    /// floating point ones.
     pub async fn for_surface(
         instance: wgpu::Instance,
         surface: &wgpu::Surface<'static>,
+        srgb_surface: bool,
     ) -> Result<Self> {
         let adapter = get_adapter_for_surface(instance, surface).await?;
 
         info!("GPU Adapter backend: {:?}", adapter.get_info().backend);
         let surface_caps = surface.get_capabilities(&adapter);
-        // Don't use srgb now, colors are specified in linear rgb space.
-        let surface_format = *surface_caps
-            .formats
-            .iter()
-            .find(|f| !f.is_srgb())
-            .unwrap_or(&surface_caps.formats[0]);
+        let preferred_format = surface_caps.formats[0];
+        let (surface_format, surface_texture_format) = if srgb_surface {
+            match surface_caps.formats.iter().find(|f| f.is_srgb()) {
+                Some(format) => (*format, *format),
+                None => (preferred_format.add_srgb_suffix(), preferred_format),
+            }
+        } else {
+            let format = surface_caps
+                .formats
+                .iter()
+                .copied()
+                .find(|f| !f.is_srgb())
+                .unwrap_or(preferred_format);
+            (format, format)
+        };
 
         info!("- Surface format: {surface_format:?}");
+        if surface_texture_format != surface_format {
+            info!("- Surface texture format: {surface_texture_format:?}");
+        }
 
         info!(
             "- Available present modes: {:?}",
@@ -48,9 +75,35 @@ impl RenderDevice {
             device,
             queue,
             surface_format,
+            surface_texture_format,
             alpha_mode,
         })
     }
+
+    /// A device for rendering without a window, for example in CI.
+    ///
+    /// Prefers wgpu's software fallback adapter so that results are reproducible across machines,
+    /// and uses any available adapter (e.g. llvmpipe) if there is none.
+    pub async fn headless(instance: wgpu::Instance, format: wgpu::TextureFormat) -> Result<Self> {
+        let adapter = get_headless_adapter(instance).await?;
+
+        let info = adapter.get_info();
+        info!(
+            "Headless GPU Adapter: {} ({:?}, {:?})",
+            info.name, info.backend, info.device_type
+        );
+
+        let (device, queue) = get_device_and_queue_from_adapter(adapter).await?;
+
+        Ok(Self {
+            device,
+            queue,
+            surface_format: format,
+            surface_texture_format: format,
+            // Not used, there is no surface to composite with.
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+        })
+    }
 }
 
 async fn get_device_and_queue_from_adapter(
@@ -88,3 +141,45 @@ async fn get_adapter_for_surface(
 
     Ok(adapter)
 }
+
+async fn get_headless_adapter(instance: wgpu::Instance) -> Result<wgpu::Adapter> {
+    let fallback = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::None,
+            compatible_surface: None,
+            force_fallback_adapter: true,
+        })
+        .await;
+
+    let adapter = match fallback {
+        Ok(adapter) => adapter,
+        Err(e) => {
+            info!("No fallback adapter available ({e}), using any adapter");
+            instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::LowPower,
+                    compatible_surface: None,
+                    force_fallback_adapter: false,
+                })
+                .await
+                .context("GPU Adapter not found")?
+        }
+    };
+
+    if !adapter.features().contains(REQUIRED_ADAPTER_FEATURES) {
+        bail!("GPU Adapter must support {:?}", REQUIRED_ADAPTER_FEATURES);
+    }
+
+    Ok(adapter)
+}
+
+/// A headless device for tests that render.
+///
+/// These tests are ignored by default, so that a machine without an adapter can't pass them. Run
+/// them with `cargo test -- --ignored`.
+#[cfg(test)]
+pub(crate) fn test_device() -> RenderDevice {
+    let format = crate::HeadlessRenderer::FORMAT;
+    futures::executor::block_on(RenderDevice::headless(Default::default(), format))
+        .expect("Tests that render need a GPU or a software adapter")
+}
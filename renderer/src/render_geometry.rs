@@ -141,8 +141,8 @@ impl DerivedCache {
 
             let camera_projection = self.camera_projection.resolve(version, || {
                 let view_matrix = camera.ndc_camera_move();
-                let perspective_matrix = camera.perspective_matrix(CAMERA_CLIP_RANGE, surface_size);
-                perspective_matrix * view_matrix
+                let projection_matrix = camera.projection_matrix(CAMERA_CLIP_RANGE, surface_size);
+                projection_matrix * view_matrix
             });
 
             *camera_projection * *model_to_camera_to_ndc_matrix
@@ -1,11 +1,14 @@
-use std::time::Instant;
+use std::{cell::Cell, mem, time::Instant};
 
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
 use derive_more::Constructor;
 use itertools::Itertools;
 use log::{info, warn};
 use wgpu::{CurrentSurfaceTexture, PresentMode, StoreOp, SurfaceTexture};
 
+use crate::backdrop::{Backdrop, BackdropRenderer};
+use crate::layers::LayerRenderer;
+use crate::offscreen::{OffscreenTexture, RgbaImage};
 use crate::tools::PipelineVariant;
 use crate::{
     RenderDevice, Transaction, TransactionManager,
@@ -13,7 +16,7 @@ use crate::{
     pods::{AsBytes, ClipRect, Immediates, ToPod},
     render_batches::RenderBatches,
     scene::{LocationTransforms, Scene},
-    stats::MeasureSeries,
+    stats::{FrameStats, FrameStatsHistory, MeasureSeries},
     tools::{DEPTH_FORMAT, QuadIndexBuffer},
 };
 use massive_geometry::{Color, Matrix4, SizePx, Vector3};
@@ -30,17 +33,29 @@ pub struct PresentationMode {
 #[derive(Debug)]
 pub struct Renderer {
     pub device: RenderDevice,
-    surface: wgpu::Surface<'static>,
+    target: RenderTargetSurface,
     config: RendererConfig,
     pub surface_config: wgpu::SurfaceConfiguration,
     depth_buffer: DepthBuffer,
     pub measure_series: MeasureSeries,
+    /// The statistics of the most recently rendered frames.
+    pub frame_stats: FrameStatsHistory,
+    /// The statistics of the frame that is prepared but not yet rendered.
+    pending_stats: FrameStats,
+    /// Quads and triangles drawn while rendering the current frame.
+    draw_counts: Cell<(usize, usize)>,
 
     /// The pipelines for each batch producer.
     pipelines: Vec<wgpu::RenderPipeline>,
     decal_pipelines: Vec<wgpu::RenderPipeline>,
     quads_index_buffer: QuadIndexBuffer,
     max_quads_in_use: usize,
+    backdrop_renderer: BackdropRenderer,
+    /// Set if at least one visual has a backdrop blur, which needs the intermediate render passes.
+    backdrops_in_use: bool,
+    layer_renderer: LayerRenderer,
+    /// The locations of the visible layers, in the order they are composited.
+    layers: Vec<Id>,
 
     //
     // Scene and Cache updates
@@ -55,6 +70,13 @@ pub struct Renderer {
     batches: RenderBatches,
 }
 
+/// Where the renderer draws into.
+#[derive(Debug)]
+enum RenderTargetSurface {
+    Window(wgpu::Surface<'static>),
+    Offscreen(OffscreenTexture),
+}
+
 #[derive(Debug)]
 struct DepthBuffer {
     // In wgpu, keeping the TextureView is sufficient for lifetime/usage in render passes.
@@ -69,6 +91,7 @@ pub struct RenderVisual {
     pub location_id: Id,
     pub decal_order: Option<usize>,
     pub clip_bounds: Option<massive_geometry::Bounds>,
+    pub backdrop: Option<Backdrop>,
     pub batches: PipelineBatches,
 }
 
@@ -91,7 +114,9 @@ impl PipelineBatches {
     pub fn max_quads(&self) -> usize {
         self.batches
             .iter()
-            .filter_map(|b| b.as_ref().map(|rb| rb.count))
+            .flatten()
+            .filter(|rb| rb.index_buffer.is_none())
+            .map(|rb| rb.count)
             .max()
             .unwrap_or_default()
     }
@@ -102,7 +127,12 @@ pub struct RenderBatch {
     pub fs_bind_group: Option<wgpu::BindGroup>,
     /// Think of making count and vertex_buffer optional. This would remove all Option<RenderBatch>.
     pub vertex_buffer: wgpu::Buffer,
+    /// The number of quads, or the number of indices if the batch has its own index buffer.
     pub count: usize,
+    /// An index buffer (`u32` indices) for batches that are not made of quads.
+    ///
+    /// If `None`, the batch is indexed by the shared quad index buffer.
+    pub index_buffer: Option<wgpu::Buffer>,
 }
 
 /// The context provided to `prepare()` middleware functions.
@@ -123,6 +153,35 @@ impl Renderer {
         surface: wgpu::Surface<'static>,
         initial_size: SizePx,
         config: RendererConfig,
+    ) -> Self {
+        Self::with_target(
+            device,
+            RenderTargetSurface::Window(surface),
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+            initial_size,
+            config,
+        )
+    }
+
+    /// Creates a renderer that renders into a texture, see [`Self::render_offscreen`].
+    pub fn new_offscreen(device: RenderDevice, size: SizePx, config: RendererConfig) -> Self {
+        let size = size.max((1, 1).into());
+        let texture = OffscreenTexture::new(&device.device, device.surface_format, size);
+        Self::with_target(
+            device,
+            RenderTargetSurface::Offscreen(texture),
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            size,
+            config,
+        )
+    }
+
+    fn with_target(
+        device: RenderDevice,
+        target: RenderTargetSurface,
+        usage: wgpu::TextureUsages,
+        initial_size: SizePx,
+        config: RendererConfig,
     ) -> Self {
         let pipelines = config.create_pipelines(&device.device, PipelineVariant::Standard);
         let decal_pipelines = config.create_pipelines(&device.device, PipelineVariant::Decal);
@@ -132,8 +191,8 @@ impl Renderer {
 
         // Architecture: I think we can re-create this every time the surface needs reconfiguration.
         let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: device.surface_format,
+            usage,
+            format: device.surface_texture_format,
             width: initial_size.width,
             height: initial_size.height,
             // 20250721: Since the time we are rendering asynchronously, not bound to the main
@@ -144,23 +203,31 @@ impl Renderer {
             // for animations). Also the "wobbly" resizing appears again with VSync.
             present_mode: PresentMode::AutoNoVsync,
             alpha_mode: device.alpha_mode,
-            view_formats: vec![],
+            view_formats: if device.surface_texture_format != device.surface_format {
+                vec![device.surface_format]
+            } else {
+                vec![]
+            },
             desired_maximum_frame_latency: DEFAULT_MAXIMUM_FRAME_LATENCY,
         };
 
-        surface.configure(&device.device, &surface_config);
         let depth_buffer = Self::create_depth_buffer(
             &device.device,
             (surface_config.width, surface_config.height),
         );
 
         let index_buffer = QuadIndexBuffer::new(&device.device);
+        let backdrop_renderer = BackdropRenderer::new(&device.device, device.surface_format);
+        let layer_renderer = LayerRenderer::new(&device.device, device.surface_format);
 
         let mut renderer = Self {
             config,
             device,
             measure_series: Default::default(),
-            surface,
+            frame_stats: Default::default(),
+            pending_stats: Default::default(),
+            draw_counts: Default::default(),
+            target,
             surface_config,
             depth_buffer,
             pipelines,
@@ -168,6 +235,10 @@ impl Renderer {
 
             quads_index_buffer: index_buffer,
             max_quads_in_use: 0,
+            backdrop_renderer,
+            backdrops_in_use: false,
+            layer_renderer,
+            layers: Vec::new(),
 
             transaction_manager: Default::default(),
             scene: Default::default(),
@@ -215,18 +286,60 @@ impl Renderer {
     /// for the next VSync. If we run prepare steps before, we can utilize CPU time more.
     /// **Update:** Yes, but this would delay output for 16ms, so prepare now runs after that.
     pub fn prepare(&mut self) -> Result<()> {
+        let prepare_start_time = Instant::now();
+
         self.prepare_batches()?;
+        self.collect_producer_stats();
 
         self.prepare_index_buffer();
 
+        self.backdrops_in_use = self.batches.render_visuals().any(|v| v.backdrop.is_some());
+
         {
             let transaction = self.transaction_manager.current_transaction();
             self.prepare_matrices(&transaction);
         }
 
+        self.prepare_layers();
+
+        self.pending_stats.prepare_time += prepare_start_time.elapsed();
         Ok(())
     }
 
+    /// Ask all batch producers for their statistics.
+    ///
+    /// If prepare runs more than once before a frame is rendered, the atlas grow, eviction, and
+    /// compaction events are accumulated.
+    fn collect_producer_stats(&mut self) {
+        let stats = &mut self.pending_stats;
+        let previous_atlases = mem::take(&mut stats.atlases);
+        for producer in self.config.batch_producers.iter_mut() {
+            producer.producer.collect_stats(stats);
+        }
+        for atlas in &mut stats.atlases {
+            if let Some(previous) = previous_atlases.iter().find(|p| p.name == atlas.name) {
+                atlas.grow_events += previous.grow_events;
+                atlas.evictions += previous.evictions;
+                atlas.compactions += previous.compactions;
+            }
+        }
+    }
+
+    /// Collect the layers that need to be rendered. Depends on the resolved locations.
+    fn prepare_layers(&mut self) {
+        let locations = &self.visual_locations;
+        self.layers.clear();
+        self.layers.extend(
+            self.batches
+                .render_visuals()
+                .filter_map(|v| locations.get_layer(v.location_id))
+                .unique()
+                .filter(|layer| locations.get_alpha(*layer) > 0.0)
+                // Composite in a stable order.
+                .sorted_by_key(|layer| layer.to_usize()),
+        );
+    }
+
     fn prepare_index_buffer(&mut self) {
         // Performance: Compute max_quads them incrementally, going through all batches might be
         // expensive.
@@ -264,7 +377,21 @@ impl Renderer {
             device: &self.device.device,
             queue: &self.device.queue,
         };
+        let mut invalidated = false;
+        for producer in self.config.batch_producers.iter_mut() {
+            invalidated |= producer.producer.begin_frame(context);
+        }
+        if invalidated {
+            // Batches may refer to textures that were replaced, produce them again.
+            for id in self.batches.ids() {
+                self.changed_visuals.add(id);
+            }
+        }
+
+        let stats = &mut self.pending_stats;
+        stats.batches_produced.resize(self.pipelines.len(), 0);
         for id in self.changed_visuals.take_all() {
+            stats.visuals_changed += 1;
             if let Some(v) = &visuals[id] {
                 Self::visual_updated(
                     id,
@@ -273,6 +400,7 @@ impl Renderer {
                     &self.pipelines,
                     context,
                     &mut self.batches,
+                    stats,
                 )?;
             } else {
                 self.batches.remove(id);
@@ -289,6 +417,7 @@ impl Renderer {
         pipelines: &[wgpu::RenderPipeline],
         context: &PreparationContext,
         render_batches: &mut RenderBatches,
+        stats: &mut FrameStats,
     ) -> Result<()> {
         // Architecture: Define a new type PipelineTable.
         let all_pipelines_count = pipelines.len();
@@ -303,12 +432,19 @@ impl Renderer {
                 .produce_batches(context, &visual.shapes, expected_batches)?;
         }
 
+        for (produced, batch) in stats.batches_produced.iter_mut().zip(&batches.batches) {
+            *produced += batch.is_some() as usize;
+        }
+
         render_batches.insert(
             id,
             RenderVisual {
                 location_id: visual.location,
                 decal_order: visual.decal_order,
                 clip_bounds: visual.clip_bounds,
+                backdrop: visual.backdrop_blur.and_then(|blur_radius| {
+                    Backdrop::new(context.device, blur_radius, &visual.shapes)
+                }),
                 batches,
             },
         );
@@ -322,7 +458,10 @@ impl Renderer {
     /// This is `&mut self`, because it might call into [`Self::reconfigure_surface`] when the
     /// surface is lost.
     pub fn get_current_texture(&mut self) -> Result<Option<SurfaceTexture>> {
-        match self.surface.get_current_texture() {
+        let RenderTargetSurface::Window(surface) = &self.target else {
+            bail!("Offscreen renderers have no surface texture, use `render_offscreen()`");
+        };
+        match surface.get_current_texture() {
             CurrentSurfaceTexture::Success(texture)
             | CurrentSurfaceTexture::Suboptimal(texture) => Ok(Some(texture)),
             // During fullscreen/minimize/visibility transitions, surfaces can temporarily become
@@ -332,7 +471,10 @@ impl Renderer {
                 // Try to reconfigure and re-acquire once when the surface is outdated or lost.
                 warn!("Surface texture status indicates reconfigure is needed, retrying...");
                 self.reconfigure_surface();
-                match self.surface.get_current_texture() {
+                let RenderTargetSurface::Window(surface) = &self.target else {
+                    unreachable!("Render target changed while reconfiguring");
+                };
+                match surface.get_current_texture() {
                     CurrentSurfaceTexture::Success(texture)
                     | CurrentSurfaceTexture::Suboptimal(texture) => Ok(Some(texture)),
                     CurrentSurfaceTexture::Occluded | CurrentSurfaceTexture::Timeout => Ok(None),
@@ -355,9 +497,39 @@ impl Renderer {
     ) {
         let surface_view = surface_texture
             .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+            .create_view(&wgpu::TextureViewDescriptor {
+                format: Some(self.device.surface_format),
+                ..Default::default()
+            });
+
+        self.render(view_projection_matrix, &surface_view);
+
+        let present_start_time = Instant::now();
+        surface_texture.present();
+        if let Some(stats) = self.frame_stats.latest_mut() {
+            stats.present_time = present_start_time.elapsed();
+        }
+    }
 
+    /// Render into the offscreen texture and read back the result.
+    ///
+    /// Blocks until the GPU has finished rendering.
+    pub fn render_offscreen(&mut self, view_projection_matrix: &Matrix4) -> Result<RgbaImage> {
+        let RenderTargetSurface::Offscreen(texture) = &self.target else {
+            bail!("Renderer does not render offscreen");
+        };
+        let view = texture.view.clone();
+        self.render(view_projection_matrix, &view);
+
+        let RenderTargetSurface::Offscreen(texture) = &self.target else {
+            unreachable!("Render target changed while rendering");
+        };
+        texture.read(&self.device)
+    }
+
+    fn render(&mut self, view_projection_matrix: &Matrix4, target_view: &wgpu::TextureView) {
         let render_start_time = Instant::now();
+        self.draw_counts.set((0, 0));
 
         let command_buffer = {
             let mut encoder =
@@ -367,44 +539,31 @@ impl Renderer {
                         label: Some("Render Encoder"),
                     });
 
-            {
-                let load_op = if let Some(color) = self.config.background_color {
-                    let (r, g, b, a) = color.into();
-                    wgpu::LoadOp::Clear(wgpu::Color {
-                        r: r as _,
-                        g: g as _,
-                        b: b as _,
-                        a: a as _,
-                    })
-                } else {
-                    wgpu::LoadOp::Load
-                };
+            self.render_layers(&mut encoder, view_projection_matrix);
 
-                let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("Render Pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &surface_view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: load_op,
-                            store: StoreOp::Store,
-                        },
-                        depth_slice: None,
-                    })],
-                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                        view: &self.depth_buffer.view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
-                            // Performance: We never sample/reuse depth after this pass.
-                            // Keep = Clear(1.0) every frame, so preserving depth contents is
-                            // unnecessary bandwidth work. Discard enables cheaper depth handling
-                            // on tile-based and deferred memory architectures.
-                            store: StoreOp::Discard,
-                        }),
-                        stencil_ops: None,
-                    }),
-                    ..Default::default()
-                });
+            let load_op = self.background_load_op();
+            if self.backdrops_in_use {
+                self.render_with_backdrops(
+                    &mut encoder,
+                    view_projection_matrix,
+                    target_view,
+                    load_op,
+                );
+            } else {
+                let render_pass = begin_render_pass(
+                    &mut encoder,
+                    target_view,
+                    load_op,
+                    &self.depth_buffer.view,
+                    wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        // Performance: We never sample/reuse depth after this pass.
+                        // Keep = Clear(1.0) every frame, so preserving depth contents is
+                        // unnecessary bandwidth work. Discard enables cheaper depth handling
+                        // on tile-based and deferred memory architectures.
+                        store: StoreOp::Discard,
+                    },
+                );
 
                 // DI: There is a lot of view_projection stuff going on.
                 let render_context = &mut RenderContext {
@@ -412,31 +571,7 @@ impl Renderer {
                     view_projection_matrix: *view_projection_matrix,
                 };
 
-                // Set the shared index buffer for all quad renderers.
-                if self.max_quads_in_use > 0 {
-                    self.quads_index_buffer
-                        .set(&mut render_context.pass, self.max_quads_in_use);
-                }
-
-                for (i, pipeline) in self.pipelines.iter().enumerate() {
-                    self.render_pipeline_batches(
-                        self.batches.normal_visuals.values(),
-                        pipeline,
-                        |b| b.batches[i].as_ref(),
-                        render_context,
-                    );
-                }
-
-                for visuals in self.batches.decal_visuals_by_order.values() {
-                    for (i, pipeline) in self.decal_pipelines.iter().enumerate() {
-                        self.render_pipeline_batches(
-                            visuals.values(),
-                            pipeline,
-                            |b| b.batches[i].as_ref(),
-                            render_context,
-                        );
-                    }
-                }
+                self.render_target_visuals(render_context, |v| !self.is_in_layer(v));
             }
             encoder.finish()
         };
@@ -456,7 +591,249 @@ impl Renderer {
             self.measure_series.add_sample(duration_passed);
         }
 
-        surface_texture.present();
+        let (quads_drawn, triangles_drawn) = self.draw_counts.get();
+        self.frame_stats.push(FrameStats {
+            quads_drawn,
+            triangles_drawn,
+            render_time: render_start_time.elapsed(),
+            ..mem::take(&mut self.pending_stats)
+        });
+    }
+
+    fn background_load_op(&self) -> wgpu::LoadOp<wgpu::Color> {
+        if let Some(color) = self.config.background_color {
+            let (r, g, b, a) = color.into();
+            wgpu::LoadOp::Clear(wgpu::Color {
+                r: r as _,
+                g: g as _,
+                b: b as _,
+                a: a as _,
+            })
+        } else {
+            wgpu::LoadOp::Load
+        }
+    }
+
+    /// Render the normal visuals and then the decal visuals by order.
+    fn render_visuals(
+        &self,
+        render_context: &mut RenderContext,
+        filter: impl Fn(&RenderVisual) -> bool,
+    ) {
+        // Set the shared index buffer for all quad renderers.
+        if self.max_quads_in_use > 0 {
+            self.quads_index_buffer
+                .set(&mut render_context.pass, self.max_quads_in_use);
+        }
+
+        self.render_normal_visuals(render_context, &filter);
+        self.render_decal_visuals(render_context, &filter);
+    }
+
+    /// Render the visuals into the target and composite the layers.
+    ///
+    /// Layers are composited after the normal visuals and before the decal visuals. A layer
+    /// contains its own decals, and is occluded by its depth like normal visuals are. Decals
+    /// outside of layers are drawn over them, regardless of the decal order of the decals inside
+    /// the layers.
+    fn render_target_visuals(
+        &self,
+        render_context: &mut RenderContext,
+        filter: impl Fn(&RenderVisual) -> bool,
+    ) {
+        // Set the shared index buffer for all quad renderers.
+        if self.max_quads_in_use > 0 {
+            self.quads_index_buffer
+                .set(&mut render_context.pass, self.max_quads_in_use);
+        }
+
+        self.render_normal_visuals(render_context, &filter);
+        self.composite_layers(render_context);
+        self.render_decal_visuals(render_context, &filter);
+    }
+
+    fn render_normal_visuals(
+        &self,
+        render_context: &mut RenderContext,
+        filter: impl Fn(&RenderVisual) -> bool,
+    ) {
+        for (i, pipeline) in self.pipelines.iter().enumerate() {
+            self.render_pipeline_batches(
+                self.batches.normal_visuals.values().filter(|&v| filter(v)),
+                pipeline,
+                |b| b.batches[i].as_ref(),
+                render_context,
+            );
+        }
+    }
+
+    fn render_decal_visuals(
+        &self,
+        render_context: &mut RenderContext,
+        filter: impl Fn(&RenderVisual) -> bool,
+    ) {
+        for visuals in self.batches.decal_visuals_by_order.values() {
+            for (i, pipeline) in self.decal_pipelines.iter().enumerate() {
+                self.render_pipeline_batches(
+                    visuals.values().filter(|&v| filter(v)),
+                    pipeline,
+                    |b| b.batches[i].as_ref(),
+                    render_context,
+                );
+            }
+        }
+    }
+
+    /// Render the visuals of every layer into its own texture.
+    fn render_layers(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view_projection_matrix: &Matrix4,
+    ) {
+        if self.layers.is_empty() {
+            return;
+        }
+        self.layer_renderer.prepare_targets(
+            &self.device.device,
+            self.surface_size(),
+            self.layers.len(),
+        );
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            let (color_view, depth_view) = self.layer_renderer.target_views(index);
+            let render_pass = begin_render_pass(
+                encoder,
+                color_view,
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                depth_view,
+                wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    // Used to composite the layer.
+                    store: StoreOp::Store,
+                },
+            );
+            let render_context = &mut RenderContext {
+                pass: render_pass,
+                view_projection_matrix: *view_projection_matrix,
+            };
+            // Performance: This goes through all visuals for every layer.
+            self.render_visuals(render_context, |v| {
+                self.visual_locations.get_layer(v.location_id) == Some(*layer)
+            });
+        }
+    }
+
+    fn composite_layers(&self, render_context: &mut RenderContext) {
+        for (index, layer) in self.layers.iter().enumerate() {
+            let alpha = self.visual_locations.get_alpha(*layer);
+            self.layer_renderer
+                .composite(&mut render_context.pass, index, alpha);
+        }
+    }
+
+    fn is_in_layer(&self, visual: &RenderVisual) -> bool {
+        self.visual_locations
+            .get_layer(visual.location_id)
+            .is_some()
+    }
+
+    /// Render into the intermediate scene texture, so that the visuals with a backdrop blur can
+    /// sample what is rendered behind them.
+    ///
+    /// First, all visuals without a backdrop are rendered. Then, for every visual with a backdrop,
+    /// the scene texture is blurred and the visual is rendered on top of its blurred backdrop.
+    /// Finally the scene texture is copied to the target.
+    fn render_with_backdrops(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view_projection_matrix: &Matrix4,
+        target_view: &wgpu::TextureView,
+        load_op: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        self.backdrop_renderer
+            .prepare_textures(&self.device.device, self.surface_size());
+
+        let scene_load_op = match load_op {
+            wgpu::LoadOp::Load => wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            clear => clear,
+        };
+
+        {
+            let render_pass = begin_render_pass(
+                encoder,
+                self.backdrop_renderer.scene_view(),
+                scene_load_op,
+                &self.depth_buffer.view,
+                wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: StoreOp::Store,
+                },
+            );
+            let render_context = &mut RenderContext {
+                pass: render_pass,
+                view_projection_matrix: *view_projection_matrix,
+            };
+            self.render_target_visuals(render_context, |v| {
+                v.backdrop.is_none() && !self.is_in_layer(v)
+            });
+        }
+
+        for visual in self.batches.render_visuals() {
+            // Inside a layer, the backdrop is ignored.
+            let Some(backdrop) = &visual.backdrop else {
+                continue;
+            };
+            if self.is_in_layer(visual) {
+                continue;
+            }
+            if self.visual_locations.get_alpha(visual.location_id) == 0.0 {
+                continue;
+            }
+
+            self.backdrop_renderer.blur(encoder, backdrop.sigma);
+
+            let render_pass = begin_render_pass(
+                encoder,
+                self.backdrop_renderer.scene_view(),
+                wgpu::LoadOp::Load,
+                &self.depth_buffer.view,
+                wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: StoreOp::Store,
+                },
+            );
+            let render_context = &mut RenderContext {
+                pass: render_pass,
+                view_projection_matrix: *view_projection_matrix,
+            };
+
+            let (variant, pipelines) = match visual.decal_order {
+                None => (PipelineVariant::Standard, &self.pipelines),
+                Some(_) => (PipelineVariant::Decal, &self.decal_pipelines),
+            };
+
+            render_context
+                .pass
+                .set_pipeline(self.backdrop_renderer.backdrop_pipeline(variant));
+            self.set_visual_immediates(visual, render_context);
+            self.backdrop_renderer
+                .render_backdrop(&mut render_context.pass, backdrop);
+
+            if self.max_quads_in_use > 0 {
+                self.quads_index_buffer
+                    .set(&mut render_context.pass, self.max_quads_in_use);
+            }
+            for (i, pipeline) in pipelines.iter().enumerate() {
+                self.render_pipeline_batches(
+                    [visual].into_iter(),
+                    pipeline,
+                    |b| b.batches[i].as_ref(),
+                    render_context,
+                );
+            }
+        }
+
+        self.backdrop_renderer.blit(encoder, target_view, load_op);
     }
 
     /// Pick up one specific pipeline batch from every visual and render it.
@@ -475,7 +852,7 @@ impl Renderer {
                 continue;
             };
 
-            let alpha = locations.get_alpha(visual.location_id);
+            let alpha = locations.get_alpha_in_layer(visual.location_id);
             if alpha == 0.0 {
                 continue;
             }
@@ -485,35 +862,56 @@ impl Renderer {
                 pipeline_set = true;
             }
 
-            // Architecture: We may go multiple times over the same visual and compute the
-            //   final matrix, because it renders to different pipelines. Perhaps we need a derived /
-            //   lazy table here.
-            let matrix = context.view_projection_matrix * *locations.get_matrix(visual.location_id);
+            self.set_visual_immediates(visual, context);
 
             let pass = &mut context.pass;
-
-            let clip_rect = visual.clip_bounds.map_or(ClipRect::NONE, ClipRect::from);
-            let push_constants = Immediates {
-                view_model: matrix.to_pod(),
-                clip_rect,
-                alpha,
-                _padding: [0.0; 3],
-            };
-            pass.set_immediates(0, push_constants.as_bytes());
             // Performance: This test needs only done once per pipeline.
             if let Some(bg) = &batch.fs_bind_group {
                 pass.set_bind_group(0, bg, &[]);
             }
             pass.set_vertex_buffer(0, batch.vertex_buffer.slice(..));
 
-            pass.draw_indexed(
-                0..(batch.count * QuadIndexBuffer::INDICES_PER_QUAD) as u32,
-                0,
-                0..1,
-            )
+            let (quads, triangles) = self.draw_counts.get();
+            self.draw_counts.set(match batch.index_buffer {
+                Some(_) => (quads, triangles + batch.count / 3),
+                None => (quads + batch.count, triangles),
+            });
+
+            match &batch.index_buffer {
+                Some(index_buffer) => {
+                    pass.set_index_buffer(index_buffer.slice(..), QuadIndexBuffer::INDEX_FORMAT);
+                    pass.draw_indexed(0..batch.count as u32, 0, 0..1);
+                    // Restore the shared index buffer for the quad batches that follow.
+                    if self.max_quads_in_use > 0 {
+                        self.quads_index_buffer.set(pass, self.max_quads_in_use);
+                    }
+                }
+                None => pass.draw_indexed(
+                    0..(batch.count * QuadIndexBuffer::INDICES_PER_QUAD) as u32,
+                    0,
+                    0..1,
+                ),
+            }
         }
     }
 
+    fn set_visual_immediates(&self, visual: &RenderVisual, context: &mut RenderContext) {
+        let locations = &self.visual_locations;
+        // Architecture: We may go multiple times over the same visual and compute the
+        //   final matrix, because it renders to different pipelines. Perhaps we need a derived /
+        //   lazy table here.
+        let matrix = context.view_projection_matrix * *locations.get_matrix(visual.location_id);
+
+        let clip_rect = visual.clip_bounds.map_or(ClipRect::NONE, ClipRect::from);
+        let push_constants = Immediates {
+            view_model: matrix.to_pod(),
+            clip_rect,
+            alpha: locations.get_alpha_in_layer(visual.location_id),
+            _padding: [0.0; 3],
+        };
+        context.pass.set_immediates(0, push_constants.as_bytes());
+    }
+
     /// A Matrix that projects from normalized view coordinates -1.0 to 1.0 (3D, all axis, Z from 0.1
     /// to 100) to 2D coordinates.
     ///
@@ -596,8 +994,21 @@ impl Renderer {
             self.surface_config.present_mode,
             self.surface_config.desired_maximum_frame_latency
         );
-        self.surface
-            .configure(&self.device.device, &self.surface_config);
+        let size = self.surface_size();
+        match &mut self.target {
+            RenderTargetSurface::Window(surface) => {
+                surface.configure(&self.device.device, &self.surface_config)
+            }
+            RenderTargetSurface::Offscreen(texture) => {
+                if texture.size() != size {
+                    *texture = OffscreenTexture::new(
+                        &self.device.device,
+                        self.surface_config.format,
+                        size,
+                    );
+                }
+            }
+        }
 
         let target_depth_size = (self.surface_config.width, self.surface_config.height);
         let current_depth_size = self.depth_buffer._texture.size();
@@ -635,3 +1046,30 @@ impl Renderer {
         }
     }
 }
+
+fn begin_render_pass<'encoder>(
+    encoder: &'encoder mut wgpu::CommandEncoder,
+    color_view: &wgpu::TextureView,
+    color_load: wgpu::LoadOp<wgpu::Color>,
+    depth_view: &wgpu::TextureView,
+    depth_ops: wgpu::Operations<f32>,
+) -> wgpu::RenderPass<'encoder> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: color_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: color_load,
+                store: StoreOp::Store,
+            },
+            depth_slice: None,
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: depth_view,
+            depth_ops: Some(depth_ops),
+            stencil_ops: None,
+        }),
+        ..Default::default()
+    })
+}
@@ -47,7 +47,7 @@ where
     }
 
     // Compute a new value and store it.
-    let new_value = Resolver::compute(shared_storage, computed_storage, source);
+    let new_value = Resolver::compute(shared_storage, computed_storage, id, source);
     *Resolver::computed_mut(computed_storage, id) = Computed {
         validated_at: head_version,
         versioned: Versioned::new(new_value, max_deps_version),
@@ -81,6 +81,7 @@ pub trait DependencyResolver {
     fn compute(
         shared: &Self::SourceStorage,
         computed: &Self::ComputedStorage,
+        id: Id,
         source: &Self::Source,
     ) -> Self::Computed;
 
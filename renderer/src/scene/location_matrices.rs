@@ -21,7 +21,12 @@ pub struct LocationTransforms {
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct ResolvedLocation {
     transform: Transform,
+    /// The alpha of this location, multiplied with the alpha of all its ancestors.
     alpha: f32,
+    /// The outermost layer this location is rendered into.
+    layer: Option<Id>,
+    /// The alpha relative to the layer, or `alpha` if the location is not inside a layer.
+    alpha_in_layer: f32,
 }
 
 impl LocationTransforms {
@@ -66,6 +71,17 @@ impl LocationTransforms {
     pub fn get_alpha(&self, location_id: Id) -> f32 {
         self.location_properties[location_id].alpha
     }
+
+    /// The alpha visuals at this location are rendered with. Inside a layer, this excludes the
+    /// alpha the layer is composited with.
+    pub fn get_alpha_in_layer(&self, location_id: Id) -> f32 {
+        self.location_properties[location_id].alpha_in_layer
+    }
+
+    /// The location of the outermost layer the location is rendered into.
+    pub fn get_layer(&self, location_id: Id) -> Option<Id> {
+        self.location_properties[location_id].layer
+    }
 }
 
 // Quick hack to prevent the use of Option<Versioned>
@@ -123,24 +139,38 @@ impl DependencyResolver for VisualLocation {
     fn compute(
         scene: &Scene,
         caches: &LocationTransforms,
+        id: Id,
         source: &Self::Source,
     ) -> Self::Computed {
         let (parent_id, transform_id) = (source.parent, source.transform);
         let local_transform = &*scene.transforms[transform_id];
         let local_alpha = source.alpha;
-        parent_id.map_or_else(
-            || ResolvedLocation {
-                transform: *local_transform,
-                alpha: local_alpha,
-            },
-            |parent_id| {
+        let (transform, alpha, parent_layer, parent_alpha_in_layer) = match parent_id {
+            None => (*local_transform, local_alpha, None, 1.0),
+            Some(parent_id) => {
                 let parent = &caches.location_properties[parent_id];
-                ResolvedLocation {
-                    transform: parent.transform * *local_transform,
-                    alpha: parent.alpha * local_alpha,
-                }
-            },
-        )
+                (
+                    parent.transform * *local_transform,
+                    parent.alpha * local_alpha,
+                    parent.layer,
+                    parent.alpha_in_layer,
+                )
+            }
+        };
+
+        let (layer, alpha_in_layer) = match parent_layer {
+            Some(layer) => (Some(layer), parent_alpha_in_layer * local_alpha),
+            // This location's alpha is applied when the layer is composited.
+            None if source.layer => (Some(id), 1.0),
+            None => (None, alpha),
+        };
+
+        ResolvedLocation {
+            transform,
+            alpha,
+            layer,
+            alpha_in_layer,
+        }
     }
 }
 
@@ -150,6 +180,8 @@ impl Default for Versioned<ResolvedLocation> {
             ResolvedLocation {
                 transform: Transform::default(),
                 alpha: 1.0,
+                layer: None,
+                alpha_in_layer: 1.0,
             },
             0,
         )
@@ -205,6 +237,7 @@ mod tests {
                     parent: None,
                     transform: parent_transform_id,
                     alpha: 0.5,
+                    layer: false,
                 },
             )),
             &transaction,
@@ -216,6 +249,7 @@ mod tests {
                     parent: Some(parent_location_id),
                     transform: child_transform_id,
                     alpha: 0.25,
+                    layer: false,
                 },
             )),
             &transaction,
@@ -231,6 +265,50 @@ mod tests {
         assert_eq!(locations.get_alpha(child_location_id), 0.125);
     }
 
+    #[test]
+    fn layer_content_alpha_is_relative_to_the_layer() {
+        let mut transaction_manager = TransactionManager::default();
+        let mut scene = Scene::default();
+        let transform_id = new_transform_id();
+        let [root_id, layer_id, child_id] = [(); 3].map(|_| new_location_id());
+        let transaction = transaction_manager.new_transaction();
+
+        scene.apply(
+            &SceneChange::Transform(Change::Create(transform_id, Transform::IDENTITY)),
+            &transaction,
+        );
+        for (id, parent, alpha, layer) in [
+            (root_id, None, 0.5, false),
+            (layer_id, Some(root_id), 0.5, true),
+            (child_id, Some(layer_id), 0.5, false),
+        ] {
+            scene.apply(
+                &SceneChange::Location(Change::Create(
+                    id,
+                    LocationRenderObj {
+                        parent,
+                        transform: transform_id,
+                        alpha,
+                        layer,
+                    },
+                )),
+                &transaction,
+            );
+        }
+
+        let mut locations = LocationTransforms::default();
+        locations.resolve_locations_and_matrices(&scene, &transaction, [child_id].into_iter());
+
+        assert_eq!(locations.get_layer(root_id), None);
+        assert_eq!(locations.get_alpha_in_layer(root_id), 0.5);
+        assert_eq!(locations.get_layer(layer_id), Some(layer_id));
+        assert_eq!(locations.get_alpha(layer_id), 0.25);
+        assert_eq!(locations.get_alpha_in_layer(layer_id), 1.0);
+        assert_eq!(locations.get_layer(child_id), Some(layer_id));
+        assert_eq!(locations.get_alpha(child_id), 0.125);
+        assert_eq!(locations.get_alpha_in_layer(child_id), 0.5);
+    }
+
     #[test]
     fn transform_and_alpha_changes_invalidate_resolved_location() {
         let mut transaction_manager = TransactionManager::default();
@@ -252,6 +330,7 @@ mod tests {
                     parent: None,
                     transform: transform_id,
                     alpha: 0.25,
+                    layer: false,
                 },
             )),
             &transaction,
@@ -279,6 +358,7 @@ mod tests {
                     parent: None,
                     transform: transform_id,
                     alpha: 0.75,
+                    layer: false,
                 },
             )),
             &transaction,
@@ -312,6 +392,7 @@ mod tests {
                     parent: None,
                     transform: transform_id,
                     alpha,
+                    layer: false,
                 },
             )),
             &transaction,
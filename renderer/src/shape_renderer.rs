@@ -1,12 +1,14 @@
 use bytemuck::{Pod, Zeroable};
 use massive_geometry::{Color, Rect};
-use massive_shapes::Shape;
+use massive_shapes::{BoxShadow, Paint, Shape};
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 
 use crate::{
+    bind_group_entries,
+    gradient_ramps::{self, GradientRamps},
     pods::{self, AsBytes, VertexLayout},
-    renderer::RenderBatch,
-    tools::{PipelineParams, PipelineVariant},
+    renderer::{PreparationContext, RenderBatch},
+    tools::{BindGroupLayoutBuilder, PipelineParams, PipelineVariant, texture_sampler},
 };
 
 const FRAGMENT_SHADER_ENTRY: &str = "fs_main";
@@ -22,6 +24,7 @@ pub enum ShapeSelector {
     Circle = 2,
     Ellipse = 3,
     BeveledRect = 4,
+    BoxShadow = 5,
     // Non-filled
     StrokeRect = 10,
 }
@@ -32,10 +35,29 @@ impl From<ShapeSelector> for u32 {
     }
 }
 
+/// Paint selector values shared with `shape_renderer.wgsl`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[repr(u32)]
+pub enum PaintSelector {
+    Solid = 0,
+    LinearGradient = 1,
+    RadialGradient = 2,
+}
+
+impl From<PaintSelector> for u32 {
+    fn from(value: PaintSelector) -> Self {
+        value as u32
+    }
+}
+
 // Detail: Everything contained in this struct is just there to create the pipeline.
 #[derive(Debug)]
 pub struct ShapeRenderer {
     pipeline_params: PipelineParams,
+    fs_bind_group_layout: wgpu::BindGroupLayout,
+    ramp_sampler: wgpu::Sampler,
+    /// Bound to batches without gradients.
+    empty_ramps_bind_group: wgpu::BindGroup,
 }
 
 impl ShapeRenderer {
@@ -46,9 +68,14 @@ impl ShapeRenderer {
     ) -> Self {
         let shader = device.create_shader_module(wgpu::include_wgsl!("shape_renderer.wgsl"));
 
+        let fs_bind_group_layout = BindGroupLayoutBuilder::fragment_stage()
+            .texture()
+            .sampler()
+            .build("Gradient Ramps Bind Group Layout", device);
+
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Shape Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[Some(&fs_bind_group_layout)],
             immediate_size: pods::Immediates::size(),
         });
 
@@ -60,13 +87,22 @@ impl ShapeRenderer {
 
         let vertex_layout = [VertexT::layout()];
 
+        let ramp_sampler = texture_sampler::linear_clamping(device);
+        let empty_ramps = gradient_ramps::create_empty_ramp_texture(device);
+        let empty_ramps_bind_group =
+            create_ramps_bind_group(device, &fs_bind_group_layout, &empty_ramps, &ramp_sampler);
+
         Self {
             pipeline_params: PipelineParams {
                 shader,
                 pipeline_layout,
                 targets,
                 vertex_layout,
+                fragment_constants: Vec::new(),
             },
+            fs_bind_group_layout,
+            ramp_sampler,
+            empty_ramps_bind_group,
         }
     }
 
@@ -84,88 +120,106 @@ impl ShapeRenderer {
     }
 
     /// Build a batch directly from a slice of `massive_shapes::Shape` objects.
-    /// Ignores glyph runs (text); only geometric shapes are converted.
+    /// Ignores glyph runs (text) and paths; only geometric shapes are converted.
     pub fn batch_from_shapes(
         &self,
-        device: &wgpu::Device,
+        context: &PreparationContext,
         shapes: &[massive_shapes::Shape],
     ) -> Option<RenderBatch> {
+        let device = context.device;
         let mut vertices: Vec<Vertex> = Vec::with_capacity(shapes.len() * 4); // upper bound
         let mut quad_count = 0usize;
+        let mut ramps = GradientRamps::default();
         const B: f32 = 1.0; // 1px AA fringe in model space
 
-        // Helper that emits a single expanded quad with AA fringe and normalized tex coords.
-        let mut emit = |rect: &Rect, selector: ShapeSelector, data: (f32, f32), color: Color| {
-            let left = rect.left as f32;
-            let top = rect.top as f32;
-            let right = rect.right as f32;
-            let bottom = rect.bottom as f32;
-            let w = right - left;
-            let h = bottom - top;
-            let size = (w, h);
-            let selector: u32 = selector.into();
-            vertices.extend([
-                Vertex::new(
-                    (left - B, top - B, 0.0),
-                    (-B, -B),
-                    selector,
-                    size,
-                    data,
-                    color,
-                ),
-                Vertex::new(
-                    (left - B, bottom + B, 0.0),
-                    (-B, h + B),
-                    selector,
-                    size,
-                    data,
-                    color,
-                ),
-                Vertex::new(
-                    (right + B, bottom + B, 0.0),
-                    (w + B, h + B),
-                    selector,
-                    size,
-                    data,
-                    color,
-                ),
-                Vertex::new(
-                    (right + B, top - B, 0.0),
-                    (w + B, -B),
-                    selector,
-                    size,
-                    data,
-                    color,
-                ),
-            ]);
-            quad_count += 1;
-        };
+        // Helper that emits a single expanded quad with a fringe and normalized tex coords.
+        let mut emit =
+            |rect: &Rect, fringe: f32, selector: ShapeSelector, data: (f32, f32), paint: &Paint| {
+                let left = rect.left as f32;
+                let top = rect.top as f32;
+                let right = rect.right as f32;
+                let bottom = rect.bottom as f32;
+                let w = right - left;
+                let h = bottom - top;
+                let size = (w, h);
+                let selector: u32 = selector.into();
+                let paint = VertexPaint::new(paint, &mut ramps);
+                vertices.extend([
+                    Vertex::new(
+                        (left - fringe, top - fringe, 0.0),
+                        (-fringe, -fringe),
+                        selector,
+                        size,
+                        data,
+                        paint,
+                    ),
+                    Vertex::new(
+                        (left - fringe, bottom + fringe, 0.0),
+                        (-fringe, h + fringe),
+                        selector,
+                        size,
+                        data,
+                        paint,
+                    ),
+                    Vertex::new(
+                        (right + fringe, bottom + fringe, 0.0),
+                        (w + fringe, h + fringe),
+                        selector,
+                        size,
+                        data,
+                        paint,
+                    ),
+                    Vertex::new(
+                        (right + fringe, top - fringe, 0.0),
+                        (w + fringe, -fringe),
+                        selector,
+                        size,
+                        data,
+                        paint,
+                    ),
+                ]);
+                quad_count += 1;
+            };
 
         for shape in shapes.iter() {
             match shape {
                 Shape::GlyphRun(..) => {}
-                Shape::Rect(r) => emit(&r.rect, ShapeSelector::Rect, (0.0, 0.0), r.color),
+                Shape::Rect(r) => emit(&r.rect, B, ShapeSelector::Rect, (0.0, 0.0), &r.paint),
                 Shape::RoundRect(r) => emit(
                     &r.rect,
+                    B,
                     ShapeSelector::RoundedRect,
                     (r.corner_radius, 0.0),
-                    r.color,
+                    &r.paint,
                 ),
                 Shape::BeveledRect(r) => emit(
                     &r.rect,
+                    B,
                     ShapeSelector::BeveledRect,
                     (r.chamfer, r.corner_mask as f32),
-                    r.color,
+                    &r.paint,
                 ),
-                Shape::Circle(c) => emit(&c.rect, ShapeSelector::Circle, (0.0, 0.0), c.color),
-                Shape::Ellipse(e) => emit(&e.rect, ShapeSelector::Ellipse, (0.0, 0.0), e.color),
+                Shape::Circle(c) => emit(&c.rect, B, ShapeSelector::Circle, (0.0, 0.0), &c.paint),
+                Shape::Ellipse(e) => emit(&e.rect, B, ShapeSelector::Ellipse, (0.0, 0.0), &e.paint),
                 Shape::StrokeRect(s) => emit(
                     &s.rect,
+                    B,
                     ShapeSelector::StrokeRect,
                     (s.stroke.width as f32, s.stroke.height as f32),
-                    s.color,
+                    &s.color.into(),
                 ),
-                Shape::Custom(..) => {}
+                Shape::BoxShadow(s) => {
+                    let sigma = shadow_sigma(s.blur_radius);
+                    // The gaussian is negligible beyond 3 sigma.
+                    emit(
+                        &s.shadow_rect(),
+                        3.0 * sigma,
+                        ShapeSelector::BoxShadow,
+                        (shadow_corner_radius(s), sigma),
+                        &s.color.into(),
+                    )
+                }
+                Shape::Image(..) | Shape::Path(..) | Shape::Custom(..) => {}
             }
         }
 
@@ -179,16 +233,106 @@ impl ShapeRenderer {
             usage: wgpu::BufferUsages::VERTEX,
         });
 
+        let fs_bind_group = if ramps.is_empty() {
+            self.empty_ramps_bind_group.clone()
+        } else {
+            let texture = ramps.create_texture(device, context.queue);
+            create_ramps_bind_group(
+                device,
+                &self.fs_bind_group_layout,
+                &texture,
+                &self.ramp_sampler,
+            )
+        };
+
         Some(RenderBatch {
-            fs_bind_group: None,
+            fs_bind_group: Some(fs_bind_group),
             vertex_buffer,
             count: quad_count,
+            index_buffer: None,
         })
     }
 }
 
+/// The standard deviation of the shadow's gaussian. Like in CSS, it is half the blur radius.
+///
+/// A minimum keeps the shader away from a division by zero and anti-aliases unblurred shadows.
+fn shadow_sigma(blur_radius: f32) -> f32 {
+    (blur_radius * 0.5).max(0.5)
+}
+
+/// The spread grows the corner radius with the shadow. Sharp corners stay sharp.
+fn shadow_corner_radius(shadow: &BoxShadow) -> f32 {
+    if shadow.corner_radius <= 0.0 {
+        return 0.0;
+    }
+    (shadow.corner_radius + shadow.spread).max(0.0)
+}
+
+fn create_ramps_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    ramps: &wgpu::Texture,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    let view = ramps.create_view(&wgpu::TextureViewDescriptor::default());
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Gradient Ramps Bind Group"),
+        layout,
+        entries: bind_group_entries!(0 => &view, 1 => sampler),
+    })
+}
+
+/// The per-vertex representation of a [`Paint`].
+#[derive(Copy, Clone, Debug)]
+pub struct VertexPaint {
+    pub selector: PaintSelector,
+    /// The solid color. Unused for gradients.
+    pub color: Color,
+    /// Linear: start xy, end xy. Radial: center xy, radius. In unit coordinates of the shape.
+    pub geometry: [f32; 4],
+    /// The row of the gradient in the ramp texture.
+    pub ramp: u32,
+}
+
+impl VertexPaint {
+    pub fn new(paint: &Paint, ramps: &mut GradientRamps) -> Self {
+        match paint {
+            Paint::Solid(color) => Self {
+                selector: PaintSelector::Solid,
+                color: *color,
+                geometry: [0.0; 4],
+                ramp: 0,
+            },
+            Paint::LinearGradient(gradient) => Self {
+                selector: PaintSelector::LinearGradient,
+                color: Color::TRANSPARENT,
+                geometry: [
+                    gradient.start.x as f32,
+                    gradient.start.y as f32,
+                    gradient.end.x as f32,
+                    gradient.end.y as f32,
+                ],
+                ramp: ramps.row(gradient, &gradient.stops),
+            },
+            Paint::RadialGradient(gradient) => Self {
+                selector: PaintSelector::RadialGradient,
+                color: Color::TRANSPARENT,
+                geometry: [
+                    gradient.center.x as f32,
+                    gradient.center.y as f32,
+                    gradient.radius as f32,
+                    0.0,
+                ],
+                ramp: ramps.row(gradient, &gradient.stops),
+            },
+        }
+    }
+}
+
 /// Vertex format for `shape/shape.wgsl`.
-/// locations: 0=position, 1=unorm_tex_coords, 2=shape_selector, 3=shape_size, 4=shape_data, 5=color
+/// locations: 0=position, 1=unorm_tex_coords, 2=shape_selector, 3=shape_size, 4=shape_data, 5=color,
+/// 6=paint_selector, 7=paint_geometry, 8=paint_ramp
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Vertex {
@@ -198,17 +342,19 @@ pub struct Vertex {
     pub shape_size: [f32; 2],
     pub shape_data: [f32; 2],
     pub color: pods::Color,
+    pub paint_selector: u32,
+    pub paint_geometry: [f32; 4],
+    pub paint_ramp: u32,
 }
 
 impl Vertex {
-    #[allow(clippy::too_many_arguments)]
     pub fn new(
         position: impl Into<pods::Vertex>,
         unorm_tex_coords: (f32, f32),
         shape_selector: u32,
         shape_size: (f32, f32),
         shape_data: (f32, f32),
-        color: impl Into<pods::Color>,
+        paint: VertexPaint,
     ) -> Self {
         Self {
             position: position.into(),
@@ -216,7 +362,10 @@ impl Vertex {
             shape_selector,
             shape_size: [shape_size.0, shape_size.1],
             shape_data: [shape_data.0, shape_data.1],
-            color: color.into(),
+            color: paint.color.into(),
+            paint_selector: paint.selector.into(),
+            paint_geometry: paint.geometry,
+            paint_ramp: paint.ramp,
         }
     }
 }
@@ -224,13 +373,16 @@ impl Vertex {
 impl VertexLayout for Vertex {
     fn layout() -> wgpu::VertexBufferLayout<'static> {
         // Order must match struct field order. Shader location indices map accordingly.
-        const ATTRS: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        const ATTRS: [wgpu::VertexAttribute; 9] = wgpu::vertex_attr_array![
             0 => Float32x3, // position
             1 => Float32x2, // unorm_tex_coords
             2 => Uint32,    // shape_selector
             3 => Float32x2, // shape_size
             4 => Float32x2, // shape_data
-            5 => Float32x4  // color
+            5 => Float32x4, // color
+            6 => Uint32,    // paint_selector
+            7 => Float32x4, // paint_geometry
+            8 => Uint32     // paint_ramp
         ];
 
         wgpu::VertexBufferLayout {
@@ -240,3 +392,76 @@ impl VertexLayout for Vertex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use massive_geometry::SizePx;
+    use massive_scene::{At, ChangeCollector, Object, Scene, ToLocation, Transform};
+
+    use super::*;
+    use crate::{HeadlessRenderer, render_device::test_device};
+
+    #[test]
+    fn shadow_rect_is_offset_and_grown_by_the_spread() {
+        let shadow = BoxShadow::new((0., 0., 20., 10.), 4.0, 8.0, Color::BLACK)
+            .with_offset((2.0, 3.0))
+            .with_spread(1.0);
+        assert_eq!(shadow.shadow_rect(), Rect::from((1., 2., 23., 14.)));
+
+        let shrunk = shadow.with_spread(-2.0);
+        assert_eq!(shrunk.shadow_rect(), Rect::from((4., 5., 20., 11.)));
+    }
+
+    #[test]
+    fn shadow_sigma_is_half_the_blur_radius() {
+        assert_eq!(shadow_sigma(8.0), 4.0);
+        assert_eq!(shadow_sigma(0.0), 0.5);
+        assert_eq!(shadow_sigma(-4.0), 0.5);
+    }
+
+    #[test]
+    fn shadow_corner_radius_grows_with_the_spread() {
+        let shadow = BoxShadow::new((0., 0., 20., 10.), 4.0, 8.0, Color::BLACK);
+        assert_eq!(shadow_corner_radius(&shadow.clone().with_spread(2.0)), 6.0);
+        assert_eq!(shadow_corner_radius(&shadow.clone().with_spread(-6.0)), 0.0);
+
+        let sharp = BoxShadow::new((0., 0., 20., 10.), 0.0, 8.0, Color::BLACK).with_spread(2.0);
+        assert_eq!(shadow_corner_radius(&sharp), 0.0);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn box_shadow_fades_out_over_the_blur_radius() {
+        let size = SizePx::new(64, 64);
+        let mut headless = HeadlessRenderer::new(test_device(), size, |builder| {
+            builder.with_background_color(Color::BLACK).with_shapes()
+        });
+
+        // The camera looks at the origin, the shadow covers the pixels 24..40 before it is blurred.
+        let scene = Scene::new(Arc::new(ChangeCollector::default()));
+        let location = Transform::IDENTITY
+            .enter(&scene)
+            .to_location()
+            .enter(&scene);
+        let _visual = vec![Shape::BoxShadow(BoxShadow::new(
+            (-8., -8., 8., 8.),
+            0.0,
+            8.0,
+            Color::WHITE,
+        ))]
+        .at(&location)
+        .enter(&scene);
+
+        let image = headless.render(scene.take_changes()).unwrap();
+        let red = |x| image.pixel(x, 32)[0];
+
+        assert!(red(32) > 200, "center: {}", red(32));
+        // Outside of the rect, the shadow fades out.
+        assert!(red(40) < red(36), "edge: {}, inside: {}", red(40), red(36));
+        assert!(red(44) > 0 && red(44) < red(40), "outside: {}", red(44));
+        // Beyond 3 sigma, nothing is drawn.
+        assert_eq!(red(56), 0);
+    }
+}
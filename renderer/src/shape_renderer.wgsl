@@ -14,13 +14,23 @@ struct VertexInput {
     @location(1) unorm_tex_coords: vec2<f32>,
     // Per-vertex shape size (width, height)
     @location(3) shape_size: vec2<f32>,
-    // Shape selector (0 = rect, 1 = rounded rect, 2 = circle, 3 = rect stroke)
+    // Shape selector (0 = rect, 1 = rounded rect, 2 = circle, 3 = ellipse, 4 = chamfer rect,
+    // 5 = box shadow, 10 = rect stroke)
     @location(2) shape_selector: u32,
     // Per-shape vec
     //  - rounded rect: radius (only [0] is used)
+    //  - box shadow: corner radius, sigma of the gaussian blur
     //  - rect stroke: stroke thickness per axis (x = horizontal edges thickness, y = vertical edges thickness)
     @location(4) shape_data: vec2<f32>,
     @location(5) color: vec4<f32>,
+    // Paint selector (0 = solid color, 1 = linear gradient, 2 = radial gradient)
+    @location(6) paint_selector: u32,
+    // Gradient geometry in unit coordinates of the shape
+    //  - linear: start xy, end xy
+    //  - radial: center xy, radius
+    @location(7) paint_geometry: vec4<f32>,
+    // Row of the gradient in the ramp texture
+    @location(8) paint_ramp: u32,
 }
 
 struct VertexOutput {
@@ -31,8 +41,16 @@ struct VertexOutput {
     @location(3) @interpolate(flat) shape_data: vec2<f32>,
     @location(4) @interpolate(flat) color: vec4<f32>,
     @location(5) model_pos: vec2<f32>,
+    @location(6) @interpolate(flat) paint_selector: u32,
+    @location(7) @interpolate(flat) paint_geometry: vec4<f32>,
+    @location(8) @interpolate(flat) paint_ramp: u32,
 }
 
+@group(0) @binding(0)
+var t_ramps: texture_2d<f32>;
+@group(0) @binding(1)
+var s_ramps: sampler;
+
 @vertex
 fn vs_main(
     vertex_input: VertexInput,
@@ -46,6 +64,9 @@ fn vs_main(
     out.shape_size = vertex_input.shape_size;
     out.shape_data = vertex_input.shape_data;
     out.color = vertex_input.color;
+    out.paint_selector = vertex_input.paint_selector;
+    out.paint_geometry = vertex_input.paint_geometry;
+    out.paint_ramp = vertex_input.paint_ramp;
     return out;
 }
 
@@ -56,6 +77,10 @@ fn vs_main(
 // - version 1 would not render horizontal / vertical edges pixel perfect. Only with df_aa_factor 0.5, but 
 //   then the diagonal anti-aliasing is too crisp.
 
+// Colors, including the ones sampled from the gradient ramps, are linear. On sRGB targets, they
+// are blended in linear space and encoded when written. Other targets are expected to take linear
+// colors as they are.
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Clip fragments outside the clip rectangle (exclusive bounds)
@@ -63,13 +88,47 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         in.model_pos.y < im.clip_rect_y.x || in.model_pos.y >= im.clip_rect_y.y) {
         discard;
     }
+
+    if (in.shape_selector == 5u) {
+        let half_shape_size = in.shape_size * 0.5;
+        let p_local = in.unorm_tex_coords - half_shape_size;
+        let coverage = rounded_box_shadow(p_local, half_shape_size, in.shape_data.x, in.shape_data.y);
+        return vec4(in.color.rgb, in.color.a * coverage * im.alpha);
+    }
     
     let distance = compute_distance(in);
     // Adaptive screen-space AA using intrinsic fwidth(distance)
     // fwidth(x) = abs(dfdx(x)) + abs(dfdy(x)); gives 1.0 on axis-aligned SDF edges, ~1.414 at 45°.
     let afwidth = fwidth(distance) * 0.5;
     let val = smoothstep(-afwidth, afwidth, distance);
-    return vec4(in.color.rgb, in.color.a * val * im.alpha);
+    let color = paint_color(in);
+    return vec4(color.rgb, color.a * val * im.alpha);
+}
+
+fn paint_color(in: VertexOutput) -> vec4<f32> {
+    // Unit coordinates of the shape, (0, 0) top left, (1, 1) bottom right.
+    let uv = in.unorm_tex_coords / max(in.shape_size, vec2<f32>(1e-5, 1e-5));
+    var t: f32 = 0.0;
+    switch (in.paint_selector) {
+        case 1u: { // linear gradient
+            let start = in.paint_geometry.xy;
+            let direction = in.paint_geometry.zw - start;
+            t = dot(uv - start, direction) / max(dot(direction, direction), 1e-10);
+        }
+        case 2u: { // radial gradient
+            let radius = max(in.paint_geometry.z, 1e-5);
+            t = length(uv - in.paint_geometry.xy) / radius;
+        }
+        default: { // solid
+            return in.color;
+        }
+    }
+    // Sample texel centers only, so that the ends of the ramp are not blended with the border.
+    let ramp_size = vec2<f32>(textureDimensions(t_ramps));
+    let u = (clamp(t, 0.0, 1.0) * (ramp_size.x - 1.0) + 0.5) / ramp_size.x;
+    let v = (f32(in.paint_ramp) + 0.5) / ramp_size.y;
+    // Explicit level, because the sample is in non-uniform control flow.
+    return textureSampleLevel(t_ramps, s_ramps, vec2<f32>(u, v), 0.0);
 }
 
 // v1
@@ -220,3 +279,45 @@ fn sd_chamfer_rect(p: vec2<f32>, half_size: vec2<f32>, chamfer: f32, corners: u3
     
     return dist;
 }
+
+// Analytic blurred rounded rect, by Evan Wallace: <https://madebyevan.com/shaders/fast-rounded-rectangle-shadows/>
+//
+// The blur is exact along x and integrated with 4 samples along y. Returns the coverage.
+
+fn rounded_box_shadow(p: vec2<f32>, half_size: vec2<f32>, corner: f32, sigma: f32) -> f32 {
+    let radius = min(corner, min(half_size.x, half_size.y));
+    // The gaussian is negligible beyond 3 sigma, so only integrate where the box is.
+    let low = p.y - half_size.y;
+    let high = p.y + half_size.y;
+    let start = clamp(-3.0 * sigma, low, high);
+    let end = clamp(3.0 * sigma, low, high);
+    let step = (end - start) / 4.0;
+    var y = start + step * 0.5;
+    var value = 0.0;
+    for (var i = 0; i < 4; i++) {
+        value += rounded_box_shadow_x(p.x, p.y - y, half_size, radius, sigma) * gaussian(y, sigma) * step;
+        y += step;
+    }
+    return value;
+}
+
+fn rounded_box_shadow_x(x: f32, y: f32, half_size: vec2<f32>, radius: f32, sigma: f32) -> f32 {
+    let delta = min(half_size.y - radius - abs(y), 0.0);
+    let curved = half_size.x - radius + sqrt(max(0.0, radius * radius - delta * delta));
+    let integral = 0.5 + 0.5 * erf(vec2<f32>(x - curved, x + curved) * (sqrt(0.5) / sigma));
+    return integral.y - integral.x;
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    let pi = 3.141592653589793;
+    return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(2.0 * pi) * sigma);
+}
+
+// Approximation of the error function.
+fn erf(x: vec2<f32>) -> vec2<f32> {
+    let s = sign(x);
+    let a = abs(x);
+    var r = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    r = r * r;
+    return s - s / (r * r);
+}
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{collections::VecDeque, time::Duration};
 
 use tracing::info;
 
@@ -62,3 +62,148 @@ impl Stats {
         Some(self.sum / self.count as u32)
     }
 }
+
+/// What happened in one frame.
+///
+/// Collected by the renderer in `prepare()`, `render()`, and `present()`.
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    /// Visuals whose batches were produced again.
+    pub visuals_changed: usize,
+    /// The batches produced in this frame, indexed by pipeline.
+    pub batches_produced: Vec<usize>,
+    /// Quads drawn from quad batches, including the ones drawn into layers.
+    pub quads_drawn: usize,
+    /// Triangles drawn from indexed batches.
+    pub triangles_drawn: usize,
+    /// The atlases reported by the batch producers after the batches were produced.
+    pub atlases: Vec<AtlasStats>,
+    pub prepare_time: Duration,
+    /// Encoding and submission. Includes the time the GPU needs if measurements are enabled.
+    pub render_time: Duration,
+    pub present_time: Duration,
+}
+
+impl FrameStats {
+    pub fn batches_produced_total(&self) -> usize {
+        self.batches_produced.iter().sum()
+    }
+
+    pub fn atlas(&self, name: &str) -> Option<&AtlasStats> {
+        self.atlases.iter().find(|atlas| atlas.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasStats {
+    pub name: &'static str,
+    /// Width and height of the square atlas texture.
+    pub dim: u32,
+    pub allocated_texels: usize,
+    /// How often the atlas grew since the previous report.
+    pub grow_events: usize,
+    /// Entries evicted since the previous report.
+    pub evictions: usize,
+    /// How often the atlas was compacted since the previous report.
+    pub compactions: usize,
+}
+
+impl AtlasStats {
+    /// The allocated part of the atlas, from 0 to 1.
+    pub fn occupancy(&self) -> f32 {
+        let texels = self.dim as usize * self.dim as usize;
+        if texels == 0 {
+            return 0.0;
+        }
+        self.allocated_texels as f32 / texels as f32
+    }
+}
+
+/// The statistics of the most recent frames, oldest first.
+#[derive(Debug)]
+pub struct FrameStatsHistory {
+    frames: VecDeque<FrameStats>,
+    capacity: usize,
+}
+
+impl Default for FrameStatsHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl FrameStatsHistory {
+    pub const DEFAULT_CAPACITY: usize = 120;
+
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Frame stats history needs a capacity");
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, frame: FrameStats) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    pub fn latest(&self) -> Option<&FrameStats> {
+        self.frames.back()
+    }
+
+    pub fn latest_mut(&mut self) -> Option<&mut FrameStats> {
+        self.frames.back_mut()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &FrameStats> + ExactSizeIterator {
+        self.frames.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the capacity and drops the oldest frames that don't fit anymore.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "Frame stats history needs a capacity");
+        self.capacity = capacity;
+        while self.frames.len() > capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_the_most_recent_frames() {
+        let mut history = FrameStatsHistory::new(2);
+        for visuals_changed in 1..=3 {
+            history.push(FrameStats {
+                visuals_changed,
+                ..Default::default()
+            });
+        }
+
+        let changed: Vec<_> = history.iter().map(|f| f.visuals_changed).collect();
+        assert_eq!(changed, [2, 3]);
+        assert_eq!(history.latest().unwrap().visuals_changed, 3);
+    }
+}
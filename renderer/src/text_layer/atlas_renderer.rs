@@ -25,6 +25,7 @@ impl AtlasRenderer {
         device: &wgpu::Device,
         atlas_format: wgpu::TextureFormat,
         shader: wgpu::ShaderModuleDescriptor<'_>,
+        fragment_constants: Vec<(&'static str, f64)>,
         target_format: wgpu::TextureFormat,
     ) -> Self {
         let fs_bind_group_layout = BindGroupLayout::new(device);
@@ -54,6 +55,7 @@ impl AtlasRenderer {
                 pipeline_layout,
                 targets,
                 vertex_layout,
+                fragment_constants,
             },
         }
     }
@@ -104,6 +106,7 @@ impl AtlasRenderer {
             fs_bind_group: Some(fs_bind_group),
             vertex_buffer,
             count: instances.len(),
+            index_buffer: None,
         })
     }
 }
@@ -0,0 +1,99 @@
+use massive_geometry::{Color, Vector3};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+
+use crate::{
+    pods::{self, AsBytes, ColorVertex, VertexLayout},
+    renderer::{PreparationContext, RenderBatch},
+    tools::{PipelineParams, PipelineVariant},
+};
+
+const FRAGMENT_SHADER_ENTRY: &str = "fs_main";
+
+/// Renders the decorations of glyph runs as solid quads.
+///
+/// The quads are not anti-aliased, they are expected to be placed on the pixel grid.
+#[derive(Debug)]
+pub struct DecorationRenderer {
+    pipeline_params: PipelineParams,
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    pub vertices: [Vector3; 4],
+    pub color: Color,
+}
+
+impl DecorationRenderer {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("decorations.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Decoration Pipeline Layout"),
+            bind_group_layouts: &[],
+            immediate_size: pods::Immediates::size(),
+        });
+
+        let targets = [Some(wgpu::ColorTargetState {
+            format: target_format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+
+        let vertex_layout = [ColorVertex::layout()];
+
+        Self {
+            pipeline_params: PipelineParams {
+                shader,
+                pipeline_layout,
+                targets,
+                vertex_layout,
+                fragment_constants: Vec::new(),
+            },
+        }
+    }
+
+    pub fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        variant: PipelineVariant,
+    ) -> wgpu::RenderPipeline {
+        self.pipeline_params.create_pipeline(
+            "Decoration Pipeline",
+            device,
+            FRAGMENT_SHADER_ENTRY,
+            variant,
+        )
+    }
+
+    pub fn batch(
+        &self,
+        context: &PreparationContext,
+        instances: &[Instance],
+    ) -> Option<RenderBatch> {
+        if instances.is_empty() {
+            return None;
+        }
+
+        let vertices: Vec<ColorVertex> = instances
+            .iter()
+            .flat_map(|instance| {
+                instance
+                    .vertices
+                    .map(|vertex| ColorVertex::new(vertex, instance.color))
+            })
+            .collect();
+
+        let vertex_buffer = context.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Decoration Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Some(RenderBatch {
+            fs_bind_group: None,
+            vertex_buffer,
+            count: instances.len(),
+            index_buffer: None,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+// Vertex shader
+
+struct Immediates {
+    view_model: mat4x4<f32>,
+    clip_rect_x: vec2<f32>, // [min_x, max_x]
+    clip_rect_y: vec2<f32>, // [min_y, max_y]
+    alpha: f32,
+}
+
+var<immediate> im: Immediates;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) color: vec4<f32>,
+    @location(1) model_pos: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    vertex_input: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.model_pos = vertex_input.position.xy;
+    out.clip_position = im.view_model * vec4<f32>(vertex_input.position, 1.0);
+    out.color = vertex_input.color;
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Clip fragments outside the clip rectangle (exclusive bounds)
+    if (in.model_pos.x < im.clip_rect_x.x || in.model_pos.x >= im.clip_rect_x.y ||
+        in.model_pos.y < im.clip_rect_y.x || in.model_pos.y >= im.clip_rect_y.y) {
+        discard;
+    }
+
+    return vec4<f32>(in.color.rgb, in.color.a * im.alpha);
+}
@@ -1,5 +1,6 @@
 mod atlas_renderer;
 mod color_atlas;
+mod decorations;
 mod renderer;
 mod sdf_atlas;
 
@@ -5,6 +5,7 @@ use cosmic_text::{self as text, SwashContent};
 use swash::scale::ScaleContext;
 use wgpu::Device;
 
+use glam::IVec2;
 use massive_geometry::{Point, Vector3};
 use massive_shapes::{GlyphRun, RunGlyph};
 
@@ -15,7 +16,13 @@ use crate::{
         glyph_rasterization::{RasterizedGlyphKey, rasterize_glyph_with_padding},
     },
     renderer::{PreparationContext, RenderBatch},
-    text_layer::{atlas_renderer::AtlasRenderer, color_atlas, sdf_atlas},
+    stats::AtlasStats,
+    text_layer::{
+        atlas_renderer::AtlasRenderer,
+        color_atlas,
+        decorations::{self, DecorationRenderer},
+        sdf_atlas,
+    },
     tools::PipelineVariant,
 };
 
@@ -34,6 +41,7 @@ pub struct TextLayerRenderer {
 
     sdf_renderer: AtlasRenderer,
     color_renderer: AtlasRenderer,
+    decoration_renderer: DecorationRenderer,
 
     /// The maximum quads currently in use. This may be more than the index buffer can hold.
     max_quads_in_use: usize,
@@ -47,6 +55,7 @@ impl fmt::Debug for TextLayerRenderer {
             .field("empty_glyphs", &self.empty_glyphs)
             .field("sdf_renderer", &self.sdf_renderer)
             .field("color_renderer", &self.color_renderer)
+            .field("decoration_renderer", &self.decoration_renderer)
             .field("max_quads_in_use", &self.max_quads_in_use)
             .finish()
     }
@@ -59,6 +68,9 @@ enum AtlasKind {
 }
 
 impl TextLayerRenderer {
+    /// Backgrounds, sdf glyphs, color glyphs, and the decorations drawn over the glyphs.
+    pub const PIPELINES: usize = 4;
+
     pub fn new(device: &Device, fonts: FontManager, target_format: wgpu::TextureFormat) -> Self {
         Self {
             scale_context: ScaleContext::default(),
@@ -70,35 +82,86 @@ impl TextLayerRenderer {
                 device,
                 wgpu::TextureFormat::R8Unorm,
                 wgpu::include_wgsl!("sdf_atlas.wgsl"),
+                vec![("SRGB_TARGET", f64::from(u8::from(target_format.is_srgb())))],
                 target_format,
             ),
             color_renderer: AtlasRenderer::new::<color_atlas::TextureVertex>(
                 device,
-                wgpu::TextureFormat::Rgba8Unorm,
+                // Color glyphs are sRGB encoded, they are decoded to linear when sampled.
+                wgpu::TextureFormat::Rgba8UnormSrgb,
                 wgpu::include_wgsl!("color_atlas.wgsl"),
+                Vec::new(),
                 target_format,
             ),
+            decoration_renderer: DecorationRenderer::new(device, target_format),
             max_quads_in_use: 0,
         }
     }
 
-    /// Prepare a number of glyph runs and produce sdf and color batches.
+    /// Sets the texture size in bytes each of the glyph atlases is compacted to.
+    pub fn with_atlas_budget(mut self, budget: usize) -> Self {
+        self.sdf_renderer.atlas.set_budget(budget);
+        self.color_renderer.atlas.set_budget(budget);
+        self
+    }
+
+    /// The stats of the sdf and color glyph atlases.
+    pub fn take_atlas_stats(&mut self) -> [AtlasStats; 2] {
+        [
+            self.sdf_renderer.atlas.take_stats("glyph-sdf"),
+            self.color_renderer.atlas.take_stats("glyph-color"),
+        ]
+    }
+
+    /// Start a new frame of the glyph atlases. Must be called before any rectangles are collected,
+    /// because the atlases may move their glyphs.
+    ///
+    /// Returns `true` if an atlas was compacted, then all batches must be produced again.
+    pub fn maintain_atlases(&mut self, context: &PreparationContext) -> bool {
+        let sdf = self
+            .sdf_renderer
+            .atlas
+            .maintain(context.device, context.queue);
+        let color = self
+            .color_renderer
+            .atlas
+            .maintain(context.device, context.queue);
+        sdf || color
+    }
+
+    /// Prepare a number of glyph runs and produce batches in the order of the pipelines, see
+    /// [`Self::PIPELINES`].
     ///
     /// All of the runs use the same model matrix.
     pub fn runs_to_batches<'a>(
         &mut self,
         context: &PreparationContext,
         runs: impl Iterator<Item = &'a GlyphRun>,
-    ) -> Result<[Option<RenderBatch>; 2]> {
+    ) -> Result<[Option<RenderBatch>; Self::PIPELINES]> {
         // Step 1: Get all instance data.
         // Performance: Compute a conservative capacity?
         let mut sdf_glyphs = Vec::new();
         let mut color_glyphs = Vec::new();
+        let mut backgrounds = Vec::new();
+        let mut lines = Vec::new();
 
         let fonts = self.fonts.clone();
 
         for run in runs {
             let translation = run.translation;
+            for decoration in run.decorations() {
+                let (lt, rb) = run.place_decoration(decoration);
+                let instance = decorations::Instance {
+                    vertices: Self::rect_vertices(lt, rb).map(|p| p + translation),
+                    color: decoration.color,
+                };
+                if decoration.kind.is_background() {
+                    backgrounds.push(instance);
+                } else {
+                    lines.push(instance);
+                }
+            }
+
             for glyph in &run.glyphs {
                 let Some((rect, placement, kind)) = self.rasterized_glyph_atlas_rect(
                     context, &fonts, // run.text_weight,
@@ -133,8 +196,10 @@ impl TextLayerRenderer {
 
         let sdf_batch = self.sdf_renderer.batch(context, &sdf_glyphs);
         let color_batch = self.color_renderer.batch(context, &color_glyphs);
+        let background_batch = self.decoration_renderer.batch(context, &backgrounds);
+        let line_batch = self.decoration_renderer.batch(context, &lines);
 
-        Ok([sdf_batch, color_batch])
+        Ok([background_batch, sdf_batch, color_batch, line_batch])
     }
 
     // This makes sure that there is a rasterized glyph in the atlas and returns the rectangle.
@@ -215,8 +280,11 @@ impl TextLayerRenderer {
         placement: &text::Placement,
     ) -> [Vector3; 4] {
         let (lt, rb) = run.place_glyph(glyph, placement);
+        Self::rect_vertices(lt, rb)
+    }
 
-        // Convert the pixel rect to 3D Points.
+    /// The corners of a pixel rect as 3D Points.
+    fn rect_vertices(lt: IVec2, rb: IVec2) -> [Vector3; 4] {
         let left = lt.x as f64;
         let top = lt.y as f64;
         let right = rb.x as f64;
@@ -248,4 +316,77 @@ impl TextLayerRenderer {
     ) -> wgpu::RenderPipeline {
         self.color_renderer.create_pipeline(device, variant)
     }
+
+    pub fn create_decoration_pipeline(
+        &self,
+        device: &wgpu::Device,
+        variant: PipelineVariant,
+    ) -> wgpu::RenderPipeline {
+        self.decoration_renderer.create_pipeline(device, variant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use massive_geometry::Color;
+    use massive_shapes::{TextAttributes, TextShaper};
+
+    use super::*;
+    use crate::{HeadlessRenderer, render_device::test_device};
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn batches_are_produced_in_pipeline_order() {
+        let format = HeadlessRenderer::FORMAT;
+        let device = test_device();
+        let fonts = FontManager::bare("en-US").with_font(
+            fs::read(
+                Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("../examples/shared/src/fonts/Montserrat/Montserrat-Regular.ttf"),
+            )
+            .unwrap(),
+        );
+        let run = TextShaper::new("ABC")
+            .with_default_attributes(
+                TextAttributes::default()
+                    .with_family(text::Family::Name("Montserrat"))
+                    .with_background(Color::WHITE)
+                    .with_underline(Color::BLACK)
+                    .with_strikethrough(Color::BLACK),
+            )
+            .layout(&mut fonts.lock(), 16.0)
+            .unwrap();
+
+        let mut renderer = TextLayerRenderer::new(&device.device, fonts, format);
+        let context = PreparationContext {
+            device: &device.device,
+            queue: &device.queue,
+        };
+        let batches = renderer
+            .runs_to_batches(&context, [&run].into_iter())
+            .unwrap();
+
+        // Backgrounds, sdf glyphs, color glyphs, underlines and strikethroughs.
+        let counts = batches.map(|batch| batch.map(|batch| batch.count));
+        assert_eq!(counts, [Some(1), Some(3), None, Some(2)]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn sdf_pipelines_are_created_for_srgb_and_linear_targets() {
+        let device = test_device();
+
+        for format in [
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureFormat::Rgba8Unorm,
+        ] {
+            let renderer =
+                TextLayerRenderer::new(&device.device, FontManager::bare("en-US"), format);
+            for variant in [PipelineVariant::Standard, PipelineVariant::Decal] {
+                renderer.create_sdf_pipeline(&device.device, variant);
+            }
+        }
+    }
 }
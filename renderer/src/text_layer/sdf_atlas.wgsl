@@ -107,9 +107,28 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let af_width = texels_moved_in_normal_dir * df_aa_factor;
 
-    // gamma correct
     // let val = saturate((distance + afwidth) / (2.0 * afwidth));
-    let val = smoothstep(-af_width, af_width, distance);
+    let val = linear_coverage(smoothstep(-af_width, af_width, distance), in.color.rgb);
 
     return vec4<f32>(in.color.rgb, in.color.a * val * im.alpha);
 }
+
+// Set by the pipeline: Whether the target is an sRGB format.
+override SRGB_TARGET: bool = true;
+
+// sRGB targets blend in linear space, but the edge coverage is perceived in sRGB space: Without a
+// correction, dark text on light backgrounds looks too thin, and light text on dark backgrounds
+// too bold.
+//
+// The coverage is adjusted so that the blended edges of black text on white and white text on black
+// are encoded as if the coverage was blended in sRGB space. Colors in between get a mix of both.
+// Other targets blend the values as they are written, so the coverage is used as is.
+fn linear_coverage(coverage: f32, color: vec3<f32>) -> f32 {
+    if (!SRGB_TARGET) {
+        return coverage;
+    }
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let dark = 1.0 - pow(1.0 - coverage, 2.2);
+    let light = pow(coverage, 2.2);
+    return mix(dark, light, saturate(luminance));
+}
@@ -0,0 +1,5 @@
+mod bind_group;
+mod view;
+
+pub use bind_group::*;
+pub use view::*;
@@ -1,3 +1,5 @@
+use massive_geometry::SizePx;
+
 use crate::{SizeBuffer, tools::AsBindingResource};
 
 #[derive(Debug)]
@@ -8,14 +10,16 @@ pub struct View {
 
 impl View {
     /// Creates a texture and uploads the image's content to the GPU.
+    #[allow(unused)]
     pub fn from_data(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
         data: &[u8],
         size: SizePx,
     ) -> Self {
         let (width, height) = size.into();
-        let size = wgpu::Extent3d {
+        let extent = wgpu::Extent3d {
             width,
             height,
             depth_or_array_layers: 1,
@@ -23,15 +27,19 @@ impl View {
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Texture"),
-            size,
+            size: extent,
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R8Unorm,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
 
+        let bytes_per_pixel = format
+            .block_copy_size(None)
+            .expect("Texture format without a block size");
+
         queue.write_texture(
             wgpu::TexelCopyTextureInfo {
                 texture: &texture,
@@ -42,14 +50,19 @@ impl View {
             data,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(width),
+                bytes_per_row: Some(width * bytes_per_pixel),
                 rows_per_image: None,
             },
-            size,
+            extent,
         );
 
+        Self::from_texture(device, &texture)
+    }
+
+    /// Creates a view of the whole texture.
+    pub fn from_texture(device: &wgpu::Device, texture: &wgpu::Texture) -> Self {
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let size = SizeBuffer::new(device, (width, height));
+        let size = SizeBuffer::new(device, (texture.width(), texture.height()).into());
         Self { view, size }
     }
 
@@ -37,6 +37,17 @@ impl BindGroupLayoutBuilder {
         })
     }
 
+    /// A depth texture, read with `textureLoad()` from a `texture_2d<f32>`.
+    ///
+    /// Not bound as `texture_depth_2d`, because the GL backend can't load from those.
+    pub fn depth_texture(self) -> Self {
+        self.add_type(wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        })
+    }
+
     pub fn sampler(self) -> Self {
         self.add_type(wgpu::BindingType::Sampler(
             wgpu::SamplerBindingType::Filtering,
@@ -17,6 +17,8 @@ pub struct PipelineParams {
     pub pipeline_layout: wgpu::PipelineLayout,
     pub targets: [Option<wgpu::ColorTargetState>; 1],
     pub vertex_layout: [wgpu::VertexBufferLayout<'static>; 1],
+    /// Values of the fragment shader's override constants.
+    pub fragment_constants: Vec<(&'static str, f64)>,
 }
 
 impl PipelineParams {
@@ -35,6 +37,7 @@ impl PipelineParams {
             &self.vertex_layout,
             &self.pipeline_layout,
             &self.targets,
+            &self.fragment_constants,
             variant,
         )
     }
@@ -49,6 +52,7 @@ pub fn create_pipeline(
     vertex_layout: &[wgpu::VertexBufferLayout],
     pipeline_layout: &wgpu::PipelineLayout,
     targets: &[Option<wgpu::ColorTargetState>],
+    fragment_constants: &[(&str, f64)],
     variant: PipelineVariant,
 ) -> wgpu::RenderPipeline {
     let label = variant_label(label, variant);
@@ -65,7 +69,10 @@ pub fn create_pipeline(
         fragment: Some(wgpu::FragmentState {
             module: shader,
             entry_point: Some(fragment_shader_entry),
-            compilation_options: Default::default(),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants: fragment_constants,
+                ..Default::default()
+            },
             targets,
         }),
         primitive: wgpu::PrimitiveState {
@@ -81,6 +88,47 @@ pub fn create_pipeline(
     device.create_render_pipeline(&pipeline)
 }
 
+/// A pipeline that draws one triangle covering the whole target, without vertex buffers.
+///
+/// The shader's vertex entry point generates the triangle from the vertex index.
+#[allow(clippy::too_many_arguments)]
+pub fn create_full_screen_pipeline(
+    label: &str,
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    fragment_shader_entry: &str,
+    pipeline_layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some(VERTEX_SHADER_ENTRY),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment_shader_entry),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil,
+        multisample: wgpu::MultisampleState::default(),
+        multiview_mask: None,
+        cache: None,
+    })
+}
+
 fn variant_label(base_label: &str, variant: PipelineVariant) -> String {
     match variant {
         PipelineVariant::Standard => base_label.to_owned(),
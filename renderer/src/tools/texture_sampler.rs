@@ -13,3 +13,15 @@ pub fn linear_clamping(device: &Device) -> Sampler {
         ..Default::default()
     })
 }
+
+/// Creates a nearest neighbor and edge clamping texture sampler.
+pub fn nearest_clamping(device: &Device) -> Sampler {
+    device.create_sampler(&SamplerDescriptor {
+        label: Some("Nearest / Clamping Texture Sampler"),
+        address_mode_u: AddressMode::ClampToEdge,
+        address_mode_v: AddressMode::ClampToEdge,
+        mag_filter: FilterMode::Nearest,
+        min_filter: FilterMode::Nearest,
+        ..Default::default()
+    })
+}
@@ -9,6 +9,13 @@ use derive_more::Deref;
 pub struct Id(u32);
 
 impl Id {
+    /// Recreates an id from its raw value, for example when recorded changes are replayed.
+    ///
+    /// The id is not acquired, so it must not be used in the identity space of a live scene.
+    pub fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
     pub fn to_usize(self) -> usize {
         self.0 as _
     }
@@ -28,6 +28,13 @@ pub struct Visual {
     /// An optional clip bounds in model space 2D only.
     pub clip_bounds: Option<Bounds>,
 
+    /// Blurs everything rendered behind this visual, like CSS `backdrop-filter: blur()`.
+    ///
+    /// The value is the standard deviation of the blur in pixels. The blurred backdrop covers the
+    /// bounds of the visual's shapes, text is not included. Visuals with a backdrop blur render
+    /// after all other visuals, so that their backdrop is complete.
+    pub backdrop_blur: Option<f32>,
+
     /// DR: Clients should be able to use [`Visual`] directly as an abstract thing. Like for
     /// example a line which contains multiple Shapes (runs, quads, etc.). Therefore, `Vec<Shape>`
     /// and not just `Shape`.
@@ -46,6 +53,7 @@ impl Visual {
             location: location.into(),
             decal_order: None,
             clip_bounds: None,
+            backdrop_blur: None,
             shapes: shapes.into(),
         }
     }
@@ -68,6 +76,13 @@ impl Visual {
             ..self
         }
     }
+
+    pub fn with_backdrop_blur(self, blur_radius: f32) -> Self {
+        Self {
+            backdrop_blur: Some(blur_radius),
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -75,6 +90,7 @@ pub struct VisualRenderObj {
     pub location: Id,
     pub decal_order: Option<usize>,
     pub clip_bounds: Option<Bounds>,
+    pub backdrop_blur: Option<f32>,
     pub shapes: Arc<[Shape]>,
 }
 
@@ -99,6 +115,7 @@ impl Object for Visual {
             location: self.location.id(),
             decal_order: self.decal_order,
             clip_bounds: self.clip_bounds,
+            backdrop_blur: self.backdrop_blur,
             shapes: self.shapes.clone(),
         }
     }
@@ -109,6 +126,12 @@ pub struct Location {
     pub parent: Option<Ref<Location>>,
     pub transform: Ref<Transform>,
     pub alpha: f32,
+    /// Render all visuals at this location and its descendants into an offscreen layer.
+    ///
+    /// The layer is composited once with the alpha of this location, so overlapping shapes don't
+    /// show through each other when the group fades. Layers inside a layer are rendered into the
+    /// outermost one.
+    pub layer: bool,
 }
 
 impl From<Handle<Transform>> for Location {
@@ -117,6 +140,7 @@ impl From<Handle<Transform>> for Location {
             parent: None,
             transform: transform.into(),
             alpha: 1.0,
+            layer: false,
         }
     }
 }
@@ -127,6 +151,7 @@ impl Location {
             parent,
             transform: transform.into(),
             alpha: 1.0,
+            layer: false,
         }
     }
 
@@ -139,6 +164,11 @@ impl Location {
         self.alpha = normalize_alpha(alpha);
         self
     }
+
+    pub fn with_layer(mut self, layer: bool) -> Self {
+        self.layer = layer;
+        self
+    }
 }
 
 // This allows `Into<Handle<Location>>` to take either a reference or an owned handle.
@@ -162,6 +192,7 @@ impl Object for Location {
             parent,
             transform,
             alpha: normalize_alpha(self.alpha),
+            layer: self.layer,
         }
     }
 }
@@ -171,6 +202,7 @@ pub struct LocationRenderObj {
     pub parent: Option<Id>,
     pub transform: Id,
     pub alpha: f32,
+    pub layer: bool,
 }
 
 fn normalize_alpha(alpha: f32) -> f32 {
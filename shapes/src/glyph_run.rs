@@ -21,6 +21,11 @@ pub struct GlyphRun {
     // we may need to remove it from there and use our own "CacheKey" like struct.
     pub text_weight: TextWeight,
     pub glyphs: Vec<RunGlyph>,
+    /// Underlines, strikethroughs and background highlights, `None` if there are none.
+    ///
+    /// Boxed, so that the decorations don't grow every [`Shape`](crate::Shape) by the size of a
+    /// `Vec`.
+    pub decorations: Option<Box<Vec<RunDecoration>>>,
 }
 
 impl GlyphRun {
@@ -37,9 +42,19 @@ impl GlyphRun {
             text_color,
             text_weight,
             glyphs,
+            decorations: None,
         }
     }
 
+    pub fn with_decorations(mut self, decorations: Vec<RunDecoration>) -> Self {
+        self.decorations = (!decorations.is_empty()).then(|| decorations.into());
+        self
+    }
+
+    pub fn decorations(&self) -> &[RunDecoration] {
+        self.decorations.as_deref().map_or(&[], Vec::as_slice)
+    }
+
     pub fn with_color(mut self, text_color: Color) -> Self {
         self.text_color = text_color;
         self
@@ -57,6 +72,15 @@ impl GlyphRun {
 
         ((left, top).into(), (right, bottom).into())
     }
+
+    /// Translate a decoration's rectangle to the coordinate system of the run.
+    pub fn place_decoration(&self, decoration: &RunDecoration) -> (IVec2, IVec2) {
+        let (left, top) = decoration.pos;
+        let right = left + decoration.size.0 as i32;
+        let bottom = top + decoration.size.1 as i32;
+
+        ((left, top).into(), (right, bottom).into())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -113,6 +137,44 @@ impl TextWeight {
     pub const BLACK: Self = Self(900);
 }
 
+#[derive(
+    Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum TextStyle {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl TextStyle {
+    pub fn is_slanted(&self) -> bool {
+        !matches!(self, Self::Normal)
+    }
+}
+
+/// The width of a font face in percent of its normal width.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TextStretch(pub u16);
+
+impl Default for TextStretch {
+    fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+impl TextStretch {
+    pub const ULTRA_CONDENSED: Self = Self(50);
+    pub const EXTRA_CONDENSED: Self = Self(62);
+    pub const CONDENSED: Self = Self(75);
+    pub const SEMI_CONDENSED: Self = Self(87);
+    pub const NORMAL: Self = Self(100);
+    pub const SEMI_EXPANDED: Self = Self(112);
+    pub const EXPANDED: Self = Self(125);
+    pub const EXTRA_EXPANDED: Self = Self(150);
+    pub const ULTRA_EXPANDED: Self = Self(200);
+}
+
 /// A glyph inside a [`GlyphRun`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct RunGlyph {
@@ -141,12 +203,53 @@ impl RunGlyph {
     }
 }
 
+/// A solid rectangle that is drawn with the glyphs of a [`GlyphRun`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunDecoration {
+    pub kind: DecorationKind,
+    /// The left / top position in pixels, relative to the run like the glyph positions.
+    ///
+    /// The baseline is at `max_ascent`.
+    pub pos: (i32, i32),
+    /// Width and height in pixels.
+    pub size: (u32, u32),
+    pub color: Color,
+}
+
+impl RunDecoration {
+    pub fn new(kind: DecorationKind, pos: (i32, i32), size: (u32, u32), color: Color) -> Self {
+        Self {
+            kind,
+            pos,
+            size,
+            color,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DecorationKind {
+    /// Drawn behind the glyphs, covering the height of the line (e.g. a selection).
+    Background,
+    Underline,
+    Strikethrough,
+}
+
+impl DecorationKind {
+    pub fn is_background(&self) -> bool {
+        matches!(self, Self::Background)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct GlyphKey {
     pub font_id: fontdb::ID,
     pub glyph_id: u16,
     pub font_size_bits: u32,
     pub weight: TextWeight,
+    /// The requested style. If the face is not slanted, an oblique is synthesized.
+    pub style: TextStyle,
+    pub stretch: TextStretch,
 }
 
 impl GlyphKey {
@@ -156,6 +259,18 @@ impl GlyphKey {
             glyph_id,
             font_size_bits: font_size.to_bits(),
             weight,
+            style: TextStyle::default(),
+            stretch: TextStretch::default(),
         }
     }
+
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_stretch(mut self, stretch: TextStretch) -> Self {
+        self.stretch = stretch;
+        self
+    }
 }
@@ -0,0 +1,94 @@
+use std::{
+    ops,
+    sync::{Arc, Weak},
+};
+
+use anyhow::{Result, bail};
+
+use massive_geometry::SizePx;
+
+/// RGBA8 pixels with straight alpha, rows are tightly packed.
+#[derive(Debug)]
+pub struct ImageData {
+    size: SizePx,
+    pixels: Vec<u8>,
+}
+
+impl ImageData {
+    pub fn new(size: impl Into<SizePx>, pixels: Vec<u8>) -> Result<Self> {
+        let size = size.into();
+        let expected = size.width as usize * size.height as usize * 4;
+        if pixels.len() != expected {
+            bail!(
+                "Pixel data of {} bytes does not match the image size {}x{}",
+                pixels.len(),
+                size.width,
+                size.height
+            );
+        }
+        Ok(Self { size, pixels })
+    }
+
+    /// Decode a PNG or JPEG image.
+    #[cfg(feature = "decoding")]
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        use anyhow::Context;
+
+        let image = ::image::load_from_memory(bytes)
+            .context("Decoding image")?
+            .into_rgba8();
+        Self::new(image.dimensions(), image.into_raw())
+    }
+
+    pub fn size(&self) -> SizePx {
+        self.size
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+/// A reference counted image resource.
+///
+/// The renderer uploads the pixels once and releases them on the GPU after the last handle is
+/// dropped. Handles compare equal if they refer to the same image.
+#[derive(Debug, Clone)]
+pub struct ImageHandle(Arc<ImageData>);
+
+impl ImageHandle {
+    pub fn new(data: ImageData) -> Self {
+        Self(Arc::new(data))
+    }
+
+    pub fn downgrade(&self) -> Weak<ImageData> {
+        Arc::downgrade(&self.0)
+    }
+
+    /// Identifies the image as long as a handle to it exists.
+    pub fn as_ptr(&self) -> *const ImageData {
+        Arc::as_ptr(&self.0)
+    }
+}
+
+impl From<ImageData> for ImageHandle {
+    fn from(data: ImageData) -> Self {
+        Self::new(data)
+    }
+}
+
+impl ops::Deref for ImageHandle {
+    type Target = ImageData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl PartialEq for ImageHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ImageHandle {}
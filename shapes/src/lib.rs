@@ -1,9 +1,15 @@
 mod glyph_run;
+mod image;
+mod paint;
+mod path;
 mod shape;
 mod text_shaper;
 
 use derive_more::{Deref, DerefMut};
 pub use glyph_run::*;
+pub use image::*;
+pub use paint::*;
+pub use path::*;
 pub use shape::*;
 pub use text_shaper::*;
 
@@ -55,6 +61,23 @@ impl<'a> SizedTextShaper<'a> {
     pub fn shape(self, font_system: &mut FontSystem) -> Option<GlyphRun> {
         self.layouter.layout(font_system, self.font_size)
     }
+
+    pub fn shape_with_missing_glyphs(
+        self,
+        font_system: &mut FontSystem,
+    ) -> (Option<GlyphRun>, Vec<MissingGlyph>) {
+        self.layouter
+            .layout_with_missing_glyphs(font_system, self.font_size)
+    }
+
+    pub fn shape_paragraph(
+        self,
+        font_system: &mut FontSystem,
+        style: &ParagraphStyle,
+    ) -> ShapedParagraph {
+        self.layouter
+            .layout_paragraph(font_system, self.font_size, style)
+    }
 }
 
 pub trait Size<'b> {
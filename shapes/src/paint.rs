@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use derive_more::From;
+
+use massive_geometry::{Color, Point};
+
+/// How the area of a shape is colored.
+///
+/// Gradient geometry is specified in unit coordinates relative to the shape's rect: `(0, 0)` is
+/// the top left corner, `(1, 1)` the bottom right corner. Gradients are shared, so cloning a paint
+/// does not copy the stops.
+#[derive(Debug, Clone, PartialEq, From)]
+pub enum Paint {
+    Solid(Color),
+    LinearGradient(Arc<LinearGradient>),
+    RadialGradient(Arc<RadialGradient>),
+}
+
+impl Paint {
+    pub fn linear_gradient(
+        start: impl Into<Point>,
+        end: impl Into<Point>,
+        stops: impl IntoIterator<Item = impl Into<GradientStop>>,
+    ) -> Self {
+        LinearGradient {
+            start: start.into(),
+            end: end.into(),
+            stops: stops.into_iter().map(Into::into).collect(),
+        }
+        .into()
+    }
+
+    /// A radial gradient. Because of the unit coordinates, the gradient is elliptical on
+    /// non-square shapes.
+    pub fn radial_gradient(
+        center: impl Into<Point>,
+        radius: f64,
+        stops: impl IntoIterator<Item = impl Into<GradientStop>>,
+    ) -> Self {
+        RadialGradient {
+            center: center.into(),
+            radius,
+            stops: stops.into_iter().map(Into::into).collect(),
+        }
+        .into()
+    }
+
+    /// The gradient stops, or `None` for a solid paint.
+    pub fn stops(&self) -> Option<&[GradientStop]> {
+        match self {
+            Paint::Solid(_) => None,
+            Paint::LinearGradient(gradient) => Some(&gradient.stops),
+            Paint::RadialGradient(gradient) => Some(&gradient.stops),
+        }
+    }
+}
+
+impl From<LinearGradient> for Paint {
+    fn from(gradient: LinearGradient) -> Self {
+        Self::LinearGradient(gradient.into())
+    }
+}
+
+impl From<RadialGradient> for Paint {
+    fn from(gradient: RadialGradient) -> Self {
+        Self::RadialGradient(gradient.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// Position along the gradient, from 0 to 1.
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl<C: Into<Color>> From<(f32, C)> for GradientStop {
+    fn from((offset, color): (f32, C)) -> Self {
+        Self {
+            offset,
+            color: color.into(),
+        }
+    }
+}
+
+/// A gradient along the line from `start` to `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearGradient {
+    pub start: Point,
+    pub end: Point,
+    pub stops: Vec<GradientStop>,
+}
+
+/// A gradient from `center` (offset 0) to the circle with `radius` (offset 1).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialGradient {
+    pub center: Point,
+    pub radius: f64,
+    pub stops: Vec<GradientStop>,
+}
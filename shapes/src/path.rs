@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use massive_geometry::{self as geometry, Color, CubicBezier, Point};
+
+/// A vector path made of one or more sub-paths, filled, stroked, or both.
+///
+/// Coordinates are in model space, like the rects of the other shapes. The segments are shared, so
+/// cloning a path (e.g. to change its color) does not copy them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub segments: Arc<[PathSegment]>,
+    pub fill: Option<PathFill>,
+    pub stroke: Option<PathStroke>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    /// Begins a new sub-path. An open sub-path is ended without closing it.
+    MoveTo(Point),
+    LineTo(Point),
+    /// A quadratic bezier curve: control point, end point.
+    QuadTo(Point, Point),
+    /// A cubic bezier curve: first control point, second control point, end point.
+    CubicTo(Point, Point, Point),
+    /// Closes the current sub-path with a line back to its start.
+    Close,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FillRule {
+    #[default]
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathFill {
+    pub rule: FillRule,
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStroke {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    pub color: Color,
+}
+
+impl PathStroke {
+    pub fn new(width: f32, color: impl Into<Color>) -> Self {
+        Self {
+            width,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            color: color.into(),
+        }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+}
+
+impl Path {
+    /// Create a path that is neither filled nor stroked yet.
+    pub fn new(segments: impl Into<Arc<[PathSegment]>>) -> Self {
+        Self {
+            segments: segments.into(),
+            fill: None,
+            stroke: None,
+        }
+    }
+
+    pub fn builder() -> PathBuilder {
+        PathBuilder::default()
+    }
+
+    /// Fill the path using the [`FillRule::NonZero`] rule.
+    pub fn filled(self, color: impl Into<Color>) -> Self {
+        self.filled_with(FillRule::NonZero, color)
+    }
+
+    pub fn filled_with(mut self, rule: FillRule, color: impl Into<Color>) -> Self {
+        self.fill = Some(PathFill {
+            rule,
+            color: color.into(),
+        });
+        self
+    }
+
+    pub fn stroked(mut self, stroke: PathStroke) -> Self {
+        self.stroke = Some(stroke);
+        self
+    }
+
+    /// The bounds of all points including the control points of curves, so they contain the
+    /// filled area. The stroke width is not included.
+    pub fn bounds(&self) -> Option<geometry::Rect> {
+        let mut points = self.segments.iter().flat_map(PathSegment::points);
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        });
+        Some((min, max).into())
+    }
+
+    /// Transform all points, for example to translate or scale the path.
+    pub fn map_points(self, f: impl Fn(Point) -> Point) -> Self {
+        let segments: Vec<_> = self
+            .segments
+            .iter()
+            .map(|segment| match *segment {
+                PathSegment::MoveTo(to) => PathSegment::MoveTo(f(to)),
+                PathSegment::LineTo(to) => PathSegment::LineTo(f(to)),
+                PathSegment::QuadTo(control, to) => PathSegment::QuadTo(f(control), f(to)),
+                PathSegment::CubicTo(control1, control2, to) => {
+                    PathSegment::CubicTo(f(control1), f(control2), f(to))
+                }
+                PathSegment::Close => PathSegment::Close,
+            })
+            .collect();
+        Self {
+            segments: segments.into(),
+            ..self
+        }
+    }
+}
+
+impl PathSegment {
+    /// The end point and control points of this segment.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let (control1, control2, to) = match *self {
+            PathSegment::MoveTo(to) | PathSegment::LineTo(to) => (None, None, Some(to)),
+            PathSegment::QuadTo(control, to) => (Some(control), None, Some(to)),
+            PathSegment::CubicTo(control1, control2, to) => {
+                (Some(control1), Some(control2), Some(to))
+            }
+            PathSegment::Close => (None, None, None),
+        };
+        control1.into_iter().chain(control2).chain(to)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PathBuilder {
+    segments: Vec<PathSegment>,
+}
+
+impl PathBuilder {
+    pub fn move_to(mut self, to: impl Into<Point>) -> Self {
+        self.segments.push(PathSegment::MoveTo(to.into()));
+        self
+    }
+
+    pub fn line_to(mut self, to: impl Into<Point>) -> Self {
+        self.segments.push(PathSegment::LineTo(to.into()));
+        self
+    }
+
+    pub fn quad_to(mut self, control: impl Into<Point>, to: impl Into<Point>) -> Self {
+        self.segments
+            .push(PathSegment::QuadTo(control.into(), to.into()));
+        self
+    }
+
+    pub fn cubic_to(
+        mut self,
+        control1: impl Into<Point>,
+        control2: impl Into<Point>,
+        to: impl Into<Point>,
+    ) -> Self {
+        self.segments.push(PathSegment::CubicTo(
+            control1.into(),
+            control2.into(),
+            to.into(),
+        ));
+        self
+    }
+
+    /// Append a cubic bezier as a new sub-path.
+    pub fn bezier(self, bezier: &CubicBezier) -> Self {
+        self.move_to(bezier.start)
+            .cubic_to(bezier.span1, bezier.span2, bezier.end)
+    }
+
+    pub fn close(mut self) -> Self {
+        self.segments.push(PathSegment::Close);
+        self
+    }
+
+    pub fn build(self) -> Path {
+        Path::new(self.segments)
+    }
+}
@@ -1,13 +1,15 @@
 use std::{any::Any, fmt, mem, ops};
 
 use derive_more::From;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
 use smallbox::{SmallBox, smallbox};
 
 use massive_geometry::{self as geometry, Color, Size};
 
-use crate::GlyphRun;
+use crate::{GlyphRun, ImageHandle, Paint, Path};
 
-// Architecture: Every one except custom has a color field, can we do something about that?
+// Architecture: Every one except custom has a color or paint field, can we do something about that?
 #[derive(Debug, Clone, From, PartialEq)]
 pub enum Shape {
     Rect(Rect),
@@ -16,11 +18,14 @@ pub enum Shape {
     Ellipse(Ellipse),
     BeveledRect(BeveledRect),
     StrokeRect(StrokeRect),
+    BoxShadow(BoxShadow),
+    Image(Image),
+    Path(Path),
     GlyphRun(GlyphRun),
     Custom(Custom),
 }
 
-const CUSTOM_EMBEDDED_SIZE: usize = 7;
+const CUSTOM_EMBEDDED_SIZE: usize = 8;
 
 const _: () = {
     // GlyphRun is expected to be the biggest contender. If that changes, we want to know.
@@ -57,14 +62,14 @@ impl Shape {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Rect {
     pub rect: geometry::Rect,
-    pub color: Color,
+    pub paint: Paint,
 }
 
 impl Rect {
-    pub fn new(rect: impl Into<geometry::Rect>, color: impl Into<Color>) -> Self {
+    pub fn new(rect: impl Into<geometry::Rect>, paint: impl Into<Paint>) -> Self {
         Self {
             rect: rect.into(),
-            color: color.into(),
+            paint: paint.into(),
         }
     }
 }
@@ -73,19 +78,19 @@ impl Rect {
 pub struct RoundRect {
     pub rect: geometry::Rect,
     pub corner_radius: f32,
-    pub color: Color,
+    pub paint: Paint,
 }
 
 impl RoundRect {
     pub fn new(
         rect: impl Into<geometry::Rect>,
         corner_radius: f32,
-        color: impl Into<Color>,
+        paint: impl Into<Paint>,
     ) -> Self {
         Self {
             rect: rect.into(),
             corner_radius,
-            color: color.into(),
+            paint: paint.into(),
         }
     }
 }
@@ -93,14 +98,14 @@ impl RoundRect {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Circle {
     pub rect: geometry::Rect,
-    pub color: Color,
+    pub paint: Paint,
 }
 
 impl Circle {
-    pub fn new(rect: impl Into<geometry::Rect>, color: impl Into<Color>) -> Self {
+    pub fn new(rect: impl Into<geometry::Rect>, paint: impl Into<Paint>) -> Self {
         Self {
             rect: rect.into(),
-            color: color.into(),
+            paint: paint.into(),
         }
     }
 }
@@ -108,14 +113,14 @@ impl Circle {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Ellipse {
     pub rect: geometry::Rect,
-    pub color: Color,
+    pub paint: Paint,
 }
 
 impl Ellipse {
-    pub fn new(rect: impl Into<geometry::Rect>, color: impl Into<Color>) -> Self {
+    pub fn new(rect: impl Into<geometry::Rect>, paint: impl Into<Paint>) -> Self {
         Self {
             rect: rect.into(),
-            color: color.into(),
+            paint: paint.into(),
         }
     }
 }
@@ -136,16 +141,16 @@ pub struct BeveledRect {
     /// Bitmask controlling which corners are beveled (clockwise from top-left).
     /// Default: 0b1111 (all corners beveled).
     pub corner_mask: u8,
-    pub color: Color,
+    pub paint: Paint,
 }
 
 impl BeveledRect {
-    pub fn new(rect: impl Into<geometry::Rect>, chamfer: f32, color: impl Into<Color>) -> Self {
+    pub fn new(rect: impl Into<geometry::Rect>, chamfer: f32, paint: impl Into<Paint>) -> Self {
         Self {
             rect: rect.into(),
             chamfer,
             corner_mask: 0b1111, // All corners beveled by default
-            color: color.into(),
+            paint: paint.into(),
         }
     }
 
@@ -203,6 +208,98 @@ impl StrokeRect {
     }
 }
 
+/// The shadow of a rect or a rounded rect.
+///
+/// The shadow is computed analytically, so it is smooth at any blur radius. Like in CSS, the
+/// shadow is drawn below the rect and not clipped by it, so it should be placed before the shape
+/// it belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoxShadow {
+    /// The rect that casts the shadow.
+    pub rect: geometry::Rect,
+    pub corner_radius: f32,
+    pub offset: Vec2,
+    /// The blur radius. The shadow's edge fades out over this distance.
+    pub blur_radius: f32,
+    /// Grows (positive) or shrinks (negative) the shadow before it is blurred.
+    pub spread: f32,
+    pub color: Color,
+}
+
+impl BoxShadow {
+    pub fn new(
+        rect: impl Into<geometry::Rect>,
+        corner_radius: f32,
+        blur_radius: f32,
+        color: impl Into<Color>,
+    ) -> Self {
+        Self {
+            rect: rect.into(),
+            corner_radius,
+            offset: Vec2::ZERO,
+            blur_radius,
+            spread: 0.0,
+            color: color.into(),
+        }
+    }
+
+    pub fn with_offset(mut self, offset: impl Into<Vec2>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// The rect of the shadow before it is blurred: offset and grown by the spread.
+    pub fn shadow_rect(&self) -> geometry::Rect {
+        let offset = geometry::Vector::new(self.offset.x as f64, self.offset.y as f64);
+        let spread = self.spread as f64;
+        (self.rect + offset).with_outset((spread, spread))
+    }
+}
+
+/// An image drawn into a rect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub rect: geometry::Rect,
+    /// The part of the image that is shown: top left and bottom right in normalized coordinates.
+    pub uv: (Vec2, Vec2),
+    pub sampling: ImageSampling,
+    pub image: ImageHandle,
+}
+
+impl Image {
+    pub fn new(rect: impl Into<geometry::Rect>, image: impl Into<ImageHandle>) -> Self {
+        Self {
+            rect: rect.into(),
+            uv: (Vec2::ZERO, Vec2::ONE),
+            sampling: ImageSampling::default(),
+            image: image.into(),
+        }
+    }
+
+    pub fn with_uv(mut self, top_left: impl Into<Vec2>, bottom_right: impl Into<Vec2>) -> Self {
+        self.uv = (top_left.into(), bottom_right.into());
+        self
+    }
+
+    pub fn with_sampling(mut self, sampling: ImageSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ImageSampling {
+    #[default]
+    Linear,
+    /// Pixelated, for pixel art or pixel exact rendering.
+    Nearest,
+}
+
 type CustomSmallBox = SmallBox<dyn CustomShape, [usize; CUSTOM_EMBEDDED_SIZE]>;
 
 #[derive(Debug, PartialEq)]
@@ -1,13 +1,22 @@
-use std::ops::Range;
+use std::{mem, ops::Range};
 
 use cosmic_text::{
-    Attrs, AttrsList, BufferLine, Ellipsize, Family, FontSystem, Hinting, LayoutGlyph, LayoutLine,
-    LineEnding, Shaping, Weight, Wrap,
+    Align, Attrs, AttrsList, BufferLine, Ellipsize, EllipsizeHeightLimit, Family, FontSystem,
+    Hinting, LayoutGlyph, LayoutLine, LineEnding, LineIter, Metrics, Shaping, Stretch, Style,
+    UnderlineStyle, Weight, Wrap, fontdb,
 };
 
-use massive_geometry::Color;
+use massive_geometry::{Color, SizePx, Vector3};
 
-use crate::{GlyphKey, GlyphRun, GlyphRunMetrics, RunGlyph, TextWeight};
+use crate::{
+    DecorationKind, GlyphKey, GlyphRun, GlyphRunMetrics, RunDecoration, RunGlyph, TextStretch,
+    TextStyle, TextWeight,
+};
+
+/// The default distance between two baselines relative to the font size.
+pub const DEFAULT_LINE_HEIGHT: f32 = 1.2;
+
+const ELLIPSIS: char = '\u{2026}';
 
 #[derive(Debug)]
 pub struct TextShaper<'a> {
@@ -21,7 +30,15 @@ pub struct TextShaper<'a> {
 pub struct TextAttributes<'a> {
     family: Family<'a>,
     weight: TextWeight,
+    style: TextStyle,
+    stretch: TextStretch,
     color: Color,
+    /// The font size in pixels. Without, the font size passed to the layout functions is used.
+    font_size: Option<f32>,
+    underline: Option<Color>,
+    strikethrough: Option<Color>,
+    /// A highlight behind the glyphs (e.g. a selection).
+    background: Option<Color>,
 }
 
 impl Default for TextAttributes<'_> {
@@ -29,7 +46,13 @@ impl Default for TextAttributes<'_> {
         Self {
             family: Family::SansSerif,
             weight: TextWeight::default(),
+            style: TextStyle::default(),
+            stretch: TextStretch::default(),
             color: Color::BLACK,
+            font_size: None,
+            underline: None,
+            strikethrough: None,
+            background: None,
         }
     }
 }
@@ -45,19 +68,209 @@ impl<'a> TextAttributes<'a> {
         self
     }
 
+    /// Families without a slanted face get a synthesized oblique.
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_stretch(mut self, stretch: TextStretch) -> Self {
+        self.stretch = stretch;
+        self
+    }
+
     pub fn with_color(mut self, color: Color) -> Self {
         self.color = color;
         self
     }
 
-    fn to_attrs(&self) -> Attrs<'a> {
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    pub fn with_underline(mut self, color: Color) -> Self {
+        self.underline = Some(color);
+        self
+    }
+
+    pub fn with_strikethrough(mut self, color: Color) -> Self {
+        self.strikethrough = Some(color);
+        self
+    }
+
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// `metadata` identifies the attributes of the laid out glyphs.
+    fn to_attrs(&self, metadata: usize, line_height: f32) -> Attrs<'a> {
         // Performance: Don't add defaults.
-        Attrs::new()
+        let mut attrs = Attrs::new()
             .family(self.family)
             .weight(Weight(self.weight.0))
+            .style(self.style.into())
+            .stretch(self.stretch.into())
+            .metadata(metadata);
+        if let Some(font_size) = self.font_size {
+            attrs = attrs.metrics(Metrics::new(font_size, font_size * line_height));
+        }
+        // cosmic-text provides the font's decoration metrics for the spans that have them.
+        if self.underline.is_some() {
+            attrs = attrs.underline(UnderlineStyle::Single);
+        }
+        if self.strikethrough.is_some() {
+            attrs = attrs.strikethrough();
+        }
+        attrs
+    }
+}
+
+/// How a paragraph is broken into lines and how the lines are placed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParagraphStyle {
+    /// Lines longer than this are wrapped. Without, lines only break at line endings.
+    pub max_width: Option<f32>,
+    pub wrap: TextWrap,
+    /// Alignment needs a `max_width` to align to.
+    pub align: TextAlign,
+    /// The distance between two baselines relative to the font size.
+    ///
+    /// Lines that contain different font sizes use the largest line height.
+    pub line_height: f32,
+    /// Lines beyond are dropped and the last line that is shown ends with an ellipsis.
+    pub max_lines: Option<usize>,
+}
+
+impl Default for ParagraphStyle {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            wrap: TextWrap::default(),
+            align: TextAlign::default(),
+            line_height: DEFAULT_LINE_HEIGHT,
+            max_lines: None,
+        }
+    }
+}
+
+impl ParagraphStyle {
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: TextWrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
     }
 }
 
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum TextWrap {
+    /// Wraps between words. Words that do not fit on a line by themselves are wrapped between
+    /// glyphs.
+    #[default]
+    Word,
+    /// Wraps between glyphs.
+    Glyph,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justified,
+}
+
+impl From<TextWrap> for Wrap {
+    fn from(wrap: TextWrap) -> Self {
+        match wrap {
+            TextWrap::Word => Wrap::WordOrGlyph,
+            TextWrap::Glyph => Wrap::Glyph,
+        }
+    }
+}
+
+impl From<TextStyle> for Style {
+    fn from(style: TextStyle) -> Self {
+        match style {
+            TextStyle::Normal => Style::Normal,
+            TextStyle::Italic => Style::Italic,
+            TextStyle::Oblique => Style::Oblique,
+        }
+    }
+}
+
+impl From<TextStretch> for Stretch {
+    /// The nearest stretch that is at least as wide.
+    fn from(stretch: TextStretch) -> Self {
+        match stretch.0 {
+            ..=50 => Stretch::UltraCondensed,
+            51..=62 => Stretch::ExtraCondensed,
+            63..=75 => Stretch::Condensed,
+            76..=87 => Stretch::SemiCondensed,
+            88..=100 => Stretch::Normal,
+            101..=112 => Stretch::SemiExpanded,
+            113..=125 => Stretch::Expanded,
+            126..=150 => Stretch::ExtraExpanded,
+            _ => Stretch::UltraExpanded,
+        }
+    }
+}
+
+impl From<TextAlign> for Align {
+    fn from(align: TextAlign) -> Self {
+        match align {
+            TextAlign::Left => Align::Left,
+            TextAlign::Center => Align::Center,
+            TextAlign::Right => Align::Right,
+            TextAlign::Justified => Align::Justified,
+        }
+    }
+}
+
+/// The lines of a laid out paragraph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapedParagraph {
+    /// The runs of the lines, translated to the top of their line.
+    ///
+    /// Every line has at least one run, and is split into more where the text color changes.
+    pub runs: Vec<GlyphRun>,
+    /// The size of all lines, including their line height.
+    pub size: SizePx,
+    /// The parts of the text no font had a glyph for.
+    pub missing_glyphs: Vec<MissingGlyph>,
+}
+
+/// A part of the text no font had a glyph for. It is rendered with the replacement glyph of the
+/// font, usually an empty box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingGlyph {
+    /// The byte range in the text.
+    pub range: Range<usize>,
+    /// The face the replacement glyph is taken from.
+    pub font_id: fontdb::ID,
+}
+
 impl<'a> TextShaper<'a> {
     /// Creates a default text shaper that uses the Sans-Serif family.
     pub fn new(text: &'a str) -> Self {
@@ -77,16 +290,37 @@ impl<'a> TextShaper<'a> {
         self.range_attributes.push((range, attributes))
     }
 
-    // Feature: Why is there only one FontSize here? Check out parley.
+    /// Lays out the text in a single line.
+    ///
+    /// Use [`TextShaper::layout_paragraph`] for text that contains line endings or needs to be
+    /// wrapped.
+    ///
+    /// Parts of the text no font had a glyph for are logged, use
+    /// [`TextShaper::layout_with_missing_glyphs`] to handle them.
     pub fn layout(self, font_system: &mut FontSystem, font_size: f32) -> Option<GlyphRun> {
+        let text = self.text;
+        let (run, missing) = self.layout_with_missing_glyphs(font_system, font_size);
+        if !missing.is_empty() {
+            let missing: String = missing
+                .iter()
+                .filter_map(|glyph| text.get(glyph.range.clone()))
+                .collect();
+            log::warn!("No font has glyphs for {missing:?} in {text:?}");
+        }
+        run
+    }
+
+    /// Lays out the text in a single line like [`TextShaper::layout`] and reports the parts of the
+    /// text no font had a glyph for.
+    pub fn layout_with_missing_glyphs(
+        self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+    ) -> (Option<GlyphRun>, Vec<MissingGlyph>) {
         // Performance: BufferLine makes a copy of the text, is there a better way?
         // Performance: Under the hood, HarfRust is used for text shaping, use it directly?
         // Performance: Shaping maintains internal caches, which might benefit reusing them.
-        let mut attrs_list = AttrsList::new(&self.default_attributes.to_attrs());
-        for (range, attrs) in self.range_attributes {
-            attrs_list.add_span(range, &attrs.to_attrs());
-        }
-
+        let attrs_list = self.attrs_list(0..self.text.len(), DEFAULT_LINE_HEIGHT);
         let mut buffer =
             BufferLine::new(self.text, LineEnding::None, attrs_list, Shaping::Advanced);
 
@@ -112,32 +346,309 @@ impl<'a> TextShaper<'a> {
             Hinting::Disabled,
         );
         if layouted_lines.is_empty() {
-            return None;
+            return (None, Vec::new());
         }
 
-        // Use the first line for metrics (for now).
-        // Feature: Support multi-line layout.
+        // Without wrapping, there is only one line.
         let metrics_line = &layouted_lines[0];
         let metrics = metrics(metrics_line);
+        let decorations = self.decorations(metrics_line, &metrics);
 
         let layouted_glyphs = layouted_lines.iter().flat_map(|l| &l.glyphs);
+        let missing = missing_glyphs(self.text, 0, layouted_glyphs.clone()).collect();
 
         // Performance: Is there a better way to estimate the number of resulting glyphs?
         let mut glyphs = Vec::with_capacity(self.text.len());
         for glyph in layouted_glyphs {
             // Optimization: Don't pass empty / blank glyphs.
-            glyphs.push(position_glyph(glyph));
+            glyphs.push(position_glyph(glyph, self.attributes(glyph.metadata)));
         }
 
-        Some(GlyphRun {
-            translation: Default::default(),
+        let run = GlyphRun::new(
+            Vector3::default(),
             metrics,
-            text_color: self.default_attributes.color,
+            self.default_attributes.color,
             // This looks redundant here. Isn't this specified by each Glyph?
-            text_weight: self.default_attributes.weight,
+            self.default_attributes.weight,
             glyphs,
+        )
+        .with_decorations(decorations);
+        (Some(run), missing)
+    }
+
+    /// Lays out the text in lines that are broken at line endings and wrapped according to
+    /// `style`.
+    ///
+    /// Empty lines produce runs without glyphs.
+    pub fn layout_paragraph(
+        self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        style: &ParagraphStyle,
+    ) -> ShapedParagraph {
+        let font_size = self.default_attributes.font_size.unwrap_or(font_size);
+        let default_attributes = TextAttributes {
+            font_size: Some(font_size),
+            ..self.default_attributes
+        };
+        let shaper = Self {
+            default_attributes,
+            ..self
+        };
+
+        let mut remaining_lines = style.max_lines.unwrap_or(usize::MAX);
+
+        let mut runs = Vec::new();
+        let mut missing = Vec::new();
+        let mut top = 0.0;
+        let mut width: u32 = 0;
+
+        let mut text_lines = LineIter::new(shaper.text).peekable();
+        while let Some((range, ending)) = text_lines.next() {
+            if remaining_lines == 0 {
+                break;
+            }
+            let line_start = range.start;
+            let text = &shaper.text[range.clone()];
+            let mut buffer = shaper.buffer_line(text, range.clone(), ending, style);
+            let line_count = shaper
+                .layout_buffer(&mut buffer, font_system, font_size, style, remaining_lines)
+                .len();
+            if style.max_lines.is_some()
+                && line_count >= remaining_lines
+                && text_lines.peek().is_some()
+            {
+                // Lines are cut at the line ending, so the last line shown must end with an
+                // ellipsis, too. If the text does not fit with it, cosmic-text ellipsizes it.
+                buffer = shaper.buffer_line(format!("{text}{ELLIPSIS}"), range, ending, style);
+            }
+            let layouted_lines =
+                shaper.layout_buffer(&mut buffer, font_system, font_size, style, remaining_lines);
+
+            for line in layouted_lines.iter().take(remaining_lines) {
+                let line_height = line
+                    .line_height_opt
+                    .unwrap_or(font_size * style.line_height);
+                // Center the glyphs vertically inside the line, like cosmic-text's Buffer does.
+                let centering_offset = (line_height - (line.max_ascent + line.max_descent)) / 2.0;
+                let translation = Vector3::new(0.0, (top + centering_offset).round() as f64, 0.0);
+
+                let metrics = GlyphRunMetrics {
+                    // Alignment moves the glyphs away from the start of the line.
+                    width: line
+                        .glyphs
+                        .iter()
+                        .map(|glyph| glyph.x + glyph.w)
+                        .fold(line.w, f32::max)
+                        .ceil() as u32,
+                    ..metrics(line)
+                };
+                width = width.max(metrics.width);
+                missing.extend(missing_glyphs(text, line_start, &line.glyphs));
+                let mut decorations = shaper.decorations(line, &metrics);
+
+                let color = |glyph: &LayoutGlyph| shaper.attributes(glyph.metadata).color;
+                let mut color_runs = line.glyphs.chunk_by(|a, b| color(a) == color(b)).peekable();
+                if color_runs.peek().is_none() {
+                    runs.push(
+                        GlyphRun::new(
+                            translation,
+                            metrics,
+                            shaper.default_attributes.color,
+                            shaper.default_attributes.weight,
+                            Vec::new(),
+                        )
+                        .with_decorations(mem::take(&mut decorations)),
+                    );
+                }
+                for glyphs in color_runs {
+                    runs.push(
+                        GlyphRun::new(
+                            translation,
+                            metrics,
+                            color(&glyphs[0]),
+                            shaper.default_attributes.weight,
+                            glyphs
+                                .iter()
+                                .map(|glyph| {
+                                    position_glyph(glyph, shaper.attributes(glyph.metadata))
+                                })
+                                .collect(),
+                        )
+                        // All runs of a line share its metrics, so the first one takes the
+                        // decorations of the whole line.
+                        .with_decorations(mem::take(&mut decorations)),
+                    );
+                }
+                top += line_height;
+                remaining_lines -= 1;
+            }
+        }
+
+        ShapedParagraph {
+            runs,
+            size: (width, top.ceil() as u32).into(),
+            missing_glyphs: missing,
+        }
+    }
+
+    /// A line of the text in `range` for paragraph layout. `text` may differ from the text in
+    /// `range` at its end.
+    fn buffer_line(
+        &self,
+        text: impl Into<String>,
+        range: Range<usize>,
+        ending: LineEnding,
+        style: &ParagraphStyle,
+    ) -> BufferLine {
+        let attrs_list = self.attrs_list(range, style.line_height);
+        let mut buffer = BufferLine::new(text, ending, attrs_list, Shaping::Advanced);
+        buffer.set_align(Some(style.align.into()));
+        buffer
+    }
+
+    /// Lays out a line of a paragraph in at most `max_lines`, if `style` limits the lines.
+    fn layout_buffer<'b>(
+        &self,
+        buffer: &'b mut BufferLine,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        style: &ParagraphStyle,
+        max_lines: usize,
+    ) -> &'b [LayoutLine] {
+        let ellipsize = match style.max_lines {
+            Some(_) => Ellipsize::End(EllipsizeHeightLimit::Lines(max_lines)),
+            None => Ellipsize::None,
+        };
+        buffer.layout(
+            font_system,
+            font_size,
+            style.max_width,
+            style.wrap.into(),
+            ellipsize,
+            None,
+            0,
+            Hinting::Disabled,
+        )
+    }
+
+    fn attrs_list(&self, range: Range<usize>, line_height: f32) -> AttrsList {
+        let mut attrs_list = AttrsList::new(&self.default_attributes.to_attrs(0, line_height));
+        for (index, (attrs_range, attrs)) in self.range_attributes.iter().enumerate() {
+            let start = attrs_range.start.max(range.start);
+            let end = attrs_range.end.min(range.end);
+            if start < end {
+                attrs_list.add_span(
+                    start - range.start..end - range.start,
+                    &attrs.to_attrs(index + 1, line_height),
+                );
+            }
+        }
+        attrs_list
+    }
+
+    /// The attributes of the glyphs that were laid out with `metadata`.
+    fn attributes(&self, metadata: usize) -> &TextAttributes<'a> {
+        match metadata {
+            0 => &self.default_attributes,
+            index => &self.range_attributes[index - 1].1,
+        }
+    }
+
+    /// The pixel aligned decorations of a line. `metrics` are the metrics of the line's run.
+    fn decorations(&self, line: &LayoutLine, metrics: &GlyphRunMetrics) -> Vec<RunDecoration> {
+        let mut decorations = Vec::new();
+
+        for (metadata, x) in attribute_spans(&line.glyphs) {
+            if let Some(color) = self.attributes(metadata).background {
+                decorations.push(RunDecoration::new(
+                    DecorationKind::Background,
+                    (x.start, 0),
+                    (x.len() as u32, metrics.max_ascent + metrics.max_descent),
+                    color,
+                ));
+            }
+        }
+
+        let baseline = metrics.max_ascent as f32;
+        for span in &line.decorations {
+            let font_size = span.font_size;
+            for (metadata, x) in attribute_spans(&line.glyphs[span.glyph_range.clone()]) {
+                let attributes = self.attributes(metadata);
+                let lines = [
+                    (
+                        DecorationKind::Underline,
+                        attributes.underline,
+                        span.data.underline_metrics,
+                    ),
+                    (
+                        DecorationKind::Strikethrough,
+                        attributes.strikethrough,
+                        span.data.strikethrough_metrics,
+                    ),
+                ];
+                for (kind, color, line_metrics) in lines {
+                    let Some(color) = color else {
+                        continue;
+                    };
+                    // The font's offset is positive above the baseline.
+                    let top = (baseline - line_metrics.offset * font_size).round() as i32;
+                    let thickness = (line_metrics.thickness * font_size).max(1.0).ceil() as u32;
+                    decorations.push(RunDecoration::new(
+                        kind,
+                        (x.start, top),
+                        (x.len() as u32, thickness),
+                        color,
+                    ));
+                }
+            }
+        }
+
+        decorations
+    }
+}
+
+/// The glyphs that were replaced with the glyph 0 of their font, because no font had a glyph for
+/// their text. The glyphs were laid out from `line`, which starts at `line_start` in the text.
+///
+/// Glyphs past the end of `line`, like an appended ellipsis, are skipped.
+fn missing_glyphs<'a>(
+    line: &'a str,
+    line_start: usize,
+    glyphs: impl IntoIterator<Item = &'a LayoutGlyph>,
+) -> impl Iterator<Item = MissingGlyph> {
+    glyphs.into_iter().filter_map(move |glyph| {
+        // Line endings and other invisible characters are not expected to have glyphs.
+        let visible = line
+            .get(glyph.start..glyph.end)
+            .is_some_and(|text| !text.chars().all(|c| c.is_whitespace() || c.is_control()));
+        (glyph.glyph_id == 0 && visible).then_some(MissingGlyph {
+            range: line_start + glyph.start..line_start + glyph.end,
+            font_id: glyph.font_id,
         })
+    })
+}
+
+/// Consecutive glyphs that were laid out with the same attributes, and their horizontal extent in
+/// pixels.
+fn attribute_spans(glyphs: &[LayoutGlyph]) -> Vec<(usize, Range<i32>)> {
+    let mut spans: Vec<(usize, f32, f32)> = Vec::new();
+    for glyph in glyphs {
+        match spans.last_mut() {
+            Some((metadata, left, right)) if *metadata == glyph.metadata => {
+                // Glyphs of right-to-left text are stored from right to left.
+                *left = left.min(glyph.x);
+                *right = right.max(glyph.x + glyph.w);
+            }
+            _ => spans.push((glyph.metadata, glyph.x, glyph.x + glyph.w)),
+        }
     }
+
+    spans
+        .into_iter()
+        .map(|(metadata, left, right)| (metadata, left.floor() as i32..right.ceil() as i32))
+        .filter(|(_, x)| !x.is_empty())
+        .collect()
 }
 
 fn metrics(line: &LayoutLine) -> GlyphRunMetrics {
@@ -153,7 +664,7 @@ fn metrics(line: &LayoutLine) -> GlyphRunMetrics {
     }
 }
 
-fn position_glyph(glyph: &LayoutGlyph) -> RunGlyph {
+fn position_glyph(glyph: &LayoutGlyph, attributes: &TextAttributes) -> RunGlyph {
     let pos = (glyph.x.round() as i32, glyph.y.round() as i32);
 
     // Robustness: There is a function physical() in glyph which also returns a GlyphKey, perhaps
@@ -166,6 +677,325 @@ fn position_glyph(glyph: &LayoutGlyph) -> RunGlyph {
             glyph.glyph_id,
             glyph.font_size,
             TextWeight(glyph.font_weight.0),
-        ),
+        )
+        // The face cosmic-text selected may not match the style, the rasterizer takes care of that.
+        .with_style(attributes.style)
+        .with_stretch(attributes.stretch),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use cosmic_text::fontdb;
+
+    use super::*;
+
+    fn font_system() -> FontSystem {
+        let fonts = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/shared/src/fonts");
+        let mut db = fontdb::Database::new();
+        db.load_font_file(fonts.join("Montserrat/Montserrat-Regular.ttf"))
+            .unwrap();
+        db.load_font_file(fonts.join("JetBrainsMono-2.304/fonts/variable/JetBrainsMono[wght].ttf"))
+            .unwrap();
+        FontSystem::new_with_locale_and_db("en-US".into(), db)
+    }
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog";
+
+    fn montserrat(text: &str) -> TextShaper<'_> {
+        TextShaper::new(text).with_default_attributes(
+            TextAttributes::default().with_family(Family::Name("Montserrat")),
+        )
+    }
+
+    fn ellipsis_glyph(font_system: &mut FontSystem, run: &GlyphRun) -> u16 {
+        let font_id = run.glyphs[0].key.font_id;
+        let font = font_system.get_font(font_id, Weight::NORMAL).unwrap();
+        font.as_swash().charmap().map(ELLIPSIS)
+    }
+
+    fn ends_with_ellipsis(font_system: &mut FontSystem, run: &GlyphRun) -> bool {
+        let ellipsis = ellipsis_glyph(font_system, run);
+        run.glyphs.last().unwrap().key.glyph_id == ellipsis
+    }
+
+    #[test]
+    fn lines_wrap_at_max_width() {
+        let mut font_system = font_system();
+        let unwrapped =
+            montserrat(TEXT).layout_paragraph(&mut font_system, 16.0, &ParagraphStyle::default());
+        let paragraph = montserrat(TEXT).layout_paragraph(
+            &mut font_system,
+            16.0,
+            &ParagraphStyle::default().with_max_width(100.0),
+        );
+
+        assert_eq!(unwrapped.runs.len(), 1);
+        assert!(paragraph.runs.len() > 1);
+        assert!(paragraph.runs.iter().all(|run| run.metrics.width <= 100));
+        assert!(paragraph.size.width <= 100);
+        let glyphs: usize = paragraph.runs.iter().map(|run| run.glyphs.len()).sum();
+        // Wrapping drops the whitespace at the line breaks only.
+        assert!(glyphs <= unwrapped.runs[0].glyphs.len());
+        assert!(glyphs > unwrapped.runs[0].glyphs.len() - paragraph.runs.len());
+    }
+
+    #[test]
+    fn lines_are_translated_by_the_line_height() {
+        let mut font_system = font_system();
+        let style = ParagraphStyle {
+            line_height: 1.5,
+            ..Default::default()
+        };
+
+        let paragraph = montserrat("Ag\n\nAg").layout_paragraph(&mut font_system, 20.0, &style);
+
+        assert_eq!(paragraph.runs.len(), 3);
+        // The empty line takes its line height, but has no glyphs.
+        assert!(paragraph.runs[1].glyphs.is_empty());
+        let (first, last) = (&paragraph.runs[0], &paragraph.runs[2]);
+        assert_eq!(last.translation.y - first.translation.y, 60.0);
+        assert_eq!(paragraph.size.height, 90);
+        assert!(first.metrics.max_ascent > 0 && first.metrics.max_descent > 0);
+        assert_eq!(last.metrics, first.metrics);
+        assert_eq!(paragraph.size.width, first.metrics.width);
+    }
+
+    #[test]
+    fn wrapped_lines_beyond_max_lines_are_ellipsized() {
+        let mut font_system = font_system();
+        let style = ParagraphStyle::default()
+            .with_max_width(100.0)
+            .with_max_lines(2);
+
+        let paragraph = montserrat(TEXT).layout_paragraph(&mut font_system, 16.0, &style);
+
+        assert_eq!(paragraph.runs.len(), 2);
+        assert!(!ends_with_ellipsis(&mut font_system, &paragraph.runs[0]));
+        assert!(ends_with_ellipsis(&mut font_system, &paragraph.runs[1]));
+    }
+
+    #[test]
+    fn lines_cut_at_a_line_ending_are_ellipsized() {
+        let mut font_system = font_system();
+        let style = ParagraphStyle::default().with_max_lines(2);
+
+        let cut =
+            montserrat("First\nSecond\nThird").layout_paragraph(&mut font_system, 16.0, &style);
+        let complete = montserrat("First\nSecond").layout_paragraph(&mut font_system, 16.0, &style);
+
+        assert_eq!(cut.runs.len(), 2);
+        assert!(ends_with_ellipsis(&mut font_system, &cut.runs[1]));
+        assert_eq!(complete.runs.len(), 2);
+        assert!(!ends_with_ellipsis(&mut font_system, &complete.runs[1]));
+    }
+
+    #[test]
+    fn ranges_set_their_font_size_and_color() {
+        let mut font_system = font_system();
+        let mut shaper = montserrat("small BIG");
+        shaper.add_range_attributes(
+            6..9,
+            TextAttributes::default()
+                .with_family(Family::Name("Montserrat"))
+                .with_font_size(32.0)
+                .with_color(Color::WHITE),
+        );
+
+        let paragraph = shaper.layout_paragraph(&mut font_system, 16.0, &ParagraphStyle::default());
+
+        // One line, split where the color changes.
+        let [small, big] = &paragraph.runs[..] else {
+            panic!("Expected two runs: {:?}", paragraph.runs);
+        };
+        assert_eq!(small.translation, big.translation);
+        assert_eq!(
+            (small.text_color, big.text_color),
+            (Color::BLACK, Color::WHITE)
+        );
+        assert!(
+            small
+                .glyphs
+                .iter()
+                .all(|g| g.key.font_size_bits == 16f32.to_bits())
+        );
+        assert!(
+            big.glyphs
+                .iter()
+                .all(|g| g.key.font_size_bits == 32f32.to_bits())
+        );
+        // The line takes the line height of the largest font size.
+        assert_eq!(
+            paragraph.size.height,
+            (32.0f32 * DEFAULT_LINE_HEIGHT).ceil() as u32
+        );
+    }
+
+    fn decorated(font_system: &mut FontSystem, text: &str, range: Range<usize>) -> GlyphRun {
+        let mut shaper = montserrat(text);
+        shaper.add_range_attributes(
+            range,
+            TextAttributes::default()
+                .with_family(Family::Name("Montserrat"))
+                .with_underline(Color::WHITE)
+                .with_strikethrough(Color::WHITE)
+                .with_background(Color::WHITE),
+        );
+        shaper.layout(font_system, 20.0).unwrap()
+    }
+
+    fn decoration(run: &GlyphRun, kind: DecorationKind) -> &RunDecoration {
+        let mut decorations = run.decorations().iter().filter(|d| d.kind == kind);
+        let decoration = decorations.next().unwrap();
+        assert!(decorations.next().is_none());
+        decoration
+    }
+
+    #[test]
+    fn decorations_cover_their_range() {
+        let mut font_system = font_system();
+        let whole = decorated(&mut font_system, "Hello", 0..5);
+        let run = decorated(&mut font_system, "Hello world", 6..11);
+        let w = &run.glyphs[6];
+
+        for kind in [
+            DecorationKind::Background,
+            DecorationKind::Underline,
+            DecorationKind::Strikethrough,
+        ] {
+            let span = decoration(&run, kind);
+            // Spans are widened to whole pixels.
+            assert!((0..=1).contains(&(w.pos.0 - span.pos.0)), "{kind:?}");
+            assert_eq!(span.pos.0 + span.size.0 as i32, run.metrics.width as i32);
+            assert_eq!(decoration(&whole, kind).pos.0, 0);
+        }
+    }
+
+    #[test]
+    fn decorations_are_placed_with_the_font_metrics() {
+        let mut font_system = font_system();
+        let run = decorated(&mut font_system, "Hello", 0..5);
+        let font = font_system
+            .get_font(run.glyphs[0].key.font_id, Weight::NORMAL)
+            .unwrap();
+        let font_metrics = font.as_swash().metrics(&[]).scale(20.0);
+        let baseline = run.metrics.max_ascent as i32;
+        let line_height = run.metrics.max_ascent + run.metrics.max_descent;
+
+        let background = decoration(&run, DecorationKind::Background);
+        assert_eq!((background.pos.1, background.size.1), (0, line_height));
+
+        let underline = decoration(&run, DecorationKind::Underline);
+        assert_eq!(
+            underline.pos.1,
+            (baseline as f32 - font_metrics.underline_offset).round() as i32
+        );
+        assert_eq!(
+            underline.size.1,
+            font_metrics.stroke_size.max(1.0).ceil() as u32
+        );
+        assert!(underline.pos.1 > baseline);
+
+        let strikethrough = decoration(&run, DecorationKind::Strikethrough);
+        assert_eq!(
+            strikethrough.pos.1,
+            (baseline as f32 - font_metrics.strikeout_offset).round() as i32
+        );
+        assert!(strikethrough.size.1 >= 1);
+        assert!((0..baseline).contains(&strikethrough.pos.1));
+    }
+
+    #[test]
+    fn right_to_left_spans_cover_their_glyphs() {
+        let mut font_system = font_system();
+        // Montserrat has no Hebrew glyphs, but the text is still laid out from right to left.
+        let text = "ab \u{5d0}\u{5d1}\u{5d2}";
+
+        let run = decorated(&mut font_system, text, 3..text.len());
+
+        let hebrew: Vec<_> = run.glyphs.iter().filter(|g| g.key.glyph_id == 0).collect();
+        assert_eq!(hebrew.len(), 3);
+        let left = hebrew.iter().map(|g| g.pos.0).min().unwrap();
+        let background = decoration(&run, DecorationKind::Background);
+        assert!((0..=1).contains(&(left - background.pos.0)));
+        assert_eq!(
+            background.pos.0 + background.size.0 as i32,
+            run.metrics.width as i32
+        );
+    }
+
+    #[test]
+    fn style_and_stretch_reach_the_glyph_keys() {
+        let mut font_system = font_system();
+        let mut shaper = montserrat("ab");
+        shaper.add_range_attributes(
+            1..2,
+            TextAttributes::default()
+                .with_family(Family::Name("Montserrat"))
+                .with_style(TextStyle::Italic)
+                .with_stretch(TextStretch(75)),
+        );
+
+        let run = shaper.layout(&mut font_system, 16.0).unwrap();
+
+        let keys: Vec<_> = run
+            .glyphs
+            .iter()
+            .map(|glyph| (glyph.key.style, glyph.key.stretch))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                (TextStyle::Normal, TextStretch::default()),
+                (TextStyle::Italic, TextStretch(75))
+            ]
+        );
+    }
+
+    #[test]
+    fn stretches_map_to_the_nearest_wider_width_class() {
+        let cases = [
+            (0, Stretch::UltraCondensed),
+            (50, Stretch::UltraCondensed),
+            (51, Stretch::ExtraCondensed),
+            (62, Stretch::ExtraCondensed),
+            (63, Stretch::Condensed),
+            (75, Stretch::Condensed),
+            (76, Stretch::SemiCondensed),
+            (87, Stretch::SemiCondensed),
+            (88, Stretch::Normal),
+            (100, Stretch::Normal),
+            (101, Stretch::SemiExpanded),
+            (112, Stretch::SemiExpanded),
+            (113, Stretch::Expanded),
+            (125, Stretch::Expanded),
+            (126, Stretch::ExtraExpanded),
+            (150, Stretch::ExtraExpanded),
+            (151, Stretch::UltraExpanded),
+            (200, Stretch::UltraExpanded),
+        ];
+        for (percent, stretch) in cases {
+            assert_eq!(Stretch::from(TextStretch(percent)), stretch, "{percent}%");
+        }
+        assert_eq!(Stretch::from(TextStretch::default()), Stretch::Normal);
+    }
+
+    #[test]
+    fn glyphs_no_font_has_are_reported() {
+        let mut font_system = font_system();
+        let text = "A\u{4e2d} \u{4e2e}\n";
+
+        let (run, missing) = TextShaper::new(text)
+            .with_default_attributes(
+                TextAttributes::default().with_family(Family::Name("Montserrat")),
+            )
+            .layout_with_missing_glyphs(&mut font_system, 16.0);
+
+        assert!(run.is_some());
+        // Whitespace and the line ending are not reported.
+        let missing: Vec<_> = missing.into_iter().map(|glyph| glyph.range).collect();
+        assert_eq!(missing, [1..4, 5..8]);
+    }
+}
@@ -20,6 +20,7 @@ pub struct WindowRendererBuilder {
     shapes: bool,
     text: Option<FontManager>,
     measurements: bool,
+    srgb_surface: bool,
 }
 
 impl WindowRendererBuilder {
@@ -34,6 +35,7 @@ impl WindowRendererBuilder {
             shapes: false,
             text: None,
             measurements: false,
+            srgb_surface: true,
         }
     }
 
@@ -57,7 +59,7 @@ impl WindowRendererBuilder {
         self
     }
 
-    /// Adds support for shape rendering.
+    /// Adds support for shape and path rendering.
     ///
     /// By default, no shape rendering is available.
     pub fn with_shapes(mut self) -> Self {
@@ -81,6 +83,14 @@ impl WindowRendererBuilder {
         self
     }
 
+    /// Render into an sRGB surface, so that colors are displayed and blended correctly.
+    ///
+    /// Default is on. Turn it off only for surfaces that expect linear colors.
+    pub fn with_srgb_surface(mut self, srgb_surface: bool) -> Self {
+        self.srgb_surface = srgb_surface;
+        self
+    }
+
     pub async fn build(self) -> Result<AsyncWindowRenderer> {
         let instance_and_surface = self
             .window
@@ -109,7 +119,7 @@ impl WindowRendererBuilder {
         .await;
         let (instance, surface) = instance_and_surface?;
 
-        let device = RenderDevice::for_surface(instance, &surface).await?;
+        let device = RenderDevice::for_surface(instance, &surface, self.srgb_surface).await?;
 
         let initial_size = self
             .initial_size